@@ -8,16 +8,34 @@ use rand::{
         Distribution, Standard, WeightedIndex,
     },
     seq::SliceRandom,
-    Rng,
+    Rng, SeedableRng,
 };
 use rand_distr::{num_traits::Float, uniform::SampleBorrow, Normal, StandardNormal};
 use std::fmt::Debug;
 
-/// commonly used random functions encapsulated from rand library to prevent unexpected panics.
+// commonly used random functions encapsulated from rand library to prevent unexpected panics.
+// every function has a `_with_rng` variant which draws from the given generator instead of `thread_rng`,
+// so that callers can share one deterministic stream (replays, lockstep simulation).
+
+// 可复现的随机数生成器, 相同种子产生相同序列, 可通过 word_pos 保存和恢复进度
+pub type SeededRng = rand_chacha::ChaCha12Rng;
+
+#[inline]
+pub fn seeded_rng(seed: u64) -> SeededRng {
+    SeededRng::seed_from_u64(seed)
+}
 
 #[inline]
 // 随机值
 pub fn random_value<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    random_value_with_rng(&mut rand::thread_rng())
+}
+
+#[inline]
+pub fn random_value_with_rng<T, G: Rng + ?Sized>(rng: &mut G) -> T
 where
     Standard: Distribution<T>,
 {
@@ -25,12 +43,21 @@ where
     // random value in [0.0, 1.0) for f32 and f64
     // random true or false for bool
     // random character for char
-    rand::thread_rng().gen()
+    rng.gen()
 }
 
 #[inline]
 // 范围内随机值
 pub fn random_value_in_range<T, R>(range: R) -> T
+where
+    T: Default + SampleUniform,
+    R: Debug + SampleRange<T>,
+{
+    random_value_in_range_with_rng(&mut rand::thread_rng(), range)
+}
+
+#[inline]
+pub fn random_value_in_range_with_rng<T, R, G: Rng + ?Sized>(rng: &mut G, range: R) -> T
 where
     T: Default + SampleUniform,
     R: Debug + SampleRange<T>,
@@ -38,62 +65,100 @@ where
     if range.is_empty() {
         T::default()
     } else {
-        rand::thread_rng().gen_range(range)
+        rng.gen_range(range)
     }
 }
 
 #[inline]
 // 按概率随机测试
 pub fn random_pass<F: Float>(probability: F) -> bool {
+    random_pass_with_rng(&mut rand::thread_rng(), probability)
+}
+
+#[inline]
+pub fn random_pass_with_rng<F: Float, G: Rng + ?Sized>(rng: &mut G, probability: F) -> bool {
     match probability {
         p if !p.is_normal() || p.is_sign_negative() => false,
         p if p >= F::one() => true,
-        p => p.to_f64().map_or(false, |p| rand::thread_rng().gen_bool(p)),
+        p => p.to_f64().map_or(false, |p| rng.gen_bool(p)),
     }
 }
 
 #[inline]
 // 按比率随机测试
 pub fn random_pass_by_ratio(numerator: u32, denominator: u32) -> bool {
+    random_pass_by_ratio_with_rng(&mut rand::thread_rng(), numerator, denominator)
+}
+
+#[inline]
+pub fn random_pass_by_ratio_with_rng<G: Rng + ?Sized>(
+    rng: &mut G,
+    numerator: u32,
+    denominator: u32,
+) -> bool {
     if denominator == 0 {
         false
     } else if numerator > denominator {
         true
     } else {
-        rand::thread_rng().gen_ratio(numerator, denominator)
+        rng.gen_ratio(numerator, denominator)
     }
 }
 
 #[inline]
 // 随机字符
 pub fn random_char() -> char {
+    random_char_with_rng(&mut rand::thread_rng())
+}
+
+#[inline]
+pub fn random_char_with_rng<G: Rng + ?Sized>(rng: &mut G) -> char {
     const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
                              abcdefghijklmnopqrstuvwxyz\
                              0123456789";
-    CHARSET[rand::thread_rng().gen_range(0..CHARSET.len())] as char
+    CHARSET[rng.gen_range(0..CHARSET.len())] as char
 }
 
 #[inline]
 // 随机字符串
 pub fn random_string(len: usize) -> String {
-    (0..len).map(|_| random_char()).collect()
+    random_string_with_rng(&mut rand::thread_rng(), len)
+}
+
+#[inline]
+pub fn random_string_with_rng<G: Rng + ?Sized>(rng: &mut G, len: usize) -> String {
+    (0..len).map(|_| random_char_with_rng(rng)).collect()
 }
 
 #[inline]
 // 随机洗牌
 pub fn random_shuffle<T>(items: &mut [T]) {
-    items.shuffle(&mut rand::thread_rng())
+    random_shuffle_with_rng(&mut rand::thread_rng(), items)
+}
+
+#[inline]
+pub fn random_shuffle_with_rng<T, G: Rng + ?Sized>(rng: &mut G, items: &mut [T]) {
+    items.shuffle(rng)
 }
 
 #[inline]
 // 正态分布, 均值为 mean, 标准差为 std_dev
 pub fn random_normal<F>(mean: F, std_dev: F) -> F
+where
+    F: Float + Default,
+    StandardNormal: Distribution<F>,
+{
+    random_normal_with_rng(&mut rand::thread_rng(), mean, std_dev)
+}
+
+#[inline]
+pub fn random_normal_with_rng<F, G: Rng + ?Sized>(rng: &mut G, mean: F, std_dev: F) -> F
 where
     F: Float + Default,
     StandardNormal: Distribution<F>,
 {
     match Normal::new(mean, std_dev) {
-        Ok(normal) => normal.sample(&mut rand::thread_rng()),
+        Ok(normal) => normal.sample(rng),
         Err(_) => F::default(),
     }
 }
@@ -122,17 +187,27 @@ pub fn random_point_in_rect<F: Float + SampleUniform>(
     pos: Vec3,
     half_length: F,
     half_width: F,
+) -> Vec3 {
+    random_point_in_rect_with_rng(&mut rand::thread_rng(), pos, half_length, half_width)
+}
+
+#[inline]
+pub fn random_point_in_rect_with_rng<F: Float + SampleUniform, G: Rng + ?Sized>(
+    rng: &mut G,
+    pos: Vec3,
+    half_length: F,
+    half_width: F,
 ) -> Vec3 {
     if (half_length.is_normal() || half_length.is_zero())
         && (half_width.is_normal() || half_width.is_zero())
         && half_length.is_sign_positive()
         && half_width.is_sign_positive()
     {
-        let x_offset = rand::thread_rng()
+        let x_offset = rng
             .gen_range(-half_length..=half_length)
             .to_f32()
             .unwrap_or(0.0);
-        let z_offset = rand::thread_rng()
+        let z_offset = rng
             .gen_range(-half_width..=half_width)
             .to_f32()
             .unwrap_or(0.0);
@@ -147,6 +222,16 @@ pub fn random_point_on_rect<F: Float + SampleUniform>(
     pos: Vec3,
     half_width: F,
     half_height: F,
+) -> Vec3 {
+    random_point_on_rect_with_rng(&mut rand::thread_rng(), pos, half_width, half_height)
+}
+
+#[inline]
+pub fn random_point_on_rect_with_rng<F: Float + SampleUniform, G: Rng + ?Sized>(
+    rng: &mut G,
+    pos: Vec3,
+    half_width: F,
+    half_height: F,
 ) -> Vec3 {
     if half_width.is_normal()
         && half_height.is_normal()
@@ -158,7 +243,7 @@ pub fn random_point_on_rect<F: Float + SampleUniform>(
         let heigh = two * half_height;
         // expand the sides of the rectangle into line to randomly generate points.
         let line = two * (width + heigh);
-        let num = rand::thread_rng().gen_range(F::zero()..line);
+        let num = rng.gen_range(F::zero()..line);
         let x_offset = match num {
             num if num < width => num - half_width,
             num if num < width + heigh => half_width,
@@ -186,12 +271,30 @@ pub fn random_point_in_square<F: Float + SampleUniform>(pos: Vec3, half_side: F)
     random_point_in_rect(pos, half_side, half_side)
 }
 
+#[inline]
+pub fn random_point_in_square_with_rng<F: Float + SampleUniform, G: Rng + ?Sized>(
+    rng: &mut G,
+    pos: Vec3,
+    half_side: F,
+) -> Vec3 {
+    random_point_in_rect_with_rng(rng, pos, half_side, half_side)
+}
+
 #[inline]
 // 正方形上随机点
 pub fn random_point_on_square<F: Float + SampleUniform>(pos: Vec3, half_side: F) -> Vec3 {
     random_point_on_rect(pos, half_side, half_side)
 }
 
+#[inline]
+pub fn random_point_on_square_with_rng<F: Float + SampleUniform, G: Rng + ?Sized>(
+    rng: &mut G,
+    pos: Vec3,
+    half_side: F,
+) -> Vec3 {
+    random_point_on_rect_with_rng(rng, pos, half_side, half_side)
+}
+
 // #[inline]
 // 长方体内随机点
 // pub fn random_point_in_cuboid<F: Float + SampleUniform>(
@@ -243,6 +346,16 @@ pub fn random_point_in_annulus<F: Float + SampleUniform>(
     pos: Vec3,
     inner_radius: F,
     outer_radius: F,
+) -> Vec3 {
+    random_point_in_annulus_with_rng(&mut rand::thread_rng(), pos, inner_radius, outer_radius)
+}
+
+#[inline]
+pub fn random_point_in_annulus_with_rng<F: Float + SampleUniform, G: Rng + ?Sized>(
+    rng: &mut G,
+    pos: Vec3,
+    inner_radius: F,
+    outer_radius: F,
 ) -> Vec3 {
     if inner_radius.is_normal()
         && outer_radius.is_normal()
@@ -251,7 +364,6 @@ pub fn random_point_in_annulus<F: Float + SampleUniform>(
         && inner_radius < outer_radius
     {
         let tau = F::from(2.0 * std::f64::consts::PI).unwrap();
-        let mut rng = rand::thread_rng();
         // generates a random angle between [0, 2 * PI).
         let theta = rng.gen_range(F::zero()..tau);
         // generates a random radius within the range [inner_radius^2, outer_radius^2) and sqrt() to ensure an even distribution within the annulus.
@@ -266,11 +378,19 @@ pub fn random_point_in_annulus<F: Float + SampleUniform>(
 #[inline]
 // 圆内随机点
 pub fn random_point_in_circle<F: Float + SampleUniform>(pos: &Vec3, radius: F) -> Vec3 {
+    random_point_in_circle_with_rng(&mut rand::thread_rng(), pos, radius)
+}
+
+#[inline]
+pub fn random_point_in_circle_with_rng<F: Float + SampleUniform, G: Rng + ?Sized>(
+    rng: &mut G,
+    pos: &Vec3,
+    radius: F,
+) -> Vec3 {
     // can't use random_point_in_annulus directly because it doesn't deal subnormal inner_radius such as zero.
     // random_point_in_annulus(pos, 0.0, radius)
     if radius.is_normal() && radius.is_sign_positive() {
         let tau = F::from(2.0 * std::f64::consts::PI).unwrap();
-        let mut rng = rand::thread_rng();
         // generates a random angle between [0, 2 * PI).
         let theta = rng.gen_range(F::zero()..tau);
         // generates a random radius within the range [0, radius) and sqrt() is done to ensure an even distribution within the circle.
@@ -285,11 +405,19 @@ pub fn random_point_in_circle<F: Float + SampleUniform>(pos: &Vec3, radius: F) -
 #[inline]
 // 圆上随机点
 pub fn random_point_on_circle<F: Float + SampleUniform>(pos: Vec3, radius: F) -> Vec3 {
+    random_point_on_circle_with_rng(&mut rand::thread_rng(), pos, radius)
+}
+
+#[inline]
+pub fn random_point_on_circle_with_rng<F: Float + SampleUniform, G: Rng + ?Sized>(
+    rng: &mut G,
+    pos: Vec3,
+    radius: F,
+) -> Vec3 {
     // use random_point_in_annulus directly will be slower
     // random_point_in_annulus(pos, radius, radius)
     if radius.is_normal() && radius.is_sign_positive() {
         let tau = F::from(2.0 * std::f64::consts::PI).unwrap();
-        let mut rng = rand::thread_rng();
         let theta = rng.gen_range(F::zero()..tau);
         let x_offset = (radius * theta.cos()).to_f32().unwrap_or(0.0);
         let z_offset = (radius * theta.sin()).to_f32().unwrap_or(0.0);
@@ -301,11 +429,19 @@ pub fn random_point_on_circle<F: Float + SampleUniform>(pos: Vec3, radius: F) ->
 #[inline]
 // 球内随机点
 pub fn random_point_in_sphere<F: Float + SampleUniform>(pos: &Vec3, radius: F) -> Vec3 {
+    random_point_in_sphere_with_rng(&mut rand::thread_rng(), pos, radius)
+}
+
+#[inline]
+pub fn random_point_in_sphere_with_rng<F: Float + SampleUniform, G: Rng + ?Sized>(
+    rng: &mut G,
+    pos: &Vec3,
+    radius: F,
+) -> Vec3 {
     if radius.is_normal() && radius.is_sign_positive() {
         let pi = F::from(std::f64::consts::PI).unwrap();
         let tau = F::from(2.0 * std::f64::consts::PI).unwrap();
         let cb = F::from(1.0f64 / 3.0f64).unwrap();
-        let mut rng = rand::thread_rng();
 
         let u = rng.gen_range(F::zero()..F::one());
         // generate two random angles, theta is the horizontal angle, phi is the vertical angle.
@@ -362,16 +498,30 @@ pub fn random_point_in_sphere<F: Float + SampleUniform>(pos: &Vec3, radius: F) -
 #[inline]
 // 等概率抽取一个下标
 pub fn average_select_index(len: usize) -> usize {
+    average_select_index_with_rng(&mut rand::thread_rng(), len)
+}
+
+#[inline]
+pub fn average_select_index_with_rng<G: Rng + ?Sized>(rng: &mut G, len: usize) -> usize {
     assert!(len > 0, "average_select_index, len must > 0");
-    rand::thread_rng().gen_range(0..len)
+    rng.gen_range(0..len)
 }
 
 #[inline]
 // 等概率不重复抽取n个下标
-pub fn average_select_indexs(len: usize, mut select_num: usize) -> Vec<usize> {
+pub fn average_select_indexs(len: usize, select_num: usize) -> Vec<usize> {
+    average_select_indexs_with_rng(&mut rand::thread_rng(), len, select_num)
+}
+
+#[inline]
+pub fn average_select_indexs_with_rng<G: Rng + ?Sized>(
+    rng: &mut G,
+    len: usize,
+    mut select_num: usize,
+) -> Vec<usize> {
     // index::sample panics if select_num > len
     select_num = std::cmp::min(len, select_num);
-    index::sample(&mut rand::thread_rng(), len, select_num).into_vec()
+    index::sample(rng, len, select_num).into_vec()
 }
 
 // #[inline]
@@ -411,11 +561,20 @@ pub fn average_select_indexs(len: usize, mut select_num: usize) -> Vec<usize> {
 #[inline]
 // 等概率重复抽取n个下标
 pub fn average_repeated_select_indexs(len: usize, select_num: usize) -> Vec<usize> {
+    average_repeated_select_indexs_with_rng(&mut rand::thread_rng(), len, select_num)
+}
+
+#[inline]
+pub fn average_repeated_select_indexs_with_rng<G: Rng + ?Sized>(
+    rng: &mut G,
+    len: usize,
+    select_num: usize,
+) -> Vec<usize> {
     if len == 0 {
         Vec::new()
     } else {
         (0..select_num)
-            .map(|_| rand::thread_rng().gen_range(0..len))
+            .map(|_| rng.gen_range(0..len))
             .collect::<Vec<_>>()
     }
 }
@@ -423,56 +582,96 @@ pub fn average_repeated_select_indexs(len: usize, select_num: usize) -> Vec<usiz
 #[inline]
 // 等概率抽取一项, 返回引用
 pub fn average_select_item<T>(items: &[T]) -> &T {
+    average_select_item_with_rng(&mut rand::thread_rng(), items)
+}
+
+#[inline]
+pub fn average_select_item_with_rng<'a, T, G: Rng + ?Sized>(rng: &mut G, items: &'a [T]) -> &'a T {
     assert!(items.len() > 0, "average_select_item, items.len() must > 0");
-    &items[rand::thread_rng().gen_range(0..items.len())]
+    &items[rng.gen_range(0..items.len())]
 }
 
 #[inline]
 // 等概率抽取一项, 返回值
 pub fn average_collect_item<T: Clone>(items: &[T]) -> T {
+    average_collect_item_with_rng(&mut rand::thread_rng(), items)
+}
+
+#[inline]
+pub fn average_collect_item_with_rng<T: Clone, G: Rng + ?Sized>(rng: &mut G, items: &[T]) -> T {
     assert!(
         items.len() > 0,
         "average_collect_item, items.len() must > 0"
     );
-    items[rand::thread_rng().gen_range(0..items.len())].clone()
+    items[rng.gen_range(0..items.len())].clone()
 }
 
 // 等概率不重复抽取n项, 返回引用
 #[inline]
 pub fn average_select_items<T>(items: &[T], select_num: usize) -> Vec<&T> {
-    items
-        .choose_multiple(&mut rand::thread_rng(), select_num)
-        .collect()
+    average_select_items_with_rng(&mut rand::thread_rng(), items, select_num)
+}
+
+#[inline]
+pub fn average_select_items_with_rng<'a, T, G: Rng + ?Sized>(
+    rng: &mut G,
+    items: &'a [T],
+    select_num: usize,
+) -> Vec<&'a T> {
+    items.choose_multiple(rng, select_num).collect()
 }
 
 // 等概率不重复抽取n项, 返回值
 #[inline]
 pub fn average_collect_items<T: Clone>(items: &[T], collect_num: usize) -> Vec<T> {
-    items
-        .choose_multiple(&mut rand::thread_rng(), collect_num)
-        .cloned()
-        .collect()
+    average_collect_items_with_rng(&mut rand::thread_rng(), items, collect_num)
+}
+
+#[inline]
+pub fn average_collect_items_with_rng<T: Clone, G: Rng + ?Sized>(
+    rng: &mut G,
+    items: &[T],
+    collect_num: usize,
+) -> Vec<T> {
+    items.choose_multiple(rng, collect_num).cloned().collect()
 }
 
 #[inline]
 // 等概率重复抽取n项, 返回引用
 pub fn average_repeated_select_items<T>(items: &[T], select_num: usize) -> Vec<&T> {
+    average_repeated_select_items_with_rng(&mut rand::thread_rng(), items, select_num)
+}
+
+#[inline]
+pub fn average_repeated_select_items_with_rng<'a, T, G: Rng + ?Sized>(
+    rng: &mut G,
+    items: &'a [T],
+    select_num: usize,
+) -> Vec<&'a T> {
     if items.is_empty() {
         Vec::new()
     } else {
         (0..select_num)
-            .map(|_| &items[rand::thread_rng().gen_range(0..items.len())])
+            .map(|_| &items[rng.gen_range(0..items.len())])
             .collect::<Vec<_>>()
     }
 }
 
 // 等概率重复抽取n项, 返回值
 pub fn average_repeated_collect_items<T: Clone>(items: &[T], select_num: usize) -> Vec<T> {
+    average_repeated_collect_items_with_rng(&mut rand::thread_rng(), items, select_num)
+}
+
+pub fn average_repeated_collect_items_with_rng<T: Clone, G: Rng + ?Sized>(
+    rng: &mut G,
+    items: &[T],
+    select_num: usize,
+) -> Vec<T> {
     if items.is_empty() {
         Vec::new()
     } else {
         (0..select_num)
-            .map(|_| items[rand::thread_rng().gen_range(0..items.len())].clone())
+            .map(|_| items[rng.gen_range(0..items.len())].clone())
             .collect::<Vec<_>>()
     }
 }
@@ -493,6 +692,19 @@ pub fn average_repeated_collect_items<T: Clone>(items: &[T], select_num: usize)
 #[inline]
 // 按权重抽取一个下标
 pub fn weight_select_index<F, X>(weights: &[F]) -> Option<usize>
+where
+    F: Float + SampleBorrow<X>,
+    for<'c> &'c F: SampleBorrow<X>,
+    X: for<'a> ::core::ops::AddAssign<&'a X> + Clone + Default + SampleUniform + PartialOrd,
+{
+    weight_select_index_with_rng(&mut rand::thread_rng(), weights)
+}
+
+#[inline]
+pub fn weight_select_index_with_rng<F, X, G: Rng + ?Sized>(
+    rng: &mut G,
+    weights: &[F],
+) -> Option<usize>
 where
     F: Float + SampleBorrow<X>,
     for<'c> &'c F: SampleBorrow<X>,
@@ -501,7 +713,7 @@ where
     // returns an error if the weights is empty, if any weight is `< 0`, or if its total value is 0.
     WeightedIndex::new(weights)
         .ok()
-        .map(|dist| dist.sample(&mut rand::thread_rng()))
+        .map(|dist| dist.sample(rng))
 }
 
 #[inline]
@@ -512,16 +724,42 @@ where
     for<'c> &'c F: SampleBorrow<X>,
     X: for<'a> ::core::ops::AddAssign<&'a X> + Clone + Default + SampleUniform + PartialOrd,
 {
-    WeightedIndex::new(weights).ok().map(|dist| {
-        dist.sample_iter(&mut rand::thread_rng())
-            .take(select_num)
-            .collect()
-    })
+    weight_select_indexs_with_rng(&mut rand::thread_rng(), weights, select_num)
+}
+
+#[inline]
+pub fn weight_select_indexs_with_rng<F, X, G: Rng + ?Sized>(
+    rng: &mut G,
+    weights: &[F],
+    select_num: usize,
+) -> Option<Vec<usize>>
+where
+    F: Float + SampleBorrow<X>,
+    for<'c> &'c F: SampleBorrow<X>,
+    X: for<'a> ::core::ops::AddAssign<&'a X> + Clone + Default + SampleUniform + PartialOrd,
+{
+    WeightedIndex::new(weights)
+        .ok()
+        .map(|dist| dist.sample_iter(rng).take(select_num).collect())
 }
 
 #[inline]
 // 按权重比例重复抽取n个下标
 pub fn weight_repeated_select_indexs<F, X>(weights: &[F], select_num: usize) -> Option<Vec<usize>>
+where
+    F: Float + SampleBorrow<X>,
+    for<'c> &'c F: SampleBorrow<X>,
+    X: for<'a> ::core::ops::AddAssign<&'a X> + Clone + Default + SampleUniform + PartialOrd,
+{
+    weight_repeated_select_indexs_with_rng(&mut rand::thread_rng(), weights, select_num)
+}
+
+#[inline]
+pub fn weight_repeated_select_indexs_with_rng<F, X, G: Rng + ?Sized>(
+    rng: &mut G,
+    weights: &[F],
+    select_num: usize,
+) -> Option<Vec<usize>>
 where
     F: Float + SampleBorrow<X>,
     for<'c> &'c F: SampleBorrow<X>,
@@ -529,7 +767,7 @@ where
 {
     WeightedIndex::new(weights).ok().map(|dist| {
         (0..select_num)
-            .map(|_| dist.sample(&mut rand::thread_rng()))
+            .map(|_| dist.sample(rng))
             .collect::<Vec<_>>()
     })
 }
@@ -537,19 +775,48 @@ where
 #[inline]
 // 按权重比例抽取一项, 返回引用
 pub fn weight_select_item<'a, 'b, T, F, X>(weights: &[F], items: &'b [T]) -> Option<&'b T>
+where
+    F: Float + SampleBorrow<X>,
+    for<'c> &'c F: SampleBorrow<X>,
+    X: for<'d> ::core::ops::AddAssign<&'d X> + Clone + Default + SampleUniform + PartialOrd,
+{
+    weight_select_item_with_rng(&mut rand::thread_rng(), weights, items)
+}
+
+#[inline]
+pub fn weight_select_item_with_rng<'b, T, F, X, G: Rng + ?Sized>(
+    rng: &mut G,
+    weights: &[F],
+    items: &'b [T],
+) -> Option<&'b T>
 where
     F: Float + SampleBorrow<X>,
     for<'c> &'c F: SampleBorrow<X>,
     X: for<'d> ::core::ops::AddAssign<&'d X> + Clone + Default + SampleUniform + PartialOrd,
 {
     (weights.len() == items.len())
-        .then(|| weight_select_index(weights).map(|index| &items[index]))
+        .then(|| weight_select_index_with_rng(rng, weights).map(|index| &items[index]))
         .flatten()
 }
 
 #[inline]
 // 按权重比例抽取一项, 返回值
 pub fn weight_collect_item<T, F, X>(weights: &[F], items: &[T]) -> Option<T>
+where
+    T: Clone,
+    F: Float + SampleBorrow<X>,
+    for<'c> &'c F: SampleBorrow<X>,
+    X: for<'d> ::core::ops::AddAssign<&'d X> + Clone + Default + SampleUniform + PartialOrd,
+{
+    weight_collect_item_with_rng(&mut rand::thread_rng(), weights, items)
+}
+
+#[inline]
+pub fn weight_collect_item_with_rng<T, F, X, G: Rng + ?Sized>(
+    rng: &mut G,
+    weights: &[F],
+    items: &[T],
+) -> Option<T>
 where
     T: Clone,
     F: Float + SampleBorrow<X>,
@@ -557,7 +824,7 @@ where
     X: for<'d> ::core::ops::AddAssign<&'d X> + Clone + Default + SampleUniform + PartialOrd,
 {
     (weights.len() == items.len())
-        .then(|| weight_select_index(weights).map(|index| items[index].clone()))
+        .then(|| weight_select_index_with_rng(rng, weights).map(|index| items[index].clone()))
         .flatten()
 }
 
@@ -568,6 +835,21 @@ pub fn weight_select_items<'a, 'b, T, F, X>(
     items: &'b [T],
     select_num: usize,
 ) -> Option<Vec<&'b T>>
+where
+    F: Float + SampleBorrow<X>,
+    for<'c> &'c F: SampleBorrow<X>,
+    X: for<'d> ::core::ops::AddAssign<&'d X> + Clone + Default + SampleUniform + PartialOrd,
+{
+    weight_select_items_with_rng(&mut rand::thread_rng(), weights, items, select_num)
+}
+
+#[inline]
+pub fn weight_select_items_with_rng<'b, T, F, X, G: Rng + ?Sized>(
+    rng: &mut G,
+    weights: &[F],
+    items: &'b [T],
+    select_num: usize,
+) -> Option<Vec<&'b T>>
 where
     F: Float + SampleBorrow<X>,
     for<'c> &'c F: SampleBorrow<X>,
@@ -576,7 +858,7 @@ where
     (weights.len() == items.len())
         .then(|| {
             WeightedIndex::new(weights).ok().map(|dist| {
-                dist.sample_iter(&mut rand::thread_rng())
+                dist.sample_iter(rng)
                     .take(select_num)
                     .map(|index| &items[index])
                     .collect::<Vec<&'b T>>()
@@ -592,6 +874,22 @@ pub fn weight_collect_items<T, F, X>(
     items: &[T],
     select_num: usize,
 ) -> Option<Vec<T>>
+where
+    T: Clone,
+    F: Float + SampleBorrow<X>,
+    for<'c> &'c F: SampleBorrow<X>,
+    X: for<'d> ::core::ops::AddAssign<&'d X> + Clone + Default + SampleUniform + PartialOrd,
+{
+    weight_collect_items_with_rng(&mut rand::thread_rng(), weights, items, select_num)
+}
+
+#[inline]
+pub fn weight_collect_items_with_rng<T, F, X, G: Rng + ?Sized>(
+    rng: &mut G,
+    weights: &[F],
+    items: &[T],
+    select_num: usize,
+) -> Option<Vec<T>>
 where
     T: Clone,
     F: Float + SampleBorrow<X>,
@@ -601,7 +899,7 @@ where
     (weights.len() == items.len())
         .then(|| {
             WeightedIndex::new(weights).ok().map(|dist| {
-                dist.sample_iter(&mut rand::thread_rng())
+                dist.sample_iter(rng)
                     .take(select_num)
                     .map(|index| items[index].clone())
                     .collect::<Vec<T>>()
@@ -617,6 +915,21 @@ pub fn weight_repeated_select_items<'a, 'b, T, F, X>(
     items: &'b [T],
     select_num: usize,
 ) -> Option<Vec<&'b T>>
+where
+    F: Float + SampleBorrow<X>,
+    for<'c> &'c F: SampleBorrow<X>,
+    X: for<'d> ::core::ops::AddAssign<&'d X> + Clone + Default + SampleUniform + PartialOrd,
+{
+    weight_repeated_select_items_with_rng(&mut rand::thread_rng(), weights, items, select_num)
+}
+
+#[inline]
+pub fn weight_repeated_select_items_with_rng<'b, T, F, X, G: Rng + ?Sized>(
+    rng: &mut G,
+    weights: &[F],
+    items: &'b [T],
+    select_num: usize,
+) -> Option<Vec<&'b T>>
 where
     F: Float + SampleBorrow<X>,
     for<'c> &'c F: SampleBorrow<X>,
@@ -626,7 +939,7 @@ where
         .then(|| {
             WeightedIndex::new(weights).ok().map(|dist| {
                 (0..select_num)
-                    .map(|_| &items[dist.sample(&mut rand::thread_rng())])
+                    .map(|_| &items[dist.sample(rng)])
                     .collect::<Vec<&'b T>>()
             })
         })
@@ -640,6 +953,22 @@ pub fn weight_repeated_collect_items<T, F, X>(
     items: &[T],
     select_num: usize,
 ) -> Option<Vec<T>>
+where
+    T: Clone,
+    F: Float + SampleBorrow<X>,
+    for<'c> &'c F: SampleBorrow<X>,
+    X: for<'d> ::core::ops::AddAssign<&'d X> + Clone + Default + SampleUniform + PartialOrd,
+{
+    weight_repeated_collect_items_with_rng(&mut rand::thread_rng(), weights, items, select_num)
+}
+
+#[inline]
+pub fn weight_repeated_collect_items_with_rng<T, F, X, G: Rng + ?Sized>(
+    rng: &mut G,
+    weights: &[F],
+    items: &[T],
+    select_num: usize,
+) -> Option<Vec<T>>
 where
     T: Clone,
     F: Float + SampleBorrow<X>,
@@ -650,7 +979,7 @@ where
         .then(|| {
             WeightedIndex::new(weights).ok().map(|dist| {
                 (0..select_num)
-                    .map(|_| items[dist.sample(&mut rand::thread_rng())].clone())
+                    .map(|_| items[dist.sample(rng)].clone())
                     .collect::<Vec<T>>()
             })
         })
@@ -718,6 +1047,26 @@ mod tests {
         println!("random_char={}", random_value::<char>());
     }
 
+    #[test]
+    fn test_seeded_rng() {
+        let (mut rng1, mut rng2) = (seeded_rng(7), seeded_rng(7));
+        let weights = [1.0, 2.0, 3.0, 4.0];
+        for _ in 0..100 {
+            assert_eq!(
+                random_value_with_rng::<u64, _>(&mut rng1),
+                random_value_with_rng::<u64, _>(&mut rng2)
+            );
+            assert_eq!(
+                weight_select_index_with_rng(&mut rng1, &weights),
+                weight_select_index_with_rng(&mut rng2, &weights)
+            );
+            assert_eq!(
+                random_point_in_circle_with_rng(&mut rng1, &Vec3::ZERO, 10.0),
+                random_point_in_circle_with_rng(&mut rng2, &Vec3::ZERO, 10.0)
+            );
+        }
+    }
+
     #[test]
     fn test_random_value_in_range() {
         println!("random_int32={}", random_value_in_range(1..10));
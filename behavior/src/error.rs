@@ -1,7 +1,7 @@
 use std::fmt::Debug;
 
 #[allow(dead_code)]
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct TreeLocation {
     tree_name: String,
     tree_index: i32,
@@ -18,7 +18,7 @@ impl TreeLocation {
     }
 }
 #[allow(dead_code)]
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct NodeLocation {
     tree_location: TreeLocation,
     node_name: String,
@@ -176,6 +176,80 @@ pub enum BehaviorError {
         expression: String,
         blackboard_name: String,
     },
+    ExpressionFunctionArityMismatch {
+        tree_location: TreeLocation,
+        node_index: i32,
+        expression: String,
+        function_name: String,
+        expected_arity: usize,
+        found_arity: usize,
+    },
+    ExpressionFunctionArgumentMismatch {
+        tree_location: TreeLocation,
+        node_index: i32,
+        expression: String,
+        function_name: String,
+        argument_index: usize,
+        expected_type: String,
+        found_type: String,
+    },
+    InvalidSelectWeight {
+        tree_location: TreeLocation,
+        node_index: i32,
+        weight_str: String,
+        error_info: String,
+    },
+    SwitchTypeUnsupported {
+        tree_location: TreeLocation,
+        node_index: i32,
+        blackboard_name: String,
+        blackboard_type: String,
+    },
+    InvalidSwitchCase {
+        tree_location: TreeLocation,
+        node_index: i32,
+        blackboard_type: String,
+        case_str: String,
+        error_info: String,
+    },
+    BlackBoardOperationUnsupported {
+        location: NodeLocation,
+        blackboard_name: String,
+        blackboard_type: String,
+        operation: String,
+    },
+    BlackBoardNotOption {
+        location: NodeLocation,
+        blackboard_name: String,
+        blackboard_type: String,
+    },
+    BlackBoardLiteralParseFailed {
+        location: NodeLocation,
+        blackboard_name: String,
+        blackboard_type: String,
+        literal: String,
+    },
+    SemaphoreNotFound {
+        location: NodeLocation,
+        semaphore_name: String,
+    },
+    SnapshotEncodeFailed {
+        node_index: i32,
+        error_info: String,
+    },
+    SnapshotBlackBoardUnsupported {
+        blackboard_name: String,
+        blackboard_type: String,
+    },
+    RestoreSnapshotFailed {
+        node_index: i32,
+        error_info: String,
+    },
+    RestoreBlackBoardFailed {
+        blackboard_name: String,
+        blackboard_type: String,
+        error_info: String,
+    },
 }
 
 impl core::fmt::Display for BehaviorError {
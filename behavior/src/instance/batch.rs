@@ -0,0 +1,84 @@
+use std::num::NonZeroUsize;
+
+#[inline]
+pub fn available_worker_count() -> usize {
+    std::thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+// Splits `items` into contiguous chunks, one per worker, and ticks each chunk on its own thread,
+// the first chunk on the calling thread. `create_world` builds the world of each worker, e.g. a
+// shared read-only view of the game world plus a command buffer, the worlds are returned in
+// worker order so that their commands can be applied deterministically afterwards.
+pub fn batch_tick<T, W, F, G>(
+    items: &mut [T],
+    worker_count: usize,
+    create_world: F,
+    tick: G,
+) -> Vec<W>
+where
+    T: Send,
+    W: Send,
+    F: Fn(usize) -> W + Sync,
+    G: Fn(&mut T, &mut W) + Sync,
+{
+    let worker_count = worker_count.clamp(1, items.len().max(1));
+    let chunk_len = items.len().div_ceil(worker_count).max(1);
+    let run_worker = |worker_index: usize, chunk: &mut [T]| {
+        let mut world = create_world(worker_index);
+        for item in chunk {
+            tick(item, &mut world);
+        }
+        world
+    };
+
+    let mut chunks = items.chunks_mut(chunk_len);
+    let first_chunk = chunks.next().unwrap_or_default();
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .enumerate()
+            .map(|(index, chunk)| scope.spawn(move || run_worker(index + 1, chunk)))
+            .collect();
+        let mut worlds = Vec::with_capacity(worker_count);
+        worlds.push(run_worker(0, first_chunk));
+        for handle in handles {
+            match handle.join() {
+                Ok(world) => worlds.push(world),
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+        worlds
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_tick_partitions_items() {
+        let mut items: Vec<usize> = (0..10).collect();
+        let worlds = batch_tick(
+            &mut items,
+            4,
+            |worker_index| (worker_index, Vec::new()),
+            |item, (_, commands): &mut (usize, Vec<usize>)| {
+                commands.push(*item);
+                *item *= 2;
+            },
+        );
+        assert_eq!(items, (0..10).map(|item| item * 2).collect::<Vec<_>>());
+        assert_eq!(
+            worlds,
+            vec![
+                (0, vec![0, 1, 2]),
+                (1, vec![3, 4, 5]),
+                (2, vec![6, 7, 8]),
+                (3, vec![9])
+            ]
+        );
+        assert_eq!(
+            batch_tick(&mut [] as &mut [usize], 4, |_| 0, |_, _| ()),
+            vec![0]
+        );
+    }
+}
@@ -57,12 +57,114 @@ impl<T: ConvertFromStr> ConvertFromStr for Option<T> {
 }
 
 // Based on my experience, the following base types is sufficient for blackboard
-impl_convert_for_base_type!(bool, i32, i64, f32, f64, usize);
+impl_convert_for_base_type!(bool, i32, i64, f32, f64, usize, String);
 // impl_convert_from_str!(bool, usize, i8, i16, i32, i64, i128, u8, u16, u32, u64, f32, f64);
 
 impl_convert_for_container!(Vec, VecDeque, LinkedList);
 // TODO: impl ConvertFromStr for HashSet, HashMap and BinaryHeap ?
 
+cfg_expression_node! {
+    use crate::ExpressionArgType;
+    use evalexpr::Value;
+    use num::cast::FromPrimitive;
+
+    // Blackboard values read and written by `Expression` nodes, see
+    // `BtFactory::register_expression_blackboard_type`.
+    pub trait ConvertExpressionValue: Sized {
+        // Any if it depends on the value, e.g. an Option is the empty value when None.
+        const VALUE_TYPE: ExpressionArgType;
+
+        fn to_expression_value(&self) -> Value;
+
+        fn from_expression_value(value: &Value) -> Option<Self>;
+    }
+
+    // Numbers are read as floats, so that `hp / 100` doesn't round. evalexpr doesn't take an int
+    // for the equal float, compare them with float literals like `hp == 30.0`.
+    macro_rules! impl_expression_for_number {
+        ($($number:ident),*) => {
+            $(
+                impl ConvertExpressionValue for $number {
+                    const VALUE_TYPE: ExpressionArgType = ExpressionArgType::Number;
+
+                    fn to_expression_value(&self) -> Value {
+                        Value::from_float(*self as f64)
+                    }
+
+                    fn from_expression_value(value: &Value) -> Option<Self> {
+                        value.as_number().ok().and_then($number::from_f64)
+                    }
+                }
+            )*
+        }
+    }
+
+    macro_rules! impl_expression_for_container {
+        ($($container:ident),*) => {
+            $(
+                impl<T: ConvertExpressionValue> ConvertExpressionValue for $container<T> {
+                    const VALUE_TYPE: ExpressionArgType = ExpressionArgType::Tuple;
+
+                    fn to_expression_value(&self) -> Value {
+                        Value::Tuple(self.iter().map(T::to_expression_value).collect())
+                    }
+
+                    fn from_expression_value(value: &Value) -> Option<Self> {
+                        match value {
+                            Value::Tuple(items) => items.iter().map(T::from_expression_value).collect(),
+                            Value::Empty => Some($container::new()),
+                            _ => None,
+                        }
+                    }
+                }
+            )*
+        }
+    }
+
+    impl_expression_for_number!(i32, i64, f32, f64);
+    impl_expression_for_container!(Vec, VecDeque, LinkedList);
+
+    impl ConvertExpressionValue for bool {
+        const VALUE_TYPE: ExpressionArgType = ExpressionArgType::Boolean;
+
+        fn to_expression_value(&self) -> Value {
+            Value::Boolean(*self)
+        }
+
+        fn from_expression_value(value: &Value) -> Option<Self> {
+            value.as_boolean().ok()
+        }
+    }
+
+    impl ConvertExpressionValue for String {
+        const VALUE_TYPE: ExpressionArgType = ExpressionArgType::String;
+
+        fn to_expression_value(&self) -> Value {
+            Value::String(self.clone())
+        }
+
+        fn from_expression_value(value: &Value) -> Option<Self> {
+            value.as_string().ok()
+        }
+    }
+
+    // None is the empty value `()`.
+    impl<T: ConvertExpressionValue> ConvertExpressionValue for Option<T> {
+        const VALUE_TYPE: ExpressionArgType = ExpressionArgType::Any;
+
+        fn to_expression_value(&self) -> Value {
+            self.as_ref().map_or(Value::Empty, T::to_expression_value)
+        }
+
+        fn from_expression_value(value: &Value) -> Option<Self> {
+            match value {
+                Value::Empty => Some(None),
+                value => T::from_expression_value(value).map(Some),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::ConvertFromStr;
@@ -1,37 +1,61 @@
 use super::convert::ConvertFromStr;
-use super::node::{BtNode, BtNodeGenerator, Executor};
+use super::node::{BtNode, BtNodeGenerator, Executor, FromI32};
 use super::serde::BlackBoardTemplate;
 use super::{node::BtAction, serde::TreeTemplate};
 use crate::{
-    BehaviorError, BlackBoard, BlackBoardMap, BlackBoardType, SharedBlackBoardValue, Status,
-    TreeLocation, TreeNode, TreeNodeType,
+    load_tree_state, save_tree_state, BehaviorError, BlackBoard, BlackBoardCodec, BlackBoardMap,
+    BlackBoardOps, BlackBoardType, BlackBoardValueState, EventQueue, ForEachItemFunc, MaybeSend,
+    NodeState, Semaphore, SharedBlackBoardValue, SharedClock, SharedSemaphore, Status,
+    SwitchEnumMatchFunc, SwitchEnumParseFunc, TreeLocation, TreeNode, TreeNodeType, TreeObserver,
+    WallClock,
 };
 
 cfg_tree_visualization!(
     pub use crate::{Visualization, FlattenedVisualization};
 );
 use ahash::HashMapExt;
-use behavior_util::type_name;
-use std::collections::HashMap;
+use behavior_util::{type_name, SeededRng};
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, LinkedList, VecDeque};
 use std::marker::PhantomPinned;
 use std::pin::Pin;
+use std::sync::Arc;
 
 pub type ActionTickFunc<C, W, E> =
     dyn FnMut(&mut BtAction<C, W, E>, &mut BlackBoard<C>, &mut W, &E) -> Status;
 pub type TreeState<C, W, E> = TreeNodeType<BtAction<C, W, E>, C, ActionTickFunc<C, W, E>, W, E>;
+// Gets the world and entity of a dropped instance and passes them to the halt function.
+pub type HaltOnDropFunc<W, E> = dyn FnMut(&mut dyn FnMut(&mut W, &E)) + Send;
 
-pub struct BtInstance<C: Unpin + Default + 'static, W: 'static, E: 'static> {
+#[cfg_attr(
+    feature = "thread_safe",
+    doc = r#"
+With the `thread_safe` feature, instances can be moved to other threads as long as their context
+is `Send`:
+
+```
+fn assert_send<T: Send>() {}
+assert_send::<behavior::factory::BtInstance<i32, Vec<f64>, u64>>();
+```
+
+```compile_fail
+fn assert_send<T: Send>() {}
+assert_send::<behavior::factory::BtInstance<std::rc::Rc<i32>, Vec<f64>, u64>>();
+```
+"#
+)]
+pub struct BtInstance<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static> {
     #[allow(dead_code)]
     pub(crate) tree_name: String,
     pub(crate) tree_state: TreeState<C, W, E>,
     pub(crate) tree_blackboard: BlackBoard<C>,
+    pub(crate) rng_seed: u64,
+    pub(crate) halt_on_drop: Option<Box<HaltOnDropFunc<W, E>>>,
     _pinned: PhantomPinned,
 }
 
-unsafe impl<C: Unpin + Default + 'static, W: 'static, E: 'static> Send for BtInstance<C, W, E> {}
-unsafe impl<C: Unpin + Default + 'static, W: 'static, E: 'static> Sync for BtInstance<C, W, E> {}
-
-impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtInstance<C, W, E> {
+impl<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static> BtInstance<C, W, E> {
     #[inline]
     pub fn tree_name(&self) -> &str {
         &self.tree_name.as_str()
@@ -51,6 +75,115 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtInstance<C, W, E> {
         )
     }
 
+    #[inline]
+    pub fn rng_seed(&self) -> u64 {
+        self.rng_seed
+    }
+
+    // Restarts the random stream shared by the randomized nodes of the whole instance, subtrees included.
+    pub fn set_rng_seed(self: Pin<&mut Self>, seed: u64) {
+        let this = unsafe { self.get_unchecked_mut() };
+        this.rng_seed = seed;
+        this.tree_blackboard.set_rng_seed(seed);
+    }
+
+    #[inline]
+    pub fn rng_mut(self: Pin<&mut Self>) -> &mut SeededRng {
+        unsafe { self.get_unchecked_mut() }
+            .tree_blackboard
+            .rng_mut()
+    }
+
+    // Resets the running nodes top-down, running actions get `BtNode::halted`. The next tick
+    // starts the tree over.
+    pub fn halt(self: Pin<&mut Self>, world: &mut W, entity: &E) {
+        let BtInstance {
+            ref mut tree_state,
+            ref mut tree_blackboard,
+            ..
+        } = unsafe { self.get_unchecked_mut() };
+        tree_state.reset(tree_blackboard, world, entity);
+    }
+
+    // Halts the instance when it is dropped, `provider` is called with the halt function
+    // and has to call it with the world and entity, e.g. after locking the world.
+    pub fn set_halt_on_drop(
+        self: Pin<&mut Self>,
+        provider: impl FnMut(&mut dyn FnMut(&mut W, &E)) + Send + 'static,
+    ) {
+        unsafe { self.get_unchecked_mut() }.halt_on_drop = Some(Box::new(provider));
+    }
+
+    #[inline]
+    pub fn clear_halt_on_drop(self: Pin<&mut Self>) {
+        unsafe { self.get_unchecked_mut() }.halt_on_drop = None;
+    }
+
+    // Reports the node events of the whole instance, subtrees included, replaces the previous
+    // observer.
+    pub fn set_observer(self: Pin<&mut Self>, observer: Box<dyn TreeObserver>) {
+        unsafe { self.get_unchecked_mut() }
+            .tree_blackboard
+            .set_observer(Some(observer));
+    }
+
+    #[inline]
+    pub fn take_observer(self: Pin<&mut Self>) -> Option<Box<dyn TreeObserver>> {
+        unsafe { self.get_unchecked_mut() }
+            .tree_blackboard
+            .set_observer(None)
+    }
+
+    #[inline]
+    pub fn observer_ref(&self) -> Option<&dyn TreeObserver> {
+        self.tree_blackboard.observer_ref()
+    }
+
+    // Fails if a blackboard value's type is not registered with `register_snapshot_blackboard_type`.
+    pub fn snapshot(&self) -> Result<BtInstanceSnapshot, BehaviorError> {
+        let mut nodes = Vec::new();
+        save_tree_state(self.tree_state.as_ref(), &mut nodes)?;
+        Ok(BtInstanceSnapshot {
+            tree_name: self.tree_name.to_string(),
+            rng_seed: self.rng_seed,
+            rng_word_pos: self.tree_blackboard.rng().get_word_pos(),
+            blackboard: self.tree_blackboard.save_values()?,
+            nodes,
+        })
+    }
+
+    // Delivered to the nodes during the next tick, see `EventQueue`.
+    pub fn push_event(self: Pin<&mut Self>, name: &str) {
+        unsafe { self.get_unchecked_mut() }
+            .tree_blackboard
+            .events_mut()
+            .push(name, None);
+    }
+
+    // The payload is written to the blackboard value named by the node receiving the event.
+    pub fn push_event_with_payload<T: BlackBoardType>(
+        self: Pin<&mut Self>,
+        name: &str,
+        payload: T,
+    ) {
+        unsafe { self.get_unchecked_mut() }
+            .tree_blackboard
+            .events_mut()
+            .push(name, Some(Box::new(payload)));
+    }
+
+    #[inline]
+    pub fn events(&self) -> &EventQueue {
+        self.tree_blackboard.events()
+    }
+
+    #[inline]
+    pub fn events_mut(self: Pin<&mut Self>) -> &mut EventQueue {
+        unsafe { self.get_unchecked_mut() }
+            .tree_blackboard
+            .events_mut()
+    }
+
     #[inline]
     pub fn context_ref(&self) -> &C {
         self.tree_blackboard.context_ref()
@@ -86,7 +219,8 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtInstance<C, W, E> {
 
         #[cfg(feature = "tree_visualization")]
         tree_blackboard.reset_visualization();
-        tree_state.control_tick(tree_blackboard, &mut tick_func, world, &entity)
+        tree_blackboard.events_mut().deliver();
+        tree_state.execute_tick(tree_blackboard, &mut tick_func, world, &entity)
     }
 
     pub fn customized_tick(
@@ -103,7 +237,8 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtInstance<C, W, E> {
 
         #[cfg(feature = "tree_visualization")]
         tree_blackboard.reset_visualization();
-        tree_state.control_tick(tree_blackboard, tick_func, world, &entity)
+        tree_blackboard.events_mut().deliver();
+        tree_state.execute_tick(tree_blackboard, tick_func, world, &entity)
     }
 
     cfg_tree_visualization! {
@@ -132,6 +267,53 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtInstance<C, W, E> {
     }
 }
 
+impl<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static> Drop
+    for BtInstance<C, W, E>
+{
+    fn drop(&mut self) {
+        if let Some(mut provider) = self.halt_on_drop.take() {
+            let BtInstance {
+                tree_state,
+                tree_blackboard,
+                ..
+            } = self;
+            provider(&mut |world, entity| tree_state.reset(tree_blackboard, world, entity));
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, PartialEq)]
+pub struct BtInstanceSnapshot {
+    pub tree_name: String,
+    pub rng_seed: u64,
+    pub rng_word_pos: u128,
+    pub blackboard: Vec<BlackBoardValueState>,
+    // pre-order, subtrees included.
+    pub nodes: Vec<NodeState>,
+}
+
+// Positions of the instances passed to `BtFactory::hot_reload_tree_template_from_json_str`,
+// instances that don't use the reloaded tree are in neither list.
+#[derive(Debug, Default)]
+pub struct HotReloadReport {
+    pub migrated: Vec<usize>,
+    // these instances keep running the tree they had before the reload.
+    pub failed: Vec<(usize, BehaviorError)>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct BtInstanceOptions {
+    // seed of the instance's random stream, a random one is drawn if not set.
+    pub rng_seed: Option<u64>,
+}
+
+impl BtInstanceOptions {
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+}
+
 /// link blackboard from parent tree to child tree
 pub(crate) struct ParentTreeLink<'parent> {
     pub(crate) parent_tree_name: &'parent str,
@@ -190,6 +372,9 @@ cfg_gen_editor_data! {
 
 pub(crate) type FastHashMap<K, V> = HashMap<K, V, ahash::RandomState>;
 
+const ROOT_TREE_INDEX: i32 = 0;
+const ROOT_TREE_DEPTH: i32 = 0;
+
 type NodeGenerateFunc<C, W, E> = fn(
     tree_name: &str,
     tree_index: i32,
@@ -211,16 +396,27 @@ type InitBlackboardFunc = fn(
     blackboard_template: &BlackBoardTemplate,
 ) -> Result<(), BehaviorError>;
 
-pub struct BtFactory<C: Default + 'static, W: 'static, E: 'static> {
+pub struct BtFactory<C: Default + MaybeSend + 'static, W: 'static, E: 'static> {
     tree_template_map: FastHashMap<String, TreeTemplate>,
     node_generator_map: FastHashMap<String, NodeGenerateFunc<C, W, E>>,
     init_blackboard_map: FastHashMap<&'static str, InitBlackboardFunc>,
+    blackboard_codec_map: FastHashMap<&'static str, BlackBoardCodec>,
+    switch_enum_map: FastHashMap<&'static str, (SwitchEnumParseFunc, SwitchEnumMatchFunc)>,
+    blackboard_ops_map: FastHashMap<&'static str, BlackBoardOps>,
+    // container type name to the item type name and the function reading an item.
+    foreach_container_map: FastHashMap<&'static str, (&'static str, ForEachItemFunc)>,
+    semaphore_map: FastHashMap<String, SharedSemaphore>,
+    clock: SharedClock,
+    #[cfg(feature = "expression_node")]
+    expression_adapter_map: FastHashMap<&'static str, crate::ExpressionAdapter>,
+    #[cfg(feature = "expression_node")]
+    expression_function_map: crate::ExpressionFunctionMap<C, W, E>,
     #[cfg(feature = "gen_editor_data")]
     gen_editor_data: GenEditorData,
 }
 
 // Only use in lib crate
-impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
+impl<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
     pub(crate) fn inner_create_tree_instance(
         &self,
         tree_name: &str,
@@ -228,6 +424,25 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
         tree_depth: i32,
         parent_tree_link: Option<ParentTreeLink>,
     ) -> Result<BtInstance<C, W, E>, BehaviorError> {
+        let (tree_blackboard, tree_state) =
+            self.inner_create_tree(tree_name, tree_index, tree_depth, parent_tree_link)?;
+        Ok(BtInstance {
+            tree_name: tree_name.to_string(),
+            tree_state,
+            tree_blackboard,
+            rng_seed: 0,
+            halt_on_drop: None,
+            _pinned: PhantomPinned,
+        })
+    }
+
+    pub(crate) fn inner_create_tree(
+        &self,
+        tree_name: &str,
+        tree_index: i32,
+        tree_depth: i32,
+        parent_tree_link: Option<ParentTreeLink>,
+    ) -> Result<(BlackBoard<C>, TreeState<C, W, E>), BehaviorError> {
         match self.tree_template_map.get(tree_name) {
             None => Err(BehaviorError::TreeTemplateNodeNotFound {
                 tree_name: tree_name.to_string(),
@@ -259,25 +474,19 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
                                 })?;
                             subtree_blackboard_map.insert(
                                 subtree_blackboard_name.to_string(),
-                                parent_blackboard.clone(),
+                                parent_blackboard.share(),
                             );
                         }
                         Some((subtree_blackboard_map, parent_tree_name))
                     }
                 };
-                let (tree_blackboard, tree_state) = tree_template.to_instance(
+                tree_template.to_instance(
                     &self,
                     tree_name,
                     tree_index,
                     tree_depth,
                     parent_link_info,
-                )?;
-                Ok(BtInstance {
-                    tree_name: tree_name.to_string(),
-                    tree_state,
-                    tree_blackboard,
-                    _pinned: PhantomPinned,
-                })
+                )
             }
         }
     }
@@ -332,6 +541,72 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
         Ok(())
     }
 
+    #[inline]
+    pub(crate) fn blackboard_ops(&self, bb_type: &str) -> Option<BlackBoardOps> {
+        self.blackboard_ops_map.get(bb_type).copied()
+    }
+
+    #[cfg(feature = "expression_node")]
+    #[inline]
+    pub(crate) fn expression_functions(&self) -> &crate::ExpressionFunctionMap<C, W, E> {
+        &self.expression_function_map
+    }
+
+    fn parse_blackboard_value<T: BlackBoardType + ConvertFromStr>(
+        value_str: &str,
+    ) -> Option<Box<dyn BlackBoardType>> {
+        T::convert_from_str(value_str).map(|value| Box::new(value) as Box<dyn BlackBoardType>)
+    }
+
+    #[inline]
+    pub(crate) fn foreach_container(
+        &self,
+        bb_type: &str,
+    ) -> Option<(&'static str, ForEachItemFunc)> {
+        self.foreach_container_map.get(bb_type).copied()
+    }
+
+    fn read_foreach_item<T: BlackBoardType, I: BlackBoardType>(
+        container: &dyn BlackBoardType,
+        index: usize,
+        item: Option<&mut dyn BlackBoardType>,
+    ) -> bool
+    where
+        for<'a> &'a T: IntoIterator<Item = &'a I>,
+    {
+        container
+            .downcast_ref::<T>()
+            .and_then(|container| container.into_iter().nth(index))
+            .is_some_and(|value| item.is_none_or(|item| item.clone_from_dyn(value)))
+    }
+
+    #[inline]
+    pub(crate) fn switch_enum_funcs(
+        &self,
+        bb_type: &str,
+    ) -> Option<(SwitchEnumParseFunc, SwitchEnumMatchFunc)> {
+        self.switch_enum_map.get(bb_type).copied()
+    }
+
+    // A case is the name `ConvertFromStr` parses, or else the i32 discriminant.
+    fn parse_switch_enum<T: BlackBoardType + ConvertFromStr + FromI32>(
+        case_str: &str,
+    ) -> Option<Box<dyn BlackBoardType>> {
+        T::convert_from_str(case_str)
+            .or_else(|| case_str.parse::<i32>().ok().and_then(T::from_i32))
+            .map(|case| Box::new(case) as Box<dyn BlackBoardType>)
+    }
+
+    fn match_switch_enum<T: BlackBoardType + PartialEq>(
+        value: &dyn BlackBoardType,
+        cases: &[Box<dyn BlackBoardType>],
+    ) -> Option<usize> {
+        let value = value.downcast_ref::<T>()?;
+        cases
+            .iter()
+            .position(|case| case.downcast_ref::<T>() == Some(value))
+    }
+
     pub(crate) fn init_blackboard(
         &self,
         tree_name: &str,
@@ -355,7 +630,21 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
             parent_tree_name,
             blackboard_map,
             blackboard_template,
-        )
+        )?;
+        if let (Some(codec), Some(blackboard)) = (
+            self.blackboard_codec_map.get(blackboard_type),
+            blackboard_map.get_mut(blackboard_template.bb_name.as_str()),
+        ) {
+            blackboard.set_codec(*codec);
+        }
+        #[cfg(feature = "expression_node")]
+        if let (Some(adapter), Some(blackboard)) = (
+            self.expression_adapter_map.get(blackboard_type),
+            blackboard_map.get_mut(blackboard_template.bb_name.as_str()),
+        ) {
+            blackboard.set_expression_adapter(*adapter);
+        }
+        Ok(())
     }
 
     pub(crate) fn get_node_executor(
@@ -390,29 +679,218 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
     }
 }
 
-impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
+impl<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
     pub fn new() -> Self {
         let mut object = Self {
             tree_template_map: FastHashMap::new(),
             node_generator_map: FastHashMap::new(),
             init_blackboard_map: FastHashMap::new(),
+            blackboard_codec_map: FastHashMap::new(),
+            switch_enum_map: FastHashMap::new(),
+            blackboard_ops_map: FastHashMap::new(),
+            foreach_container_map: FastHashMap::new(),
+            semaphore_map: FastHashMap::new(),
+            clock: Arc::new(WallClock),
+            #[cfg(feature = "expression_node")]
+            expression_adapter_map: FastHashMap::new(),
+            #[cfg(feature = "expression_node")]
+            expression_function_map: FastHashMap::new(),
             #[cfg(feature = "gen_editor_data")]
             gen_editor_data: GenEditorData::new(),
         };
         // TODO: 简化写法
-        object.register_blackboard_type::<bool>();
-        object.register_blackboard_type::<i32>();
-        object.register_blackboard_type::<i64>();
-        object.register_blackboard_type::<f32>();
-        object.register_blackboard_type::<f64>();
+        object.register_snapshot_blackboard_type::<bool>();
+        object.register_snapshot_blackboard_type::<i32>();
+        object.register_snapshot_blackboard_type::<i64>();
+        object.register_snapshot_blackboard_type::<f32>();
+        object.register_snapshot_blackboard_type::<f64>();
+        object.register_snapshot_blackboard_type::<String>();
+        object.register_comparable_blackboard_type::<bool>();
+        object.register_comparable_blackboard_type::<i32>();
+        object.register_comparable_blackboard_type::<i64>();
+        object.register_comparable_blackboard_type::<f32>();
+        object.register_comparable_blackboard_type::<f64>();
+        object.register_comparable_blackboard_type::<String>();
+        object.register_default_blackboard_type::<bool>();
+        object.register_default_blackboard_type::<i32>();
+        object.register_default_blackboard_type::<i64>();
+        object.register_default_blackboard_type::<f32>();
+        object.register_default_blackboard_type::<f64>();
+        object.register_default_blackboard_type::<String>();
+        #[cfg(feature = "expression_node")]
+        {
+            object.register_expression_blackboard_type::<bool>();
+            object.register_expression_blackboard_type::<i32>();
+            object.register_expression_blackboard_type::<i64>();
+            object.register_expression_blackboard_type::<f32>();
+            object.register_expression_blackboard_type::<f64>();
+            object.register_expression_blackboard_type::<String>();
+        }
         object
     }
 
+    #[inline]
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    // The clock is shared by all instances created afterwards, including their subtrees.
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.clock = clock;
+    }
+
+    // Registers the semaphore `Semaphore` nodes acquire slots of by name, it is shared by all
+    // instances of the factory. Changes the capacity if it is already registered.
+    pub fn register_semaphore(&mut self, name: &str, capacity: i64) -> SharedSemaphore {
+        assert!(
+            capacity >= 0,
+            "semaphore capacity must not be negative, name={}, capacity={}",
+            name,
+            capacity
+        );
+        self.semaphore_map
+            .entry(name.to_string())
+            .and_modify(|semaphore| semaphore.set_capacity(capacity))
+            .or_insert_with(|| Arc::new(Semaphore::new(name, capacity)))
+            .clone()
+    }
+
+    #[inline]
+    pub fn semaphore(&self, name: &str) -> Option<&SharedSemaphore> {
+        self.semaphore_map.get(name)
+    }
+
+    // Makes `name(...)` callable in the `Expression` nodes of trees created afterwards, it shadows
+    // the evalexpr builtin function of the same name. Calls with another number of arguments or
+    // with literals and blackboard values of other types fail to compile.
+    #[cfg(feature = "expression_node")]
+    pub fn register_expression_function(
+        &mut self,
+        name: &str,
+        arg_types: &[crate::ExpressionArgType],
+        func: crate::ExpressionFunc<C, W, E>,
+    ) {
+        self.expression_function_map.insert(
+            name.to_string(),
+            crate::ExpressionFunction::new(arg_types, func),
+        );
+    }
+
     pub fn register_blackboard_type<T: BlackBoardType + ConvertFromStr>(&mut self) {
         self.init_blackboard_map.insert(
             type_name::<T>(),
             Self::inner_init_blackboard::<T> as InitBlackboardFunc,
         );
+        self.blackboard_ops_map
+            .entry(type_name::<T>())
+            .or_insert_with(|| BlackBoardOps::new(Self::parse_blackboard_value::<T>));
+    }
+
+    fn update_blackboard_ops<T: BlackBoardType + ConvertFromStr>(
+        &mut self,
+        update: impl FnOnce(BlackBoardOps) -> BlackBoardOps,
+    ) {
+        self.register_blackboard_type::<T>();
+        let ops = self
+            .blackboard_ops_map
+            .get_mut(type_name::<T>())
+            .expect("blackboard ops are registered with the type");
+        *ops = update(*ops);
+    }
+
+    // Same as `register_blackboard_type`, `CompareBlackBoard` nodes can also test values of the
+    // type for Eq and Ne.
+    pub fn register_equatable_blackboard_type<T: BlackBoardType + ConvertFromStr + PartialEq>(
+        &mut self,
+    ) {
+        self.update_blackboard_ops::<T>(|ops| ops.with_eq::<T>());
+    }
+
+    // Same as `register_equatable_blackboard_type`, with the ordering comparisons as well.
+    pub fn register_comparable_blackboard_type<T: BlackBoardType + ConvertFromStr + PartialOrd>(
+        &mut self,
+    ) {
+        self.update_blackboard_ops::<T>(|ops| ops.with_eq::<T>().with_partial_cmp::<T>());
+    }
+
+    // Same as `register_blackboard_type`, `ClearBlackBoard` resets values of the type to
+    // `T::default()`.
+    pub fn register_default_blackboard_type<T: BlackBoardType + ConvertFromStr + Default>(
+        &mut self,
+    ) {
+        self.update_blackboard_ops::<T>(|ops| ops.with_default::<T>());
+    }
+
+    // Registers `Option<T>`, `ClearBlackBoard` resets it to None and `IsBlackBoardSet` tests
+    // it for Some.
+    pub fn register_option_blackboard_type<T: BlackBoardType + Clone + ConvertFromStr>(&mut self) {
+        self.update_blackboard_ops::<Option<T>>(|ops| ops.with_option::<T>());
+    }
+
+    // Same as `register_blackboard_type`, values of the type are also kept by `BtInstance::snapshot`.
+    pub fn register_snapshot_blackboard_type<
+        T: BlackBoardType + ConvertFromStr + Encode + Decode,
+    >(
+        &mut self,
+    ) {
+        self.register_blackboard_type::<T>();
+        self.blackboard_codec_map
+            .insert(type_name::<T>(), BlackBoardCodec::new::<T>());
+    }
+
+    // Same as `register_blackboard_type`, `Expression` nodes can also read and write values of the
+    // type, e.g. `Option` and `Vec` of numbers with `is_some(x)`, `len(x)` and `get(x, index)`.
+    #[cfg(feature = "expression_node")]
+    pub fn register_expression_blackboard_type<
+        T: BlackBoardType + ConvertFromStr + super::convert::ConvertExpressionValue,
+    >(
+        &mut self,
+    ) {
+        self.register_blackboard_type::<T>();
+        self.expression_adapter_map
+            .insert(type_name::<T>(), crate::ExpressionAdapter::new::<T>());
+    }
+
+    // Same as `register_blackboard_type`, `Switch` nodes can also read values of the enum, their
+    // cases are parsed by `ConvertFromStr`, or else as the i32 discriminants `FromI32` converts.
+    pub fn register_switch_enum_type<T: BlackBoardType + ConvertFromStr + FromI32 + PartialEq>(
+        &mut self,
+    ) {
+        self.register_blackboard_type::<T>();
+        self.switch_enum_map.insert(
+            type_name::<T>(),
+            (
+                Self::parse_switch_enum::<T> as SwitchEnumParseFunc,
+                Self::match_switch_enum::<T> as SwitchEnumMatchFunc,
+            ),
+        );
+    }
+
+    // Registers the type along with `Vec`, `VecDeque` and `LinkedList` of it, `ForEach` nodes can
+    // iterate over blackboard values of these containers.
+    pub fn register_foreach_item_type<I: BlackBoardType + ConvertFromStr + Clone>(&mut self) {
+        self.register_blackboard_type::<I>();
+        self.register_foreach_container_type::<Vec<I>, I>();
+        self.register_foreach_container_type::<VecDeque<I>, I>();
+        self.register_foreach_container_type::<LinkedList<I>, I>();
+    }
+
+    fn register_foreach_container_type<
+        T: BlackBoardType + ConvertFromStr,
+        I: BlackBoardType + ConvertFromStr,
+    >(
+        &mut self,
+    ) where
+        for<'a> &'a T: IntoIterator<Item = &'a I>,
+    {
+        self.register_blackboard_type::<T>();
+        self.foreach_container_map.insert(
+            type_name::<T>(),
+            (
+                type_name::<I>(),
+                Self::read_foreach_item::<T, I> as ForEachItemFunc,
+            ),
+        );
     }
 
     cfg_not_gen_editor_data! {
@@ -505,9 +983,427 @@ impl<C: Unpin + Default + 'static, W: 'static, E: 'static> BtFactory<C, W, E> {
         &self,
         tree_name: &str,
     ) -> Result<Pin<Box<BtInstance<C, W, E>>>, BehaviorError> {
-        static ROOT_TREE_INDEX: i32 = 0;
-        static ROOT_TREE_DEPTH: i32 = 0;
-        self.inner_create_tree_instance(tree_name, ROOT_TREE_INDEX, ROOT_TREE_DEPTH, None)
-            .map(|instance| Box::pin(instance))
+        self.create_tree_instance_with_options(tree_name, BtInstanceOptions::default())
+    }
+
+    pub fn create_tree_instance_with_options(
+        &self,
+        tree_name: &str,
+        options: BtInstanceOptions,
+    ) -> Result<Pin<Box<BtInstance<C, W, E>>>, BehaviorError> {
+        let mut instance =
+            self.inner_create_tree_instance(tree_name, ROOT_TREE_INDEX, ROOT_TREE_DEPTH, None)?;
+        let rng_seed = options.rng_seed.unwrap_or_else(rand::random);
+        instance.rng_seed = rng_seed;
+        instance.tree_blackboard.set_rng_seed(rng_seed);
+        Ok(Box::pin(instance))
+    }
+
+    // Whether the tree, or one of its subtrees, is built from the template of `template_name`.
+    pub fn tree_uses_template(&self, tree_name: &str, template_name: &str) -> bool {
+        let mut visited = vec![tree_name];
+        let mut index = 0;
+        while let Some(name) = visited.get(index).copied() {
+            if name == template_name {
+                return true;
+            }
+            if let Some(tree_template) = self.tree_template_map.get(name) {
+                for subtree_name in tree_template.subtree_names() {
+                    if !visited.contains(&subtree_name) {
+                        visited.push(subtree_name);
+                    }
+                }
+            }
+            index += 1;
+        }
+        false
+    }
+
+    // Rebuilds the instance from the current template of its tree. Running nodes of the old
+    // tree are halted, blackboard values whose name and type still match are kept along with
+    // the context and the random stream, values local to subtrees restart from the template.
+    // On error the instance is left untouched.
+    pub fn migrate_tree_instance(
+        &self,
+        instance: Pin<&mut BtInstance<C, W, E>>,
+        world: &mut W,
+        entity: &E,
+    ) -> Result<(), BehaviorError> {
+        let this = unsafe { instance.get_unchecked_mut() };
+        let (mut tree_blackboard, tree_state) =
+            self.inner_create_tree(&this.tree_name, ROOT_TREE_INDEX, ROOT_TREE_DEPTH, None)?;
+        this.tree_state
+            .reset(&mut this.tree_blackboard, world, entity);
+        for (blackboard_name, blackboard) in tree_blackboard.blackboard_map_ref() {
+            if let Some(old_blackboard) = this
+                .tree_blackboard
+                .blackboard_map_ref()
+                .get(blackboard_name)
+            {
+                blackboard.clone_value_from(old_blackboard);
+            }
+        }
+        std::mem::swap(
+            tree_blackboard.context_mut(),
+            this.tree_blackboard.context_mut(),
+        );
+        tree_blackboard.swap_shared_state(&mut this.tree_blackboard);
+        this.tree_state = tree_state;
+        this.tree_blackboard = tree_blackboard;
+        Ok(())
+    }
+
+    // Recompiles the template and migrates the given instances that use it. A template that
+    // fails to compile or to build on its own is rejected before any instance is touched, the
+    // previous one is kept.
+    pub fn hot_reload_tree_template_from_json_str<'a>(
+        &mut self,
+        tree_name: &str,
+        tree_json_str: &str,
+        instances: impl IntoIterator<Item = (Pin<&'a mut BtInstance<C, W, E>>, &'a E)>,
+        world: &mut W,
+    ) -> Result<HotReloadReport, BehaviorError> {
+        let prev_template = self.tree_template_map.remove(tree_name);
+        let trial = match self.compile_tree_template_from_json_str(tree_name, tree_json_str) {
+            Ok(()) => self
+                .inner_create_tree(tree_name, ROOT_TREE_INDEX, ROOT_TREE_DEPTH, None)
+                .map(|_| ()),
+            Err(error) => Err(error),
+        };
+        if let Err(error) = trial {
+            match prev_template {
+                Some(prev_template) => {
+                    self.tree_template_map
+                        .insert(tree_name.to_string(), prev_template);
+                }
+                None => {
+                    self.tree_template_map.remove(tree_name);
+                }
+            }
+            return Err(error);
+        }
+        let mut report = HotReloadReport::default();
+        for (position, (instance, entity)) in instances.into_iter().enumerate() {
+            if !self.tree_uses_template(instance.tree_name(), tree_name) {
+                continue;
+            }
+            match self.migrate_tree_instance(instance, world, entity) {
+                Ok(()) => report.migrated.push(position),
+                Err(error) => report.failed.push((position, error)),
+            }
+        }
+        Ok(report)
+    }
+
+    // Rebuilds the instance from the template and resumes it where the snapshot was taken.
+    pub fn restore_tree_instance(
+        &self,
+        tree_name: &str,
+        snapshot: &BtInstanceSnapshot,
+    ) -> Result<Pin<Box<BtInstance<C, W, E>>>, BehaviorError> {
+        let mut instance = self.create_tree_instance_with_options(
+            tree_name,
+            BtInstanceOptions::default().with_rng_seed(snapshot.rng_seed),
+        )?;
+        let BtInstance {
+            tree_state,
+            tree_blackboard,
+            ..
+        } = unsafe { instance.as_mut().get_unchecked_mut() };
+        tree_blackboard
+            .rng_mut()
+            .set_word_pos(snapshot.rng_word_pos);
+        tree_blackboard.load_values(&snapshot.blackboard)?;
+        let mut states = snapshot.nodes.iter();
+        load_tree_state(tree_state.as_mut(), &mut states)?;
+        if let Some(state) = states.next() {
+            return Err(BehaviorError::RestoreSnapshotFailed {
+                node_index: state.node_index,
+                error_info: "node not found in tree".to_string(),
+            });
+        }
+        Ok(instance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::BtNode;
+    use crate::{TreeNodeBase, TreeNodeStatus};
+    use behavior_macros::TreeNodeStatus;
+
+    // counts in the world how many times it was ended.
+    #[derive(Debug, Default, TreeNodeStatus)]
+    struct RunningNode {
+        base: TreeNodeBase,
+    }
+
+    impl BtNodeGenerator for RunningNode {
+        type Context = ();
+        type World = i32;
+        type Entity = ();
+
+        fn generate_node(
+            _tree_name: &str,
+            _tree_index: i32,
+            _tree_depth: i32,
+            _node_name: &str,
+            _node_index: i32,
+            _blackboard_map: &BlackBoardMap,
+            _metadata_map: Option<&HashMap<String, String>>,
+            _blackboard_ref_map: Option<&HashMap<String, String>>,
+            _dynamic_ref_map: Option<&HashMap<String, String>>,
+        ) -> Result<Executor<(), i32, ()>, BehaviorError> {
+            Ok(Box::new(RunningNode::default()))
+        }
+    }
+
+    #[cfg(feature = "gen_editor_data")]
+    impl EditorNodeDataGenerator for RunningNode {
+        fn generate_editor_node_data() -> serde_json::Value {
+            serde_json::Value::Null
+        }
+    }
+
+    impl BtNode for RunningNode {
+        type Context = ();
+        type World = i32;
+        type Entity = ();
+
+        fn end(&mut self, _ctx: &mut (), world: &mut i32, _entity: &()) -> Status {
+            *world += 1;
+            Status::Success
+        }
+
+        fn tick(&mut self, _ctx: &mut (), _world: &mut i32, _entity: &()) -> Status {
+            Status::Running
+        }
+    }
+
+    const TREE_JSON: &str = r#"{
+        "tree_blackboard": [
+            { "bb_name": "count", "bb_type": "i32", "bb_value": "3" },
+            { "bb_name": "speed", "bb_type": "f32", "bb_value": "1.5" }
+        ],
+        "tree_structure": {
+            "Sequence": [1, [
+                { "SubTree": [2, "child", { "count": "count" }] },
+                { "Action": [3, { "name": "RunningNode" }] }
+            ]]
+        }
+    }"#;
+
+    fn create_factory() -> BtFactory<(), i32, ()> {
+        let mut bt_factory = BtFactory::<(), i32, ()>::new();
+        bt_factory.register_tree_node::<RunningNode>();
+        bt_factory
+            .compile_tree_template_from_json_str("test", TREE_JSON)
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "child",
+                r#"{ "tree_blackboard": [{ "bb_name": "count", "bb_type": "i32", "bb_value": "0" }],
+                    "tree_structure": { "AlwaysSuccess": 1 } }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "other",
+                r#"{ "tree_structure": { "Action": [1, { "name": "RunningNode" }] } }"#,
+            )
+            .unwrap();
+        bt_factory
+    }
+
+    fn get_value<T: BlackBoardType + Clone>(
+        instance: &BtInstance<(), i32, ()>,
+        blackboard_name: &str,
+    ) -> Option<T> {
+        instance
+            .blackboard_map_ref()
+            .get(blackboard_name)
+            .and_then(|blackboard| blackboard.borrow().downcast_ref::<T>().cloned())
+    }
+
+    #[test]
+    #[cfg(feature = "thread_safe")]
+    fn move_instance_across_threads() {
+        let bt_factory = create_factory();
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        let handle = std::thread::spawn(move || {
+            let mut world = 0;
+            let status = instance.as_mut().tick(&mut world, &());
+            (instance, status)
+        });
+        let (instance, status) = handle.join().unwrap();
+        assert_eq!(status, Status::Running);
+        assert_eq!(get_value::<i32>(&instance, "count"), Some(3));
+    }
+
+    #[test]
+    fn blackboard_cells_share_values() {
+        let bt_factory = create_factory();
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        let blackboard_map = instance.as_mut().blackboard_map_mut();
+        assert!(blackboard_map["count"]
+            .downcast_to_blackboard_cell::<f32>()
+            .is_none());
+        let mut count = blackboard_map["count"]
+            .downcast_to_blackboard_cell::<i32>()
+            .unwrap();
+        let other = blackboard_map["count"]
+            .downcast_to_blackboard_cell::<i32>()
+            .unwrap();
+        *count.as_mut() += 2;
+        assert_eq!(other.get(), 5);
+        assert_eq!(get_value::<i32>(&instance, "count"), Some(5));
+    }
+
+    #[test]
+    #[cfg(feature = "thread_safe")]
+    fn blackboard_cells_across_threads() {
+        let bt_factory = create_factory();
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        let blackboard_map = instance.as_mut().blackboard_map_mut();
+        let mut count = blackboard_map["count"]
+            .downcast_to_blackboard_cell::<i32>()
+            .unwrap();
+        let other = blackboard_map["count"]
+            .downcast_to_blackboard_cell::<i32>()
+            .unwrap();
+
+        // a value borrowed on another thread is waited for.
+        let borrowed = other.as_ref();
+        let handle = std::thread::spawn(move || {
+            count.set(7);
+            count
+        });
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(*borrowed, 3);
+        drop(borrowed);
+        let count = handle.join().unwrap();
+        assert_eq!(get_value::<i32>(&instance, "count"), Some(7));
+
+        // borrowing it again on the same thread panics instead of deadlocking.
+        let borrowed = count.as_ref();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| other.get()));
+        assert!(result.is_err());
+        assert_eq!(*borrowed, 7);
+    }
+
+    #[test]
+    fn halt_and_halt_on_drop() {
+        let bt_factory = create_factory();
+        let mut world = 0;
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        instance.as_mut().halt(&mut world, &());
+        assert_eq!(world, 0);
+        assert_eq!(instance.as_mut().tick(&mut world, &()), Status::Running);
+        instance.as_mut().halt(&mut world, &());
+        assert_eq!(world, 1);
+        instance.as_mut().halt(&mut world, &());
+        assert_eq!(world, 1);
+
+        let shared_world = Arc::new(std::sync::Mutex::new(world));
+        assert_eq!(instance.as_mut().tick(&mut world, &()), Status::Running);
+        let provider_world = shared_world.clone();
+        instance.as_mut().set_halt_on_drop(move |halt| {
+            halt(&mut provider_world.lock().unwrap(), &());
+        });
+        drop(instance);
+        assert_eq!(*shared_world.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn hot_reload_migrates_instances() {
+        let mut bt_factory = create_factory();
+        let mut world = 0;
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        let mut other = bt_factory.create_tree_instance("other").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut world, &()), Status::Running);
+        assert_eq!(other.as_mut().tick(&mut world, &()), Status::Running);
+        *instance
+            .blackboard_map_ref()
+            .get("count")
+            .unwrap()
+            .borrow_mut()
+            .downcast_mut::<i32>()
+            .unwrap() = 7;
+        let rng_seed = instance.rng_seed();
+
+        let report = bt_factory
+            .hot_reload_tree_template_from_json_str(
+                "test",
+                r#"{
+                    "tree_blackboard": [
+                        { "bb_name": "count", "bb_type": "i32", "bb_value": "0" },
+                        { "bb_name": "speed", "bb_type": "i64", "bb_value": "2" }
+                    ],
+                    "tree_structure": {
+                        "Sequence": [1, [
+                            { "SubTree": [2, "child", { "count": "count" }] },
+                            { "AlwaysFailure": 3 }
+                        ]]
+                    }
+                }"#,
+                [(instance.as_mut(), &()), (other.as_mut(), &())],
+                &mut world,
+            )
+            .unwrap();
+        assert_eq!(report.migrated, vec![0]);
+        assert!(report.failed.is_empty());
+        // the running action of the old tree is halted, `other` is left alone.
+        assert_eq!(world, 1);
+        assert_eq!(get_value::<i32>(&instance, "count"), Some(7));
+        assert_eq!(get_value::<i64>(&instance, "speed"), Some(2));
+        assert_eq!(instance.rng_seed(), rng_seed);
+        assert_eq!(instance.as_mut().tick(&mut world, &()), Status::Failure);
+        assert_eq!(other.as_mut().tick(&mut world, &()), Status::Running);
+    }
+
+    #[test]
+    fn hot_reload_subtree_and_failures() {
+        let mut bt_factory = create_factory();
+        let mut world = 0;
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut world, &()), Status::Running);
+
+        assert!(bt_factory
+            .hot_reload_tree_template_from_json_str(
+                "child",
+                "{",
+                [(instance.as_mut(), &())],
+                &mut world
+            )
+            .is_err());
+        // a template that doesn't build is rejected and the previous one is kept.
+        assert!(matches!(
+            bt_factory.hot_reload_tree_template_from_json_str(
+                "child",
+                r#"{ "tree_structure": { "Action": [1, { "name": "Unregistered" }] } }"#,
+                [(instance.as_mut(), &())],
+                &mut world,
+            ),
+            Err(BehaviorError::UnregisteredTreeNode { .. })
+        ));
+        assert_eq!(world, 0);
+        assert_eq!(instance.as_mut().tick(&mut world, &()), Status::Running);
+        let mut created = bt_factory.create_tree_instance("test").unwrap();
+        assert_eq!(created.as_mut().tick(&mut world, &()), Status::Running);
+        assert_eq!(get_value::<i32>(&created, "count"), Some(3));
+
+        let report = bt_factory
+            .hot_reload_tree_template_from_json_str(
+                "child",
+                r#"{ "tree_structure": { "AlwaysFailure": 1 } }"#,
+                [(instance.as_mut(), &())],
+                &mut world,
+            )
+            .unwrap();
+        assert_eq!(report.migrated, vec![0]);
+        assert_eq!(world, 1);
+        assert_eq!(get_value::<i32>(&instance, "count"), Some(3));
+        assert_eq!(instance.as_mut().tick(&mut world, &()), Status::Failure);
     }
 }
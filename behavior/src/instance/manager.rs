@@ -0,0 +1,371 @@
+#[cfg(feature = "thread_safe")]
+use super::batch::batch_tick;
+use super::factory::{BtFactory, BtInstance, BtInstanceOptions, FastHashMap};
+use crate::{BehaviorError, MaybeSend, Status};
+use ahash::HashMapExt;
+use std::hash::Hash;
+use std::pin::Pin;
+
+// What happens to an instance once its root completes with Success or Failure.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    // the tree starts over on the next tick.
+    #[default]
+    Always,
+    // the instance is no longer ticked until `BtManager::restart`.
+    Never,
+    // the instance is dropped right after the tick that completed it.
+    Despawn,
+}
+
+struct ManagedInstance<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static> {
+    entity: E,
+    instance: Pin<Box<BtInstance<C, W, E>>>,
+    restart_policy: RestartPolicy,
+    last_status: Status,
+}
+
+impl<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static> ManagedInstance<C, W, E> {
+    #[inline]
+    fn is_stopped(&self) -> bool {
+        self.restart_policy == RestartPolicy::Never
+            && matches!(self.last_status, Status::Success | Status::Failure)
+    }
+}
+
+// Owns one instance per entity, `tick_all` goes through them in a stable order that doesn't
+// depend on hashing.
+pub struct BtManager<
+    C: Unpin + Default + MaybeSend + 'static,
+    W: 'static,
+    E: Eq + Hash + Clone + 'static,
+> {
+    factory: BtFactory<C, W, E>,
+    instances: Vec<ManagedInstance<C, W, E>>,
+    entity_map: FastHashMap<E, usize>,
+}
+
+impl<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: Eq + Hash + Clone + 'static>
+    BtManager<C, W, E>
+{
+    pub fn new(factory: BtFactory<C, W, E>) -> Self {
+        Self {
+            factory,
+            instances: Vec::new(),
+            entity_map: FastHashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn factory(&self) -> &BtFactory<C, W, E> {
+        &self.factory
+    }
+
+    #[inline]
+    pub fn factory_mut(&mut self) -> &mut BtFactory<C, W, E> {
+        &mut self.factory
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    #[inline]
+    pub fn contains(&self, entity: &E) -> bool {
+        self.entity_map.contains_key(entity)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = &E> {
+        self.instances.iter().map(|managed| &managed.entity)
+    }
+
+    pub fn spawn(
+        &mut self,
+        entity: E,
+        tree_name: &str,
+    ) -> Result<Option<Pin<Box<BtInstance<C, W, E>>>>, BehaviorError> {
+        self.spawn_with_options(
+            entity,
+            tree_name,
+            BtInstanceOptions::default(),
+            RestartPolicy::default(),
+        )
+    }
+
+    // Returns the instance the entity had before, it is not halted.
+    pub fn spawn_with_options(
+        &mut self,
+        entity: E,
+        tree_name: &str,
+        options: BtInstanceOptions,
+        restart_policy: RestartPolicy,
+    ) -> Result<Option<Pin<Box<BtInstance<C, W, E>>>>, BehaviorError> {
+        let instance = self
+            .factory
+            .create_tree_instance_with_options(tree_name, options)?;
+        let managed = ManagedInstance {
+            entity: entity.clone(),
+            instance,
+            restart_policy,
+            last_status: Status::Idle,
+        };
+        match self.entity_map.get(&entity) {
+            Some(&index) => Ok(Some(
+                std::mem::replace(&mut self.instances[index], managed).instance,
+            )),
+            None => {
+                self.entity_map.insert(entity, self.instances.len());
+                self.instances.push(managed);
+                Ok(None)
+            }
+        }
+    }
+
+    // Halts the running nodes of the instance and drops it.
+    pub fn despawn(&mut self, entity: &E, world: &mut W) -> bool {
+        match self.remove(entity) {
+            Some(mut instance) => {
+                instance.as_mut().halt(world, entity);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Takes the instance out of the manager as is, without halting it.
+    pub fn remove(&mut self, entity: &E) -> Option<Pin<Box<BtInstance<C, W, E>>>> {
+        let index = self.entity_map.remove(entity)?;
+        let managed = self.instances.swap_remove(index);
+        if let Some(moved) = self.instances.get(index) {
+            self.entity_map.insert(moved.entity.clone(), index);
+        }
+        Some(managed.instance)
+    }
+
+    pub fn get(&self, entity: &E) -> Option<&BtInstance<C, W, E>> {
+        self.entity_map
+            .get(entity)
+            .map(|&index| self.instances[index].instance.as_ref().get_ref())
+    }
+
+    pub fn get_mut(&mut self, entity: &E) -> Option<Pin<&mut BtInstance<C, W, E>>> {
+        self.entity_map
+            .get(entity)
+            .map(|&index| self.instances[index].instance.as_mut())
+    }
+
+    // Idle until the instance is ticked.
+    pub fn last_status(&self, entity: &E) -> Option<Status> {
+        self.entity_map
+            .get(entity)
+            .map(|&index| self.instances[index].last_status)
+    }
+
+    pub fn restart_policy(&self, entity: &E) -> Option<RestartPolicy> {
+        self.entity_map
+            .get(entity)
+            .map(|&index| self.instances[index].restart_policy)
+    }
+
+    pub fn set_restart_policy(&mut self, entity: &E, restart_policy: RestartPolicy) -> bool {
+        match self.entity_map.get(entity) {
+            Some(&index) => {
+                self.instances[index].restart_policy = restart_policy;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Halts the instance and lets it be ticked from the root again, also resumes an instance
+    // stopped by `RestartPolicy::Never`.
+    pub fn restart(&mut self, entity: &E, world: &mut W) -> bool {
+        match self.entity_map.get(entity) {
+            Some(&index) => {
+                let managed = &mut self.instances[index];
+                managed.instance.as_mut().halt(world, entity);
+                managed.last_status = Status::Idle;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // None if the entity has no instance or it is stopped by `RestartPolicy::Never`.
+    pub fn tick(&mut self, entity: &E, world: &mut W) -> Option<Status> {
+        let index = *self.entity_map.get(entity)?;
+        let status = Self::tick_managed(&mut self.instances[index], world)?;
+        if Self::should_despawn(&self.instances[index]) {
+            self.remove(entity);
+        }
+        Some(status)
+    }
+
+    pub fn tick_all(&mut self, world: &mut W) {
+        let mut index = 0;
+        while index < self.instances.len() {
+            Self::tick_managed(&mut self.instances[index], world);
+            if Self::should_despawn(&self.instances[index]) {
+                let entity = self.instances[index].entity.clone();
+                self.remove(&entity);
+                // the last instance was moved here and still has to be ticked.
+                continue;
+            }
+            index += 1;
+        }
+    }
+
+    // Ticks the instances on `worker_count` threads, each with the world `create_world` builds
+    // for it, see `batch_tick`. Instances to despawn are removed once all workers are done.
+    #[cfg(feature = "thread_safe")]
+    pub fn tick_all_parallel<F>(&mut self, worker_count: usize, create_world: F) -> Vec<W>
+    where
+        W: Send,
+        E: Send + Sync,
+        F: Fn(usize) -> W + Sync,
+    {
+        let worlds = batch_tick(
+            &mut self.instances,
+            worker_count,
+            create_world,
+            |managed, world| {
+                Self::tick_managed(managed, world);
+            },
+        );
+        let despawned: Vec<E> = self
+            .instances
+            .iter()
+            .filter(|managed| Self::should_despawn(managed))
+            .map(|managed| managed.entity.clone())
+            .collect();
+        for entity in despawned.iter() {
+            self.remove(entity);
+        }
+        worlds
+    }
+
+    fn tick_managed(managed: &mut ManagedInstance<C, W, E>, world: &mut W) -> Option<Status> {
+        if managed.is_stopped() {
+            return None;
+        }
+        let ManagedInstance {
+            entity, instance, ..
+        } = managed;
+        managed.last_status = instance.as_mut().tick(world, entity);
+        Some(managed.last_status)
+    }
+
+    #[inline]
+    fn should_despawn(managed: &ManagedInstance<C, W, E>) -> bool {
+        managed.restart_policy == RestartPolicy::Despawn
+            && matches!(managed.last_status, Status::Success | Status::Failure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_manager() -> BtManager<(), (), u32> {
+        let mut bt_factory = BtFactory::<(), (), u32>::new();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "test",
+                r#"{ "tree_structure": { "Sequence": [1, [{ "AlwaysRunning": 2 }]] } }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "success",
+                r#"{ "tree_structure": { "AlwaysSuccess": 1 } }"#,
+            )
+            .unwrap();
+        BtManager::new(bt_factory)
+    }
+
+    #[test]
+    fn spawn_tick_despawn() {
+        let mut manager = create_manager();
+        assert!(manager.spawn(1, "test").unwrap().is_none());
+        assert!(manager.spawn(2, "success").unwrap().is_none());
+        assert!(manager.spawn(3, "missing").is_err());
+        assert_eq!(manager.last_status(&1), Some(Status::Idle));
+
+        manager.tick_all(&mut ());
+        assert_eq!(manager.last_status(&1), Some(Status::Running));
+        assert_eq!(manager.last_status(&2), Some(Status::Success));
+        assert_eq!(manager.get(&1).unwrap().tree_name(), "test");
+
+        assert!(manager.despawn(&1, &mut ()));
+        assert!(!manager.despawn(&1, &mut ()));
+        assert_eq!(manager.len(), 1);
+        assert_eq!(manager.entities().collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(manager.tick(&2, &mut ()), Some(Status::Success));
+        assert!(manager.spawn(2, "test").unwrap().is_some());
+        assert_eq!(manager.tick(&2, &mut ()), Some(Status::Running));
+    }
+
+    #[test]
+    #[cfg(feature = "thread_safe")]
+    fn tick_all_parallel() {
+        let mut manager = create_manager();
+        for entity in 0..10 {
+            let (tree_name, restart_policy) = match entity % 2 {
+                0 => ("test", RestartPolicy::Always),
+                _ => ("success", RestartPolicy::Despawn),
+            };
+            manager
+                .spawn_with_options(
+                    entity,
+                    tree_name,
+                    BtInstanceOptions::default(),
+                    restart_policy,
+                )
+                .unwrap();
+        }
+        assert_eq!(manager.tick_all_parallel(3, |_| ()).len(), 3);
+        let mut entities: Vec<_> = manager.entities().copied().collect();
+        entities.sort();
+        assert_eq!(entities, vec![0, 2, 4, 6, 8]);
+        assert!(entities
+            .iter()
+            .all(|entity| manager.last_status(entity) == Some(Status::Running)));
+    }
+
+    #[test]
+    fn restart_policy() {
+        let mut manager = create_manager();
+        for (entity, restart_policy) in [
+            (1, RestartPolicy::Always),
+            (2, RestartPolicy::Never),
+            (3, RestartPolicy::Despawn),
+            (4, RestartPolicy::Despawn),
+        ] {
+            manager
+                .spawn_with_options(
+                    entity,
+                    "success",
+                    BtInstanceOptions::default(),
+                    restart_policy,
+                )
+                .unwrap();
+        }
+        manager.tick_all(&mut ());
+        assert_eq!(manager.entities().collect::<Vec<_>>(), vec![&1, &2]);
+
+        assert_eq!(manager.tick(&1, &mut ()), Some(Status::Success));
+        assert_eq!(manager.tick(&2, &mut ()), None);
+        assert_eq!(manager.last_status(&2), Some(Status::Success));
+        assert!(manager.restart(&2, &mut ()));
+        assert_eq!(manager.tick(&2, &mut ()), Some(Status::Success));
+        assert!(manager.set_restart_policy(&2, RestartPolicy::Despawn));
+        assert_eq!(manager.tick(&2, &mut ()), Some(Status::Success));
+        assert!(!manager.contains(&2));
+    }
+}
@@ -1,6 +1,8 @@
 // TODO: 修改可见类型
+pub mod batch;
 pub mod convert;
 pub mod factory;
 pub mod generator;
+pub mod manager;
 pub mod node;
 pub mod serde;
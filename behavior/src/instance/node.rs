@@ -1,7 +1,7 @@
 use super::{convert::ConvertFromStr, factory::ActionTickFunc};
 use crate::{
-    BehaviorError, BlackBoard, BlackBoardMap, BlackBoardType, NodeLocation, NodeType, Status,
-    TreeNode, TreeNodeStatus,
+    restore_status, BehaviorError, BlackBoard, BlackBoardMap, BlackBoardType, MaybeSend,
+    NodeLocation, NodeState, NodeType, SharedBlackBoardValue, Status, TreeNode, TreeNodeStatus,
 };
 use regex::Regex;
 use std::{
@@ -9,7 +9,6 @@ use std::{
     fmt::Debug,
     marker::PhantomData,
     ops::{Deref, DerefMut},
-    ptr::NonNull,
     sync::OnceLock,
 };
 
@@ -53,54 +52,247 @@ impl<T: Clone, Enum: FromI32> MetaDataCell<T, Enum> {
     }
 }
 
-#[derive(Debug)]
-pub struct BlackBoardCell<T> {
-    name: NonNull<String>,
-    value: NonNull<T>,
-}
-impl<T> BlackBoardCell<T> {
-    #[inline]
-    pub fn new(name: *const String, value: *mut T) -> Self {
-        let name = unsafe { NonNull::new_unchecked(name as *mut String) };
-        let value = unsafe { NonNull::new_unchecked(value) };
-        Self { name, value }
+cfg_not_thread_safe! {
+    use std::ptr::NonNull;
+
+    pub type BlackBoardRef<'a, T> = &'a T;
+    pub type BlackBoardRefMut<'a, T> = &'a mut T;
+
+    fn plain_ref<T>(value: &T) -> BlackBoardRef<'_, T> {
+        value
     }
-    #[inline]
-    pub fn as_ref(&self) -> &T {
-        unsafe { self.value.as_ref() }
+
+    fn plain_mut<T>(value: &mut T) -> BlackBoardRefMut<'_, T> {
+        value
     }
-    #[inline]
-    pub fn as_mut(&mut self) -> &mut T {
-        unsafe { self.value.as_mut() }
+
+    // Shares a blackboard value of the instance its node belongs to, created by `get_blackboard`
+    // or `SharedBlackBoardValue::downcast_to_blackboard_cell`. The cell keeps the value alive and
+    // points into it, the value is only used by the thread ticking the instance.
+    #[derive(Debug)]
+    pub struct BlackBoardCell<T> {
+        shared: SharedBlackBoardValue,
+        value: NonNull<T>,
     }
-    #[inline]
-    pub fn name(&self) -> &str {
-        unsafe { self.name.as_ref() }
+    impl<T: BlackBoardType> BlackBoardCell<T> {
+        // Panics if the value is not a `T`.
+        #[inline]
+        pub(crate) fn new(shared: SharedBlackBoardValue) -> Self {
+            let value = shared
+                .borrow_mut()
+                .downcast_mut::<T>()
+                .map(NonNull::from)
+                .unwrap();
+            Self { shared, value }
+        }
+        #[inline]
+        pub fn as_ref(&self) -> &T {
+            unsafe { self.value.as_ref() }
+        }
+        #[inline]
+        pub fn as_mut(&mut self) -> &mut T {
+            unsafe { self.value.as_mut() }
+        }
+        #[inline]
+        pub fn name(&self) -> &str {
+            self.shared.bb_name()
+        }
+        #[inline]
+        pub fn set(&mut self, value: T) {
+            *self.as_mut() = value;
+        }
     }
-    #[inline]
-    pub fn set(&mut self, value: T) {
-        unsafe {
-            *self.value.as_mut() = value;
+    impl<T: BlackBoardType> Deref for BlackBoardCell<T> {
+        type Target = T;
+        fn deref(&self) -> &Self::Target {
+            self.as_ref()
         }
     }
-}
-impl<T: Clone> BlackBoardCell<T> {
-    pub fn get(&self) -> T {
-        self.as_ref().clone()
+    impl<T: BlackBoardType> DerefMut for BlackBoardCell<T> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.as_mut()
+        }
+    }
+    impl<U: BlackBoardType + Clone> BlackBoardCell<Option<U>> {
+        #[inline]
+        pub fn unwrap_ref(&self) -> &U {
+            self.as_ref().as_ref().unwrap()
+        }
+        #[inline]
+        pub fn unwrap_mut(&mut self) -> &mut U {
+            self.as_mut().as_mut().unwrap()
+        }
+    }
+    impl<T: BlackBoardType + Clone> Deref for DynamicCell<T> {
+        type Target = T;
+        fn deref(&self) -> &Self::Target {
+            self.as_ref()
+        }
+    }
+    impl<T: BlackBoardType + Clone> DerefMut for DynamicCell<T> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.as_mut()
+        }
     }
 }
-impl<T> Deref for BlackBoardCell<T> {
-    type Target = T;
-    fn deref(&self) -> &Self::Target {
-        self.as_ref()
+
+cfg_thread_safe! {
+    use crate::BlackBoardGuard;
+
+    // Borrows the value of a blackboard cell, the blackboard value stays locked until it's dropped.
+    pub struct BlackBoardRef<'a, T> {
+        inner: RefInner<'a, T>,
+    }
+    enum RefInner<'a, T> {
+        Locked(BlackBoardGuard<'a>, fn(&dyn BlackBoardType) -> &T),
+        Plain(&'a T),
+    }
+    impl<'a, T> BlackBoardRef<'a, T> {
+        fn locked(guard: BlackBoardGuard<'a>, project: fn(&dyn BlackBoardType) -> &T) -> Self {
+            project(guard.as_ref());
+            Self {
+                inner: RefInner::Locked(guard, project),
+            }
+        }
+    }
+    impl<T> Deref for BlackBoardRef<'_, T> {
+        type Target = T;
+        fn deref(&self) -> &Self::Target {
+            match &self.inner {
+                RefInner::Locked(guard, project) => project(guard.as_ref()),
+                RefInner::Plain(value) => value,
+            }
+        }
+    }
+    impl<T: Debug> Debug for BlackBoardRef<'_, T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            (**self).fmt(f)
+        }
+    }
+
+    pub struct BlackBoardRefMut<'a, T> {
+        inner: RefMutInner<'a, T>,
+    }
+    enum RefMutInner<'a, T> {
+        Locked(
+            BlackBoardGuard<'a>,
+            fn(&dyn BlackBoardType) -> &T,
+            fn(&mut dyn BlackBoardType) -> &mut T,
+        ),
+        Plain(&'a mut T),
+    }
+    impl<'a, T> BlackBoardRefMut<'a, T> {
+        fn locked(
+            mut guard: BlackBoardGuard<'a>,
+            project: fn(&dyn BlackBoardType) -> &T,
+            project_mut: fn(&mut dyn BlackBoardType) -> &mut T,
+        ) -> Self {
+            project_mut(guard.as_mut());
+            Self {
+                inner: RefMutInner::Locked(guard, project, project_mut),
+            }
+        }
+    }
+    impl<T> Deref for BlackBoardRefMut<'_, T> {
+        type Target = T;
+        fn deref(&self) -> &Self::Target {
+            match &self.inner {
+                RefMutInner::Locked(guard, project, _) => project(guard.as_ref()),
+                RefMutInner::Plain(value) => value,
+            }
+        }
+    }
+    impl<T> DerefMut for BlackBoardRefMut<'_, T> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            match &mut self.inner {
+                RefMutInner::Locked(guard, _, project_mut) => project_mut(guard.as_mut()),
+                RefMutInner::Plain(value) => value,
+            }
+        }
+    }
+    impl<T: Debug> Debug for BlackBoardRefMut<'_, T> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            (**self).fmt(f)
+        }
+    }
+
+    fn plain_ref<T>(value: &T) -> BlackBoardRef<'_, T> {
+        BlackBoardRef {
+            inner: RefInner::Plain(value),
+        }
+    }
+
+    fn plain_mut<T>(value: &mut T) -> BlackBoardRefMut<'_, T> {
+        BlackBoardRefMut {
+            inner: RefMutInner::Plain(value),
+        }
+    }
+
+    // Shares a blackboard value of the instance its node belongs to, created by `get_blackboard`
+    // or `SharedBlackBoardValue::downcast_to_blackboard_cell`. Values are locked while they're
+    // borrowed, so cells can be moved to other threads.
+    #[derive(Debug)]
+    pub struct BlackBoardCell<T> {
+        value: SharedBlackBoardValue,
+        _marker: PhantomData<fn() -> T>,
+    }
+    impl<T: BlackBoardType> BlackBoardCell<T> {
+        #[inline]
+        pub(crate) fn new(value: SharedBlackBoardValue) -> Self {
+            Self {
+                value,
+                _marker: PhantomData,
+            }
+        }
+        // Waits while another thread holds the value, panics if this thread already does, e.g.
+        // through another cell of the same blackboard.
+        #[inline]
+        pub fn as_ref(&self) -> BlackBoardRef<'_, T> {
+            BlackBoardRef::locked(self.value.borrow(), |value| {
+                value.downcast_ref::<T>().unwrap()
+            })
+        }
+        #[inline]
+        pub fn as_mut(&mut self) -> BlackBoardRefMut<'_, T> {
+            BlackBoardRefMut::locked(
+                self.value.borrow_mut(),
+                |value| value.downcast_ref::<T>().unwrap(),
+                |value| value.downcast_mut::<T>().unwrap(),
+            )
+        }
+        #[inline]
+        pub fn name(&self) -> &str {
+            self.value.bb_name()
+        }
+        #[inline]
+        pub fn set(&mut self, value: T) {
+            *self.as_mut() = value;
+        }
+    }
+    impl<U: BlackBoardType + Clone> BlackBoardCell<Option<U>> {
+        #[inline]
+        pub fn unwrap_ref(&self) -> BlackBoardRef<'_, U> {
+            BlackBoardRef::locked(self.value.borrow(), |value| {
+                value.downcast_ref::<Option<U>>().unwrap().as_ref().unwrap()
+            })
+        }
+        #[inline]
+        pub fn unwrap_mut(&mut self) -> BlackBoardRefMut<'_, U> {
+            BlackBoardRefMut::locked(
+                self.value.borrow_mut(),
+                |value| value.downcast_ref::<Option<U>>().unwrap().as_ref().unwrap(),
+                |value| value.downcast_mut::<Option<U>>().unwrap().as_mut().unwrap(),
+            )
+        }
     }
 }
-impl<T> DerefMut for BlackBoardCell<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.as_mut()
+
+impl<T: BlackBoardType + Clone> BlackBoardCell<T> {
+    pub fn get(&self) -> T {
+        T::clone(&self.as_ref())
     }
 }
-impl<U> BlackBoardCell<Option<U>> {
+impl<U: BlackBoardType + Clone> BlackBoardCell<Option<U>> {
     #[inline]
     pub fn is_some(&self) -> bool {
         self.as_ref().is_some()
@@ -109,18 +301,8 @@ impl<U> BlackBoardCell<Option<U>> {
     pub fn is_none(&self) -> bool {
         self.as_ref().is_none()
     }
-    #[inline]
-    pub fn unwrap_ref(&self) -> &U {
-        self.as_ref().as_ref().unwrap()
-    }
-    #[inline]
-    pub fn unwrap_mut(&mut self) -> &mut U {
-        self.as_mut().as_mut().unwrap()
-    }
-}
-impl<U: Clone> BlackBoardCell<Option<U>> {
     pub fn unwrap_value(&self) -> U {
-        self.as_ref().as_ref().unwrap().clone()
+        U::clone(&self.unwrap_ref())
     }
 }
 
@@ -164,7 +346,7 @@ impl<T: Clone> From<DummyMutable<T>> for DynamicCell<T> {
         Self::Immutable(value)
     }
 }
-impl<T: Clone> DynamicCell<T> {
+impl<T: BlackBoardType + Clone> DynamicCell<T> {
     #[inline]
     pub fn new(data: impl Into<Self>) -> Self {
         data.into()
@@ -174,17 +356,17 @@ impl<T: Clone> DynamicCell<T> {
         matches!(self, DynamicCell::Mutable(_))
     }
     #[inline]
-    pub fn as_ref(&self) -> &T {
+    pub fn as_ref(&self) -> BlackBoardRef<'_, T> {
         match self {
             DynamicCell::Mutable(cell) => cell.as_ref(),
-            DynamicCell::Immutable(cell) => cell.as_ref(),
+            DynamicCell::Immutable(cell) => plain_ref(cell.as_ref()),
         }
     }
     #[inline]
-    pub fn as_mut(&mut self) -> &mut T {
+    pub fn as_mut(&mut self) -> BlackBoardRefMut<'_, T> {
         match self {
             DynamicCell::Mutable(cell) => cell.as_mut(),
-            DynamicCell::Immutable(cell) => cell.as_mut(),
+            DynamicCell::Immutable(cell) => plain_mut(cell.as_mut()),
         }
     }
     #[inline]
@@ -209,18 +391,7 @@ impl<T: Clone> DynamicCell<T> {
         }
     }
 }
-impl<T: Clone> Deref for DynamicCell<T> {
-    type Target = T;
-    fn deref(&self) -> &Self::Target {
-        self.as_ref()
-    }
-}
-impl<T: Clone> DerefMut for DynamicCell<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        self.as_mut()
-    }
-}
-impl<T: Clone> DynamicCell<Option<T>> {
+impl<T: BlackBoardType + Clone> DynamicCell<Option<T>> {
     #[inline]
     pub fn is_some(&self) -> bool {
         self.as_ref().is_some()
@@ -230,16 +401,22 @@ impl<T: Clone> DynamicCell<Option<T>> {
         self.as_ref().is_none()
     }
     #[inline]
-    pub fn unwrap_ref(&self) -> &T {
-        self.as_ref().as_ref().unwrap()
+    pub fn unwrap_ref(&self) -> BlackBoardRef<'_, T> {
+        match self {
+            DynamicCell::Mutable(cell) => cell.unwrap_ref(),
+            DynamicCell::Immutable(cell) => plain_ref(cell.as_ref().as_ref().unwrap()),
+        }
     }
     #[inline]
-    pub fn unwrap_mut(&mut self) -> &mut T {
-        self.as_mut().as_mut().unwrap()
+    pub fn unwrap_mut(&mut self) -> BlackBoardRefMut<'_, T> {
+        match self {
+            DynamicCell::Mutable(cell) => cell.unwrap_mut(),
+            DynamicCell::Immutable(cell) => plain_mut(cell.as_mut().as_mut().unwrap()),
+        }
     }
     #[inline]
     pub fn unwrap_value(&self) -> T {
-        self.as_ref().as_ref().unwrap().clone()
+        T::clone(&self.unwrap_ref())
     }
 }
 
@@ -389,7 +566,7 @@ pub fn get_dynamic<T: BlackBoardType + ConvertFromStr + Clone>(
     }
 }
 
-pub trait BtNode: TreeNodeStatus + Debug {
+pub trait BtNode: TreeNodeStatus + Debug + MaybeSend {
     type Context;
     type World;
     type Entity;
@@ -412,6 +589,12 @@ pub trait BtNode: TreeNodeStatus + Debug {
         Status::Success
     }
 
+    // Called instead of `end` when the node is stopped while running, e.g. by an aborting
+    // parent or `BtInstance::halt`.
+    fn halted(&mut self, ctx: &mut Self::Context, world: &mut Self::World, entity: &Self::Entity) {
+        self.end(ctx, world, entity);
+    }
+
     fn tick(
         &mut self,
         _ctx: &mut Self::Context,
@@ -438,14 +621,57 @@ pub trait BtNode: TreeNodeStatus + Debug {
         status
     }
 
+    // Utility of the option the node stands for, used when it is registered as the scorer of
+    // a `UtilitySelect` child. Not ticked in that role.
+    fn score(
+        &mut self,
+        _ctx: &mut Self::Context,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) -> f64 {
+        0.0
+    }
+
     #[inline]
     fn node_name(&self) -> &'static str {
         behavior_util::simplified_name::<Self>()
     }
 
+    // Snapshot hooks, implement `BtNodeState` and forward to `encode_node_state` and
+    // `decode_node_state` to keep the node's own state across `BtInstance::snapshot`.
+    fn encode_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn decode_state(&mut self, _data: &[u8]) -> bool {
+        false
+    }
+
     // fn clone_box(&self) -> Box<dyn BTNode>;
 }
 
+pub trait BtNodeState {
+    type State: bincode::Encode + bincode::Decode;
+
+    fn save_state(&self) -> Self::State;
+
+    fn load_state(&mut self, state: Self::State);
+}
+
+pub fn encode_node_state<T: BtNodeState>(node: &T) -> Option<Vec<u8>> {
+    bincode::encode_to_vec(node.save_state(), bincode::config::standard()).ok()
+}
+
+pub fn decode_node_state<T: BtNodeState>(node: &mut T, data: &[u8]) -> bool {
+    match bincode::decode_from_slice(data, bincode::config::standard()) {
+        Ok((state, _)) => {
+            node.load_state(state);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
 pub type Executor<C, W, E> = Box<dyn BtNode<Context = C, World = W, Entity = E>>;
 #[derive(Debug)]
 pub struct BtAction<C, W, E> {
@@ -511,16 +737,44 @@ impl<C, W, E> TreeNode for BtAction<C, W, E> {
         self.executor.execute(ctx, world, entity)
     }
 
-    fn reset(
+    fn action_score(
         &mut self,
         ctx: &mut Self::BlackBoardContext,
         world: &mut Self::World,
         entity: &Self::Entity,
+    ) -> f64 {
+        self.executor.score(ctx, world, entity)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
     ) {
-        self.executor.end(ctx, world, entity);
+        self.executor
+            .halted(blackboard.context_mut(), world, entity);
         self.reset_status();
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(self.get_status(), self.executor.encode_state()))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        let (status, data): (Status, Option<Vec<u8>>) = state.decode()?;
+        restore_status(self, status);
+        match data {
+            Some(data) if !self.executor.decode_state(&data) => {
+                Err(BehaviorError::RestoreSnapshotFailed {
+                    node_index: self.node_index,
+                    error_info: format!("decode {} state failed", self.node_name()),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn node_index(&self) -> i32 {
         self.node_index
     }
@@ -548,6 +802,22 @@ impl<C, W, E> TreeNode for BtAction<C, W, E> {
     > {
         Vec::new()
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::new()
+    }
 }
 
 pub trait BtNodeGenerator {
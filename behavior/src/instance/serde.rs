@@ -1,15 +1,21 @@
-use super::factory::{BtFactory, BtInstance, FastHashMap, ParentTreeLink, TreeState};
+use super::factory::{BtFactory, FastHashMap, ParentTreeLink, TreeState};
 use super::node::BtAction;
 use crate::template::{
-    ActionNode, BranchCondNode, BranchNode, ForceFailureNode, ForceSuccessNode, IfNode,
-    IfThenElseNode, InvertNode, LimiterNode, ParallelOrNode, ParallelSelectNode,
-    ParallelSequenceNode, PriorityBranchNode, RepeatNode, RetryNode, SelectNode, SequenceNode,
-    SubTreeNode, TimeoutNode, UntilFailureNode, UntilSuccessNode, WeightSelectNode, WhileNode,
+    ActionNode, BlackBoardOperand, BranchCondNode, BranchNode, ChanceNode, ClearBlackBoardNode,
+    CompareBlackBoardNode, CompareOp, CooldownGroup, CooldownNode, EmitEventNode, ForEachNode,
+    ForceFailureNode, ForceSuccessNode, IfNode, IfThenElseNode, InvertNode, IsBlackBoardSetNode,
+    LimiterNode, LimiterWindow, MultipleBranchNode, OnEventNode, ParallelNode, ParallelOrNode,
+    ParallelSelectNode, ParallelSequenceNode, PriorityBranchNode, RandomSelectNode,
+    RandomSequenceNode, ReactiveSelectNode, ReactiveSequenceNode, RepeatNode, RetryNode,
+    RoundRobinSelectNode, Scorer, SelectNode, SelectWeight, SemaphoreNode, SequenceNode,
+    SetBlackBoardNode, SubTreeNode, SwitchCases, SwitchNode, TimeoutNode, TimeoutTicksNode,
+    UntilFailureNode, UntilSuccessNode, UtilitySelectNode, WeightSelectNode, WhileNode,
 };
 use crate::{
     AlwaysFailureNode, AlwaysRunningNode, AlwaysSuccessNode, Behavior, BehaviorError, BlackBoard,
-    BlackBoardMap, ImmediateRepeatNode, ImmediateRetryNode, LogNode, ParallelAndNode,
-    WaitForeverNode, WaitNode,
+    BlackBoardMap, BlackBoardOps, ImmediateRepeatNode, ImmediateRetryNode, LogNode, MaybeSend,
+    NodeLocation, ParallelAndNode, SharedBlackBoardValue, UtilityScorer, WaitForEventNode,
+    WaitForeverNode, WaitNode, WaitTicksNode,
 };
 use ahash::HashMapExt;
 use serde::{Deserialize, Serialize};
@@ -37,7 +43,7 @@ pub struct TreeTemplate {
 }
 
 impl TreeTemplate {
-    fn to_tree_action<C: Unpin + Default + 'static, W: 'static, E: 'static>(
+    fn to_tree_action<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static>(
         factory: &BtFactory<C, W, E>,
         tree_name: &str,
         tree_index: i32,
@@ -68,7 +74,171 @@ impl TreeTemplate {
         ))
     }
 
-    fn to_tree_state<C: Unpin + Default + 'static, W: 'static, E: 'static>(
+    fn to_blackboard_value<'a>(
+        location: &NodeLocation,
+        blackboard_ref_name: &str,
+        blackboard_name: &str,
+        blackboard_map: &'a BlackBoardMap,
+    ) -> Result<&'a SharedBlackBoardValue, BehaviorError> {
+        blackboard_map
+            .get(blackboard_name)
+            .ok_or_else(|| BehaviorError::BlackBoardNotFound {
+                location: location.clone(),
+                blackboard_ref_name: blackboard_ref_name.to_string(),
+                blackboard_name: blackboard_name.to_string(),
+            })
+    }
+
+    fn to_blackboard_ops<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static>(
+        factory: &BtFactory<C, W, E>,
+        location: &NodeLocation,
+        value: &SharedBlackBoardValue,
+        operation: &str,
+    ) -> Result<BlackBoardOps, BehaviorError> {
+        factory.blackboard_ops(value.bb_type()).ok_or_else(|| {
+            BehaviorError::BlackBoardOperationUnsupported {
+                location: location.clone(),
+                blackboard_name: value.bb_name().to_string(),
+                blackboard_type: value.bb_type().to_string(),
+                operation: operation.to_string(),
+            }
+        })
+    }
+
+    // `<name>` refers to another blackboard value of the same type, anything else is a literal.
+    fn to_blackboard_operand(
+        location: &NodeLocation,
+        value: &SharedBlackBoardValue,
+        ops: &BlackBoardOps,
+        operand_str: &str,
+        blackboard_map: &BlackBoardMap,
+    ) -> Result<BlackBoardOperand, BehaviorError> {
+        match operand_str
+            .strip_prefix('<')
+            .and_then(|name| name.strip_suffix('>'))
+        {
+            Some(operand_name) => {
+                let operand =
+                    Self::to_blackboard_value(location, "operand", operand_name, blackboard_map)?;
+                if operand.bb_type() != value.bb_type() {
+                    return Err(BehaviorError::BlackBoardDowncastFailed {
+                        location: location.clone(),
+                        blackboard_ref_name: "operand".to_string(),
+                        blackboard_name: operand_name.to_string(),
+                        blackboard_type: value.bb_type().to_string(),
+                    });
+                }
+                Ok(BlackBoardOperand::BlackBoard(operand.share()))
+            }
+            None => ops
+                .parse(operand_str)
+                .map(BlackBoardOperand::Literal)
+                .ok_or_else(|| BehaviorError::BlackBoardLiteralParseFailed {
+                    location: location.clone(),
+                    blackboard_name: value.bb_name().to_string(),
+                    blackboard_type: value.bb_type().to_string(),
+                    literal: operand_str.to_string(),
+                }),
+        }
+    }
+
+    fn to_compare_blackboard_node<
+        C: Unpin + Default + MaybeSend + 'static,
+        W: 'static,
+        E: 'static,
+    >(
+        factory: &BtFactory<C, W, E>,
+        tree_name: &str,
+        tree_index: i32,
+        tree_depth: i32,
+        node_index: i32,
+        blackboard_name: &str,
+        op: CompareOp,
+        operand_str: &str,
+        blackboard_map: &BlackBoardMap,
+    ) -> Result<TreeState<C, W, E>, BehaviorError> {
+        let location = NodeLocation::new(
+            tree_name,
+            tree_index,
+            tree_depth,
+            "CompareBlackBoardNode",
+            node_index,
+        );
+        let operation = format!("{:?}", op);
+        let value = Self::to_blackboard_value(&location, "value", blackboard_name, blackboard_map)?;
+        let ops = Self::to_blackboard_ops(factory, &location, value, &operation)?;
+        let supported = if op.is_ordering() {
+            ops.has_partial_cmp()
+        } else {
+            ops.has_eq()
+        };
+        if !supported {
+            return Err(BehaviorError::BlackBoardOperationUnsupported {
+                location,
+                blackboard_name: blackboard_name.to_string(),
+                blackboard_type: value.bb_type().to_string(),
+                operation,
+            });
+        }
+        let operand =
+            Self::to_blackboard_operand(&location, value, &ops, operand_str, blackboard_map)?;
+        Ok(Box::new(CompareBlackBoardNode::new(
+            node_index,
+            op,
+            ops,
+            value.share(),
+            operand,
+        )))
+    }
+
+    fn to_set_blackboard_node<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static>(
+        factory: &BtFactory<C, W, E>,
+        tree_name: &str,
+        tree_index: i32,
+        tree_depth: i32,
+        node_index: i32,
+        blackboard_name: &str,
+        operand_str: &str,
+        blackboard_map: &BlackBoardMap,
+    ) -> Result<TreeState<C, W, E>, BehaviorError> {
+        let location = NodeLocation::new(
+            tree_name,
+            tree_index,
+            tree_depth,
+            "SetBlackBoardNode",
+            node_index,
+        );
+        let value = Self::to_blackboard_value(&location, "value", blackboard_name, blackboard_map)?;
+        let ops = Self::to_blackboard_ops(factory, &location, value, "Set")?;
+        let operand =
+            Self::to_blackboard_operand(&location, value, &ops, operand_str, blackboard_map)?;
+        Ok(Box::new(SetBlackBoardNode::new(
+            node_index,
+            value.share(),
+            operand,
+        )))
+    }
+
+    fn to_event_payload(
+        tree_name: &str,
+        tree_index: i32,
+        tree_depth: i32,
+        node_name: &str,
+        node_index: i32,
+        payload_name: Option<&String>,
+        blackboard_map: &BlackBoardMap,
+    ) -> Result<Option<SharedBlackBoardValue>, BehaviorError> {
+        payload_name
+            .map(|payload_name| {
+                let location =
+                    NodeLocation::new(tree_name, tree_index, tree_depth, node_name, node_index);
+                Self::to_blackboard_value(&location, "payload", payload_name, blackboard_map)
+                    .map(SharedBlackBoardValue::share)
+            })
+            .transpose()
+    }
+
+    fn to_tree_state<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static>(
         factory: &BtFactory<C, W, E>,
         tree_name: &str,
         tree_index: i32,
@@ -96,6 +266,10 @@ impl TreeTemplate {
                 let wait_node = WaitNode::new(*node_index, *wait_time);
                 Ok(Box::new(wait_node))
             }
+            Behavior::WaitTicks(node_index, ticks) => {
+                let wait_ticks_node = WaitTicksNode::new(*node_index, *ticks);
+                Ok(Box::new(wait_ticks_node))
+            }
             Behavior::WaitForever(node_index) => {
                 let wait_forever_node = WaitForeverNode::new(*node_index);
                 Ok(Box::new(wait_forever_node))
@@ -112,6 +286,111 @@ impl TreeTemplate {
                 let always_running_node = AlwaysRunningNode::new(*node_index);
                 Ok(Box::new(always_running_node))
             }
+            Behavior::CompareBlackBoard(node_index, blackboard_name, op, operand_str) => {
+                Self::to_compare_blackboard_node(
+                    factory,
+                    tree_name,
+                    tree_index,
+                    tree_depth,
+                    *node_index,
+                    blackboard_name,
+                    *op,
+                    operand_str,
+                    blackboard_map,
+                )
+            }
+            Behavior::SetBlackBoard(node_index, blackboard_name, operand_str) => {
+                Self::to_set_blackboard_node(
+                    factory,
+                    tree_name,
+                    tree_index,
+                    tree_depth,
+                    *node_index,
+                    blackboard_name,
+                    operand_str,
+                    blackboard_map,
+                )
+            }
+            Behavior::ClearBlackBoard(node_index, blackboard_name) => {
+                let location = NodeLocation::new(
+                    tree_name,
+                    tree_index,
+                    tree_depth,
+                    "ClearBlackBoardNode",
+                    *node_index,
+                );
+                let value =
+                    Self::to_blackboard_value(&location, "value", blackboard_name, blackboard_map)?;
+                let ops = Self::to_blackboard_ops(factory, &location, value, "Clear")?;
+                if !ops.has_clear() {
+                    return Err(BehaviorError::BlackBoardOperationUnsupported {
+                        location,
+                        blackboard_name: blackboard_name.to_string(),
+                        blackboard_type: value.bb_type().to_string(),
+                        operation: "Clear".to_string(),
+                    });
+                }
+                Ok(Box::new(ClearBlackBoardNode::new(
+                    *node_index,
+                    ops,
+                    value.share(),
+                )))
+            }
+            Behavior::IsBlackBoardSet(node_index, blackboard_name) => {
+                let location = NodeLocation::new(
+                    tree_name,
+                    tree_index,
+                    tree_depth,
+                    "IsBlackBoardSetNode",
+                    *node_index,
+                );
+                let value =
+                    Self::to_blackboard_value(&location, "value", blackboard_name, blackboard_map)?;
+                match factory.blackboard_ops(value.bb_type()) {
+                    Some(ops) if ops.has_is_set() => Ok(Box::new(IsBlackBoardSetNode::new(
+                        *node_index,
+                        ops,
+                        value.share(),
+                    ))),
+                    _ => Err(BehaviorError::BlackBoardNotOption {
+                        location,
+                        blackboard_name: blackboard_name.to_string(),
+                        blackboard_type: value.bb_type().to_string(),
+                    }),
+                }
+            }
+            Behavior::EmitEvent(node_index, event_name, payload_name) => {
+                let emit_event_node = EmitEventNode::new(
+                    *node_index,
+                    event_name.to_string(),
+                    Self::to_event_payload(
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        "EmitEventNode",
+                        *node_index,
+                        payload_name.as_ref(),
+                        blackboard_map,
+                    )?,
+                );
+                Ok(Box::new(emit_event_node))
+            }
+            Behavior::WaitForEvent(node_index, event_name, payload_name) => {
+                let wait_for_event_node = WaitForEventNode::new(
+                    *node_index,
+                    event_name.to_string(),
+                    Self::to_event_payload(
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        "WaitForEventNode",
+                        *node_index,
+                        payload_name.as_ref(),
+                        blackboard_map,
+                    )?,
+                );
+                Ok(Box::new(wait_for_event_node))
+            }
             #[cfg(feature = "expression_node")]
             Behavior::Expression(node_index, expression_str) => {
                 use crate::template::ExpressionNode;
@@ -122,6 +401,7 @@ impl TreeTemplate {
                     *node_index,
                     expression_str,
                     blackboard_map,
+                    factory.expression_functions(),
                 )?;
                 Ok(Box::new(expression_node))
             }
@@ -244,6 +524,131 @@ impl TreeTemplate {
                 );
                 Ok(Box::new(sequence_node))
             }
+            Behavior::ReactiveSelect(node_index, children) => {
+                let reactive_select_node = ReactiveSelectNode::new(
+                    *node_index,
+                    children
+                        .into_iter()
+                        .map(|child| {
+                            Self::to_tree_state(
+                                factory,
+                                tree_name,
+                                tree_index,
+                                tree_depth,
+                                child,
+                                blackboard_map,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+                Ok(Box::new(reactive_select_node))
+            }
+            Behavior::ReactiveSequence(node_index, children) => {
+                let reactive_sequence_node = ReactiveSequenceNode::new(
+                    *node_index,
+                    children
+                        .into_iter()
+                        .map(|child| {
+                            Self::to_tree_state(
+                                factory,
+                                tree_name,
+                                tree_index,
+                                tree_depth,
+                                child,
+                                blackboard_map,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+                Ok(Box::new(reactive_sequence_node))
+            }
+            Behavior::RandomSelect(node_index, select_weights, children) => {
+                let select_weights = select_weights
+                    .as_ref()
+                    .map(|select_weights| {
+                        SelectWeight::parse_weights(
+                            tree_name,
+                            tree_index,
+                            tree_depth,
+                            *node_index,
+                            select_weights,
+                            blackboard_map,
+                            factory,
+                        )
+                    })
+                    .transpose()?;
+                let random_select_node = RandomSelectNode::new(
+                    *node_index,
+                    select_weights,
+                    children
+                        .into_iter()
+                        .map(|child| {
+                            Self::to_tree_state(
+                                factory,
+                                tree_name,
+                                tree_index,
+                                tree_depth,
+                                child,
+                                blackboard_map,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+                Ok(Box::new(random_select_node))
+            }
+            Behavior::RandomSequence(node_index, select_weights, children) => {
+                let select_weights = select_weights
+                    .as_ref()
+                    .map(|select_weights| {
+                        SelectWeight::parse_weights(
+                            tree_name,
+                            tree_index,
+                            tree_depth,
+                            *node_index,
+                            select_weights,
+                            blackboard_map,
+                            factory,
+                        )
+                    })
+                    .transpose()?;
+                let random_sequence_node = RandomSequenceNode::new(
+                    *node_index,
+                    select_weights,
+                    children
+                        .into_iter()
+                        .map(|child| {
+                            Self::to_tree_state(
+                                factory,
+                                tree_name,
+                                tree_index,
+                                tree_depth,
+                                child,
+                                blackboard_map,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+                Ok(Box::new(random_sequence_node))
+            }
+            Behavior::RoundRobinSelect(node_index, children) => {
+                let round_robin_select_node = RoundRobinSelectNode::new(
+                    *node_index,
+                    children
+                        .into_iter()
+                        .map(|child| {
+                            Self::to_tree_state(
+                                factory,
+                                tree_name,
+                                tree_index,
+                                tree_depth,
+                                child,
+                                blackboard_map,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+                Ok(Box::new(round_robin_select_node))
+            }
             Behavior::Branch(node_index, can_abort, branch_cond, branch_children) => {
                 let branch_node = BranchNode::new(
                     *node_index,
@@ -326,6 +731,89 @@ impl TreeTemplate {
                 );
                 Ok(Box::new(branch_node))
             }
+            Behavior::Switch(node_index, can_abort, blackboard_name, cases, default) => {
+                let value = blackboard_map.get(blackboard_name).ok_or_else(|| {
+                    BehaviorError::BlackBoardNotFound {
+                        location: NodeLocation::new(
+                            tree_name,
+                            tree_index,
+                            tree_depth,
+                            "SwitchNode",
+                            *node_index,
+                        ),
+                        blackboard_ref_name: "switch".to_string(),
+                        blackboard_name: blackboard_name.to_string(),
+                    }
+                })?;
+                let case_strs: Vec<_> = cases.iter().map(|(case, _)| case.as_str()).collect();
+                let switch_cases = SwitchCases::new(
+                    tree_name,
+                    tree_index,
+                    tree_depth,
+                    *node_index,
+                    value,
+                    &case_strs,
+                    factory.switch_enum_funcs(value.bb_type()),
+                )?;
+                let children = cases
+                    .iter()
+                    .map(|(_, child)| child)
+                    .chain(std::iter::once(default.as_ref()))
+                    .map(|child| {
+                        Self::to_tree_state(
+                            factory,
+                            tree_name,
+                            tree_index,
+                            tree_depth,
+                            child,
+                            blackboard_map,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let switch_node = SwitchNode::new(
+                    *node_index,
+                    *can_abort,
+                    value.share(),
+                    switch_cases,
+                    children,
+                );
+                Ok(Box::new(switch_node))
+            }
+            Behavior::MultipleBranch(
+                node_index,
+                can_abort,
+                policy,
+                branch_cond,
+                branch_children,
+            ) => {
+                let branch_node = MultipleBranchNode::new(
+                    *node_index,
+                    *can_abort,
+                    *policy,
+                    Self::to_tree_state(
+                        factory,
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        branch_cond,
+                        blackboard_map,
+                    )?,
+                    branch_children
+                        .into_iter()
+                        .map(|child| {
+                            Self::to_tree_state(
+                                factory,
+                                tree_name,
+                                tree_index,
+                                tree_depth,
+                                child,
+                                blackboard_map,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+                Ok(Box::new(branch_node))
+            }
             Behavior::ParallelAnd(node_index, children) => {
                 let parallel_and_node = ParallelAndNode::new(
                     *node_index,
@@ -402,10 +890,46 @@ impl TreeTemplate {
                 );
                 Ok(Box::new(parallel_select_node))
             }
+            Behavior::Parallel(
+                node_index,
+                success_threshold,
+                failure_threshold,
+                policy,
+                children,
+            ) => {
+                let parallel_node = ParallelNode::new(
+                    *node_index,
+                    *success_threshold,
+                    *failure_threshold,
+                    *policy,
+                    children
+                        .into_iter()
+                        .map(|child| {
+                            Self::to_tree_state(
+                                factory,
+                                tree_name,
+                                tree_index,
+                                tree_depth,
+                                child,
+                                blackboard_map,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                );
+                Ok(Box::new(parallel_node))
+            }
             Behavior::WeightSelect(node_index, select_weights, select_children) => {
                 let branch_node = WeightSelectNode::new(
                     *node_index,
-                    select_weights,
+                    SelectWeight::parse_weights(
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        *node_index,
+                        select_weights,
+                        blackboard_map,
+                        factory,
+                    )?,
                     select_children
                         .into_iter()
                         .map(|child| {
@@ -422,6 +946,58 @@ impl TreeTemplate {
                 );
                 Ok(Box::new(branch_node))
             }
+            Behavior::UtilitySelect(node_index, reevaluate, hysteresis, scored_children) => {
+                let (scorers, children) = scored_children
+                    .into_iter()
+                    .map(|(scorer, child)| {
+                        let scorer = match scorer {
+                            UtilityScorer::Constant(score) => Scorer::Constant(*score),
+                            #[cfg(feature = "expression_node")]
+                            UtilityScorer::Expression(expression_str) => {
+                                Scorer::Expression(crate::template::ExpressionScorer::new(
+                                    tree_name,
+                                    tree_index,
+                                    tree_depth,
+                                    *node_index,
+                                    expression_str,
+                                    blackboard_map,
+                                    factory.expression_functions(),
+                                )?)
+                            }
+                            UtilityScorer::Action(action_template) => {
+                                Scorer::Action(Self::to_tree_action(
+                                    factory,
+                                    tree_name,
+                                    tree_index,
+                                    tree_depth,
+                                    node_index,
+                                    action_template,
+                                    blackboard_map,
+                                )?)
+                            }
+                        };
+                        let child = Self::to_tree_state(
+                            factory,
+                            tree_name,
+                            tree_index,
+                            tree_depth,
+                            child,
+                            blackboard_map,
+                        )?;
+                        Ok((scorer, child))
+                    })
+                    .collect::<Result<Vec<_>, BehaviorError>>()?
+                    .into_iter()
+                    .unzip();
+                let utility_select_node = UtilitySelectNode::new(
+                    *node_index,
+                    *reevaluate,
+                    *hysteresis,
+                    scorers,
+                    children,
+                );
+                Ok(Box::new(utility_select_node))
+            }
             Behavior::Invert(node_index, child) => {
                 let invert_node = InvertNode::new(
                     *node_index,
@@ -523,6 +1099,147 @@ impl TreeTemplate {
                 );
                 Ok(Box::new(timeout_node))
             }
+            Behavior::TimeoutTicks(node_index, timeout, child) => {
+                let timeout_node = TimeoutTicksNode::new(
+                    *node_index,
+                    *timeout,
+                    Self::to_tree_state(
+                        factory,
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        child,
+                        blackboard_map,
+                    )?,
+                );
+                Ok(Box::new(timeout_node))
+            }
+            Behavior::LimiterTicks(node_index, ticks, limit, child) => {
+                let limiter_node = LimiterNode::with_window(
+                    *node_index,
+                    LimiterWindow::Ticks,
+                    *ticks,
+                    *limit,
+                    Self::to_tree_state(
+                        factory,
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        child,
+                        blackboard_map,
+                    )?,
+                );
+                Ok(Box::new(limiter_node))
+            }
+            Behavior::Cooldown(node_index, duration, trigger, group, child) => {
+                let group = group
+                    .as_ref()
+                    .map(|group| {
+                        CooldownGroup::new(
+                            tree_name,
+                            tree_index,
+                            tree_depth,
+                            *node_index,
+                            group,
+                            blackboard_map,
+                        )
+                    })
+                    .transpose()?;
+                let cooldown_node = CooldownNode::new(
+                    *node_index,
+                    *duration,
+                    *trigger,
+                    group,
+                    Self::to_tree_state(
+                        factory,
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        child,
+                        blackboard_map,
+                    )?,
+                );
+                Ok(Box::new(cooldown_node))
+            }
+            Behavior::ForEach(
+                node_index,
+                container_name,
+                item_name,
+                policy,
+                one_per_tick,
+                child,
+            ) => {
+                let location = NodeLocation::new(
+                    tree_name,
+                    tree_index,
+                    tree_depth,
+                    "ForEachNode",
+                    *node_index,
+                );
+                let container = Self::to_blackboard_value(
+                    &location,
+                    "container",
+                    container_name,
+                    blackboard_map,
+                )?;
+                let item = Self::to_blackboard_value(&location, "item", item_name, blackboard_map)?;
+                let (item_type, read_item) = factory
+                    .foreach_container(container.bb_type())
+                    .ok_or_else(|| BehaviorError::BlackBoardOperationUnsupported {
+                        location: location.clone(),
+                        blackboard_name: container_name.to_string(),
+                        blackboard_type: container.bb_type().to_string(),
+                        operation: "ForEach".to_string(),
+                    })?;
+                if item.bb_type() != item_type {
+                    return Err(BehaviorError::BlackBoardDowncastFailed {
+                        location,
+                        blackboard_ref_name: "item".to_string(),
+                        blackboard_name: item_name.to_string(),
+                        blackboard_type: item_type.to_string(),
+                    });
+                }
+                let foreach_node = ForEachNode::new(
+                    *node_index,
+                    container.share(),
+                    item.share(),
+                    read_item,
+                    *policy,
+                    *one_per_tick,
+                    Self::to_tree_state(
+                        factory,
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        child,
+                        blackboard_map,
+                    )?,
+                );
+                Ok(Box::new(foreach_node))
+            }
+            Behavior::Chance(node_index, probability_str, child) => {
+                let chance_node = ChanceNode::new(
+                    *node_index,
+                    SelectWeight::parse(
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        *node_index,
+                        probability_str,
+                        blackboard_map,
+                        factory,
+                    )?,
+                    Self::to_tree_state(
+                        factory,
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        child,
+                        blackboard_map,
+                    )?,
+                );
+                Ok(Box::new(chance_node))
+            }
             Behavior::Repeat(node_index, repeat_limit, child) => {
                 let timeout_node = RepeatNode::new(
                     *node_index,
@@ -583,6 +1300,58 @@ impl TreeTemplate {
                 );
                 Ok(Box::new(retry_node))
             }
+            Behavior::OnEvent(node_index, event_name, payload_name, child) => {
+                let on_event_node = OnEventNode::new(
+                    *node_index,
+                    event_name.to_string(),
+                    Self::to_event_payload(
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        "OnEventNode",
+                        *node_index,
+                        payload_name.as_ref(),
+                        blackboard_map,
+                    )?,
+                    Self::to_tree_state(
+                        factory,
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        child,
+                        blackboard_map,
+                    )?,
+                );
+                Ok(Box::new(on_event_node))
+            }
+            Behavior::Semaphore(node_index, semaphore_name, wait, child) => {
+                let semaphore = factory.semaphore(semaphore_name).ok_or_else(|| {
+                    BehaviorError::SemaphoreNotFound {
+                        location: NodeLocation::new(
+                            tree_name,
+                            tree_index,
+                            tree_depth,
+                            "SemaphoreNode",
+                            *node_index,
+                        ),
+                        semaphore_name: semaphore_name.to_string(),
+                    }
+                })?;
+                let semaphore_node = SemaphoreNode::new(
+                    *node_index,
+                    semaphore.clone(),
+                    *wait,
+                    Self::to_tree_state(
+                        factory,
+                        tree_name,
+                        tree_index,
+                        tree_depth,
+                        child,
+                        blackboard_map,
+                    )?,
+                );
+                Ok(Box::new(semaphore_node))
+            }
             Behavior::Log(node_index, blackboards_str, child) => {
                 let log_node = LogNode::new(
                     *node_index,
@@ -600,12 +1369,7 @@ impl TreeTemplate {
                 Ok(Box::new(log_node))
             }
             Behavior::SubTree(node_index, subtree_name, parent_ref_map) => {
-                let BtInstance {
-                    tree_name,
-                    tree_state,
-                    tree_blackboard,
-                    ..
-                } = match factory.inner_create_tree_instance(
+                let (tree_blackboard, tree_state) = match factory.inner_create_tree(
                     subtree_name,
                     *node_index,
                     tree_depth + 1,
@@ -623,16 +1387,32 @@ impl TreeTemplate {
                             create_error: Box::new(error),
                         })
                     }
-                    Ok(instance) => instance,
+                    Ok(tree) => tree,
                 };
-                let subtree_node =
-                    SubTreeNode::new(*node_index, tree_name, tree_blackboard, tree_state);
+                let subtree_node = SubTreeNode::new(
+                    *node_index,
+                    subtree_name.to_string(),
+                    tree_blackboard,
+                    tree_state,
+                );
                 Ok(Box::new(subtree_node))
             }
         }
     }
 
-    pub fn to_instance<C: Unpin + Default + 'static, W: 'static, E: 'static>(
+    pub(crate) fn subtree_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        let mut stack = vec![&self.tree_structure];
+        while let Some(behavior) = stack.pop() {
+            if let Behavior::SubTree(_, subtree_name, _) = behavior {
+                names.push(subtree_name.as_str());
+            }
+            stack.extend(behavior.children());
+        }
+        names
+    }
+
+    pub fn to_instance<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static>(
         &self,
         factory: &BtFactory<C, W, E>,
         tree_name: &str,
@@ -653,7 +1433,7 @@ impl TreeTemplate {
         Ok((tree_blackboard, tree_state))
     }
 
-    pub fn to_tree_blackboard<C: Unpin + Default + 'static, W: 'static, E: 'static>(
+    pub fn to_tree_blackboard<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static>(
         &self,
         factory: &BtFactory<C, W, E>,
         tree_name: &str,
@@ -675,12 +1455,14 @@ impl TreeTemplate {
                 )?;
             }
         }
-        Ok(BlackBoard::new(
+        let mut tree_blackboard = BlackBoard::new(
             C::default(),
             tree_name.to_string(),
             tree_index,
             tree_depth,
             blackboard_map,
-        ))
+        );
+        tree_blackboard.set_clock(factory.clock().clone());
+        Ok(tree_blackboard)
     }
 }
@@ -10,3 +10,6 @@ cfg_gen_editor_data! {
     pub use instance::factory::{EditorEnumDataGenerator, EditorNodeDataGenerator};
 }
 pub use instance::*;
+cfg_expression_node! {
+    pub use evalexpr;
+}
@@ -44,3 +44,21 @@ macro_rules! cfg_expression_node {
         )*
     }
 }
+
+macro_rules! cfg_thread_safe {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "thread_safe")]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_not_thread_safe {
+    ($($item:item)*) => {
+        $(
+            #[cfg(not(feature = "thread_safe"))]
+            $item
+        )*
+    }
+}
@@ -1,18 +1,41 @@
+macro_rules! notify_status_change {
+    ($self:ident, $blackboard:ident, $prev_status:expr, $status:expr) => {
+        if $blackboard.is_observed() && $prev_status != $status {
+            $blackboard.notify_status_change($self.index, $self.node_name(), $prev_status, $status);
+        }
+    };
+}
+
+macro_rules! reset_status {
+    ($self:ident, $blackboard:ident) => {{
+        $self.reset_status();
+        if $blackboard.is_observed() {
+            $blackboard.notify_reset($self.index, $self.node_name());
+        }
+    }};
+}
+
 cfg_tree_visualization! {
     macro_rules! set_status {
         ($self:ident, $blackboard:ident, $status:expr) => {{
-            $self.set_status($status);
-            $blackboard.update_node_status($self.index, $status);
-            $status
+            let status = $status;
+            let prev_status = $self.get_status();
+            $self.set_status(status);
+            $blackboard.update_node_status($self.index, status);
+            notify_status_change!($self, $blackboard, prev_status, status);
+            status
         }};
     }
 
     macro_rules! set_subtree_status {
         ($self:ident, $blackboard:ident, $status:expr) => {{
-            $self.set_status($status);
-            $blackboard.update_node_status($self.index, $status);
+            let status = $status;
+            let prev_status = $self.get_status();
+            $self.set_status(status);
+            $blackboard.update_node_status($self.index, status);
             $blackboard.update_children_visualization($self.blackboard.visualization_ref().clone());
-            $status
+            notify_status_change!($self, $blackboard, prev_status, status);
+            status
         }};
     }
 }
@@ -20,15 +43,21 @@ cfg_tree_visualization! {
 cfg_not_tree_visualization! {
     macro_rules! set_status {
         ($self:ident, $blackboard:ident, $status:expr) => {{
-            $self.set_status($status);
-            $status
+            let status = $status;
+            let prev_status = $self.get_status();
+            $self.set_status(status);
+            notify_status_change!($self, $blackboard, prev_status, status);
+            status
         }};
     }
 
     macro_rules! set_subtree_status {
         ($self:ident, $blackboard:ident, $status:expr) => {{
-            $self.set_status($status);
-            $status
+            let status = $status;
+            let prev_status = $self.get_status();
+            $self.set_status(status);
+            notify_status_change!($self, $blackboard, prev_status, status);
+            status
         }};
     }
 }
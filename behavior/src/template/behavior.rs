@@ -1,15 +1,36 @@
+use super::{CompareOp, CooldownTrigger, ForEachPolicy, MultipleBranchPolicy, ParallelPolicy};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+// Scores a child of `UtilitySelect`, `Action` is a registered node providing `BtNode::score`.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub enum UtilityScorer<A> {
+    Constant(f64),
+    #[cfg(feature = "expression_node")]
+    Expression(String),
+    Action(A),
+}
+
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub enum Behavior<A> {
     // Leaf Node
     Action(i32, A),
     Wait(i32, i64),
+    // counts the ticks the node receives instead of milliseconds, as the other `Ticks` nodes.
+    WaitTicks(i32, i64),
     WaitForever(i32),
     AlwaysSuccess(i32),
     AlwaysFailure(i32),
     AlwaysRunning(i32),
+    // blackboard name, op, `<name>` of another blackboard value or a literal of the same type.
+    CompareBlackBoard(i32, String, CompareOp, String),
+    SetBlackBoard(i32, String, String),
+    // resets a blackboard value, an `Option<T>` to None and other types to their default value.
+    ClearBlackBoard(i32, String),
+    IsBlackBoardSet(i32, String),
+    // event name, blackboard value the payload is read from or written to.
+    EmitEvent(i32, String, Option<String>),
+    WaitForEvent(i32, String, Option<String>),
     #[cfg(feature = "expression_node")]
     Expression(i32, String),
     // Control Node
@@ -24,14 +45,37 @@ pub enum Behavior<A> {
     While(i32, Box<Behavior<A>>, Vec<Behavior<A>>),
     Select(i32, Vec<Behavior<A>>),
     Sequence(i32, Vec<Behavior<A>>),
+    ReactiveSelect(i32, Vec<Behavior<A>>),
+    ReactiveSequence(i32, Vec<Behavior<A>>),
+    // optional weights as in `WeightSelect`, uniform without.
+    RandomSelect(i32, Option<String>, Vec<Behavior<A>>),
+    RandomSequence(i32, Option<String>, Vec<Behavior<A>>),
+    RoundRobinSelect(i32, Vec<Behavior<A>>),
     Branch(i32, bool, Box<Behavior<A>>, Vec<Behavior<A>>),
     PriorityBranch(i32, bool, String, Box<Behavior<A>>, Vec<Behavior<A>>),
     BranchCond(i32, Vec<Behavior<A>>),
+    // can_abort, blackboard name, cases, default child.
+    Switch(
+        i32,
+        bool,
+        String,
+        Vec<(String, Behavior<A>)>,
+        Box<Behavior<A>>,
+    ),
+    MultipleBranch(
+        i32,
+        bool,
+        MultipleBranchPolicy,
+        Box<Behavior<A>>,
+        Vec<Behavior<A>>,
+    ),
     ParallelAnd(i32, Vec<Behavior<A>>),
     ParallelOr(i32, Vec<Behavior<A>>),
     ParallelSequence(i32, Vec<Behavior<A>>),
     ParallelSelect(i32, Vec<Behavior<A>>),
+    Parallel(i32, i32, i32, ParallelPolicy, Vec<Behavior<A>>),
     WeightSelect(i32, String, Vec<Behavior<A>>),
+    UtilitySelect(i32, bool, f64, Vec<(UtilityScorer<A>, Behavior<A>)>),
     // Decorator Node
     Invert(i32, Box<Behavior<A>>),
     ForceFailure(i32, Box<Behavior<A>>),
@@ -40,10 +84,95 @@ pub enum Behavior<A> {
     UntilFailure(i32, Box<Behavior<A>>),
     TimeOut(i32, i64, Box<Behavior<A>>),
     Limiter(i32, i64, i32, Box<Behavior<A>>),
+    TimeoutTicks(i32, i64, Box<Behavior<A>>),
+    LimiterTicks(i32, i64, i32, Box<Behavior<A>>),
+    // duration, trigger, name of the i64 blackboard value shared by a cooldown group.
+    Cooldown(i32, i64, CooldownTrigger, Option<String>, Box<Behavior<A>>),
+    // container and item blackboard names, policy, one element per tick.
+    ForEach(i32, String, String, ForEachPolicy, bool, Box<Behavior<A>>),
+    // probability as a number, `<name>` of a numeric blackboard value or an expression.
+    Chance(i32, String, Box<Behavior<A>>),
     Repeat(i32, i32, Box<Behavior<A>>),
     ImmediateRepeat(i32, i32, Box<Behavior<A>>),
     Retry(i32, i32, Box<Behavior<A>>),
     ImmediateRetry(i32, i32, Box<Behavior<A>>),
     Log(i32, String, Box<Behavior<A>>),
+    OnEvent(i32, String, Option<String>, Box<Behavior<A>>),
+    // semaphore name, wait for a free slot instead of failing.
+    Semaphore(i32, String, bool, Box<Behavior<A>>),
     SubTree(i32, String, HashMap<String, String>),
 }
+
+impl<A> Behavior<A> {
+    pub fn children(&self) -> Vec<&Behavior<A>> {
+        match self {
+            Behavior::Action(..)
+            | Behavior::Wait(..)
+            | Behavior::WaitTicks(..)
+            | Behavior::WaitForever(..)
+            | Behavior::AlwaysSuccess(..)
+            | Behavior::AlwaysFailure(..)
+            | Behavior::AlwaysRunning(..)
+            | Behavior::CompareBlackBoard(..)
+            | Behavior::SetBlackBoard(..)
+            | Behavior::ClearBlackBoard(..)
+            | Behavior::IsBlackBoardSet(..)
+            | Behavior::EmitEvent(..)
+            | Behavior::WaitForEvent(..)
+            | Behavior::SubTree(..) => Vec::new(),
+            #[cfg(feature = "expression_node")]
+            Behavior::Expression(..) => Vec::new(),
+            Behavior::If(_, _, cond, success) => vec![cond.as_ref(), success.as_ref()],
+            Behavior::IfThenElse(_, _, cond, success, failure) => {
+                vec![cond.as_ref(), success.as_ref(), failure.as_ref()]
+            }
+            Behavior::While(_, cond, children)
+            | Behavior::Branch(_, _, cond, children)
+            | Behavior::PriorityBranch(_, _, _, cond, children)
+            | Behavior::MultipleBranch(_, _, _, cond, children) => {
+                std::iter::once(cond.as_ref()).chain(children).collect()
+            }
+            Behavior::Select(_, children)
+            | Behavior::Sequence(_, children)
+            | Behavior::ReactiveSelect(_, children)
+            | Behavior::ReactiveSequence(_, children)
+            | Behavior::RandomSelect(_, _, children)
+            | Behavior::RandomSequence(_, _, children)
+            | Behavior::RoundRobinSelect(_, children)
+            | Behavior::BranchCond(_, children)
+            | Behavior::ParallelAnd(_, children)
+            | Behavior::ParallelOr(_, children)
+            | Behavior::ParallelSequence(_, children)
+            | Behavior::ParallelSelect(_, children)
+            | Behavior::Parallel(_, _, _, _, children)
+            | Behavior::WeightSelect(_, _, children) => children.iter().collect(),
+            Behavior::UtilitySelect(_, _, _, children) => {
+                children.iter().map(|(_, child)| child).collect()
+            }
+            Behavior::Switch(_, _, _, cases, default) => cases
+                .iter()
+                .map(|(_, child)| child)
+                .chain(std::iter::once(default.as_ref()))
+                .collect(),
+            Behavior::Invert(_, child)
+            | Behavior::ForceFailure(_, child)
+            | Behavior::ForceSuccess(_, child)
+            | Behavior::UntilSuccess(_, child)
+            | Behavior::UntilFailure(_, child)
+            | Behavior::TimeOut(_, _, child)
+            | Behavior::Limiter(_, _, _, child)
+            | Behavior::TimeoutTicks(_, _, child)
+            | Behavior::LimiterTicks(_, _, _, child)
+            | Behavior::ForEach(_, _, _, _, _, child)
+            | Behavior::OnEvent(_, _, _, child)
+            | Behavior::Semaphore(_, _, _, child)
+            | Behavior::Chance(_, _, child)
+            | Behavior::Cooldown(_, _, _, _, child)
+            | Behavior::Repeat(_, _, child)
+            | Behavior::ImmediateRepeat(_, _, child)
+            | Behavior::Retry(_, _, child)
+            | Behavior::ImmediateRetry(_, _, child)
+            | Behavior::Log(_, _, child) => vec![child.as_ref()],
+        }
+    }
+}
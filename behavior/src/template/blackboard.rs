@@ -1,25 +1,42 @@
-#[cfg(feature = "tree_visualization")]
+use super::clock::{SharedClock, WallClock};
+use super::event::EventQueue;
+use super::observer::TreeObserver;
+use super::snapshot::BlackBoardValueState;
+use super::tree_node::MaybeSend;
 use super::Status;
-use crate::{factory::FastHashMap, node::BlackBoardCell};
+use crate::{factory::FastHashMap, node::BlackBoardCell, BehaviorError};
+use behavior_util::{seeded_rng, SeededRng};
+use bincode::{Decode, Encode};
 use downcast_rs::{impl_downcast, Downcast};
-#[cfg(feature = "expression_node")]
-use num::cast::FromPrimitive;
 use std::{
     any::TypeId,
-    cell::RefCell,
+    cmp::Ordering,
     fmt::Debug,
     ops::{Deref, DerefMut},
-    rc::Rc,
+    sync::Arc,
 };
 
-pub trait BlackBoardType: Downcast + Debug + 'static {
+pub trait BlackBoardType: Downcast + Debug + MaybeSend + 'static {
     fn clone_box(&self) -> Box<dyn BlackBoardType>;
+
+    // Assign in place, false if the source is of another type.
+    fn clone_from_dyn(&mut self, source: &dyn BlackBoardType) -> bool;
 }
 impl_downcast!(BlackBoardType);
-impl<T: Debug + Clone + 'static> BlackBoardType for T {
+impl<T: Debug + Clone + MaybeSend + 'static> BlackBoardType for T {
     fn clone_box(&self) -> Box<dyn BlackBoardType> {
         Box::new(self.clone())
     }
+
+    fn clone_from_dyn(&mut self, source: &dyn BlackBoardType) -> bool {
+        match source.downcast_ref::<T>() {
+            Some(source) => {
+                self.clone_from(source);
+                true
+            }
+            None => false,
+        }
+    }
 }
 impl Clone for Box<dyn BlackBoardType> {
     fn clone(&self) -> Box<dyn BlackBoardType> {
@@ -27,19 +44,373 @@ impl Clone for Box<dyn BlackBoardType> {
     }
 }
 
-#[derive(Debug, Clone)]
+// Encodes blackboard values of one type for instance snapshots, the value is decoded in place
+// because blackboard cells point into it.
+#[derive(Clone, Copy)]
+pub struct BlackBoardCodec {
+    encode: fn(&dyn BlackBoardType) -> Option<Vec<u8>>,
+    decode: fn(&mut dyn BlackBoardType, &[u8]) -> bool,
+}
+impl BlackBoardCodec {
+    pub fn new<T: BlackBoardType + Encode + Decode>() -> Self {
+        Self {
+            encode: |value| {
+                value.downcast_ref::<T>().and_then(|value| {
+                    bincode::encode_to_vec(value, bincode::config::standard()).ok()
+                })
+            },
+            decode: |value, data| match (
+                value.downcast_mut::<T>(),
+                bincode::decode_from_slice::<T, _>(data, bincode::config::standard()),
+            ) {
+                (Some(value), Ok((decoded, _))) => {
+                    *value = decoded;
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+cfg_expression_node! {
+    use crate::{convert::ConvertExpressionValue, ExpressionArgType};
+
+    // Converts blackboard values of one type from and to expression values, attached to the
+    // values of the types registered for expressions.
+    #[derive(Clone, Copy)]
+    pub struct ExpressionAdapter {
+        value_type: ExpressionArgType,
+        read: fn(&dyn BlackBoardType) -> Option<evalexpr::Value>,
+        write: fn(&mut dyn BlackBoardType, &evalexpr::Value) -> bool,
+    }
+    impl ExpressionAdapter {
+        pub fn new<T: BlackBoardType + ConvertExpressionValue>() -> Self {
+            Self {
+                value_type: T::VALUE_TYPE,
+                read: |value| value.downcast_ref::<T>().map(T::to_expression_value),
+                write: |value, expr_value| match (
+                    value.downcast_mut::<T>(),
+                    T::from_expression_value(expr_value),
+                ) {
+                    (Some(value), Some(converted)) => {
+                        *value = converted;
+                        true
+                    }
+                    _ => false,
+                },
+            }
+        }
+
+        #[inline]
+        pub fn value_type(&self) -> ExpressionArgType {
+            self.value_type
+        }
+    }
+}
+
+pub type BlackBoardParseFunc = fn(&str) -> Option<Box<dyn BlackBoardType>>;
+pub type BlackBoardEqFunc = fn(&dyn BlackBoardType, &dyn BlackBoardType) -> bool;
+pub type BlackBoardCmpFunc = fn(&dyn BlackBoardType, &dyn BlackBoardType) -> Option<Ordering>;
+pub type BlackBoardClearFunc = fn(&mut dyn BlackBoardType) -> bool;
+pub type BlackBoardIsSetFunc = fn(&dyn BlackBoardType) -> bool;
+
+// Parses, compares and clears blackboard values of one type for the builtin blackboard nodes,
+// the comparisons are only there for types registered as equatable or comparable, clearing for
+// types with a default value and the presence check for Options.
+#[derive(Clone, Copy)]
+pub struct BlackBoardOps {
+    parse: BlackBoardParseFunc,
+    eq: Option<BlackBoardEqFunc>,
+    partial_cmp: Option<BlackBoardCmpFunc>,
+    clear: Option<BlackBoardClearFunc>,
+    is_set: Option<BlackBoardIsSetFunc>,
+}
+impl BlackBoardOps {
+    pub fn new(parse: BlackBoardParseFunc) -> Self {
+        Self {
+            parse,
+            eq: None,
+            partial_cmp: None,
+            clear: None,
+            is_set: None,
+        }
+    }
+
+    // Clearing assigns the default value.
+    pub fn with_default<T: BlackBoardType + Default>(mut self) -> Self {
+        self.clear = Some(|value| {
+            value
+                .downcast_mut::<T>()
+                .map(|value| *value = T::default())
+                .is_some()
+        });
+        self
+    }
+
+    // For `Option<T>`, clearing assigns None.
+    pub fn with_option<T: BlackBoardType + Clone>(mut self) -> Self {
+        self = self.with_default::<Option<T>>();
+        self.is_set = Some(|value| {
+            value
+                .downcast_ref::<Option<T>>()
+                .is_some_and(Option::is_some)
+        });
+        self
+    }
+
+    pub fn with_eq<T: BlackBoardType + PartialEq>(mut self) -> Self {
+        self.eq = Some(
+            |left, right| match (left.downcast_ref::<T>(), right.downcast_ref::<T>()) {
+                (Some(left), Some(right)) => left == right,
+                _ => false,
+            },
+        );
+        self
+    }
+
+    pub fn with_partial_cmp<T: BlackBoardType + PartialOrd>(mut self) -> Self {
+        self.partial_cmp =
+            Some(
+                |left, right| match (left.downcast_ref::<T>(), right.downcast_ref::<T>()) {
+                    (Some(left), Some(right)) => left.partial_cmp(right),
+                    _ => None,
+                },
+            );
+        self
+    }
+
+    #[inline]
+    pub fn parse(&self, value_str: &str) -> Option<Box<dyn BlackBoardType>> {
+        (self.parse)(value_str)
+    }
+
+    #[inline]
+    pub fn has_eq(&self) -> bool {
+        self.eq.is_some()
+    }
+
+    #[inline]
+    pub fn has_partial_cmp(&self) -> bool {
+        self.partial_cmp.is_some()
+    }
+
+    #[inline]
+    pub fn has_clear(&self) -> bool {
+        self.clear.is_some()
+    }
+
+    #[inline]
+    pub fn has_is_set(&self) -> bool {
+        self.is_set.is_some()
+    }
+
+    // False if the type can't be cleared.
+    #[inline]
+    pub fn clear(&self, value: &mut dyn BlackBoardType) -> bool {
+        self.clear.is_some_and(|clear| clear(value))
+    }
+
+    // False if the type is not an Option.
+    #[inline]
+    pub fn is_set(&self, value: &dyn BlackBoardType) -> bool {
+        self.is_set.is_some_and(|is_set| is_set(value))
+    }
+
+    // False if the type is not equatable.
+    #[inline]
+    pub fn values_eq(&self, left: &dyn BlackBoardType, right: &dyn BlackBoardType) -> bool {
+        self.eq.is_some_and(|eq| eq(left, right))
+    }
+
+    // None if the type is not comparable or the values are unordered.
+    #[inline]
+    pub fn values_cmp(
+        &self,
+        left: &dyn BlackBoardType,
+        right: &dyn BlackBoardType,
+    ) -> Option<Ordering> {
+        self.partial_cmp
+            .and_then(|partial_cmp| partial_cmp(left, right))
+    }
+}
+
+cfg_not_thread_safe! {
+    use std::{
+        cell::{Ref, RefCell, RefMut},
+        rc::Rc as Shared,
+    };
+
+    type BlackBoardValueCell = RefCell<Box<dyn BlackBoardType>>;
+}
+
+cfg_thread_safe! {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc as Shared, Mutex, MutexGuard, PoisonError, TryLockError,
+    };
+
+    // Identifies the current thread while it's alive.
+    fn thread_token() -> usize {
+        thread_local!(static TOKEN: u8 = const { 0 });
+        TOKEN.with(|token| token as *const u8 as usize)
+    }
+
+    #[derive(Debug)]
+    struct BlackBoardValueCell {
+        // token of the thread holding the lock, 0 if it's not locked.
+        owner: AtomicUsize,
+        value: Mutex<Box<dyn BlackBoardType>>,
+    }
+    impl BlackBoardValueCell {
+        fn new(value: Box<dyn BlackBoardType>) -> Self {
+            Self {
+                owner: AtomicUsize::new(0),
+                value: Mutex::new(value),
+            }
+        }
+
+        fn lock(&self, bb_name: &str) -> BlackBoardGuard<'_> {
+            let thread = thread_token();
+            let guard = match self.value.try_lock() {
+                Ok(guard) => guard,
+                Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                // only the holder stores its own token, so this is a re-borrow that would
+                // deadlock if the token matches.
+                Err(TryLockError::WouldBlock) => {
+                    if self.owner.load(AtomicOrdering::Relaxed) == thread {
+                        panic!("blackboard value already borrowed, bb_name={}", bb_name);
+                    }
+                    self.value.lock().unwrap_or_else(PoisonError::into_inner)
+                }
+            };
+            self.owner.store(thread, AtomicOrdering::Relaxed);
+            BlackBoardGuard {
+                owner: &self.owner,
+                guard,
+            }
+        }
+    }
+
+    // Keeps a blackboard value locked until it's dropped.
+    pub struct BlackBoardGuard<'a> {
+        owner: &'a AtomicUsize,
+        guard: MutexGuard<'a, Box<dyn BlackBoardType>>,
+    }
+    impl Drop for BlackBoardGuard<'_> {
+        fn drop(&mut self) {
+            // cleared before the mutex is unlocked by dropping the guard.
+            self.owner.store(0, AtomicOrdering::Relaxed);
+        }
+    }
+    impl Deref for BlackBoardGuard<'_> {
+        type Target = Box<dyn BlackBoardType>;
+        fn deref(&self) -> &Self::Target {
+            &self.guard
+        }
+    }
+    impl DerefMut for BlackBoardGuard<'_> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.guard
+        }
+    }
+    impl Debug for BlackBoardGuard<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            self.guard.fmt(f)
+        }
+    }
+}
+
+// Handles to one value are shared by the blackboard cells, linked subtrees and expression
+// variables of the instance that created it. Without the `thread_safe` feature they stay on the
+// thread of the instance. With it, the instance and its cells can move to other threads: a value
+// locked by another thread is waited for, borrowing it again on the thread holding it panics like
+// a RefCell instead of deadlocking.
 pub struct SharedBlackBoardValue {
-    bb_name: Rc<String>,
+    bb_name: Shared<String>,
     bb_type: &'static str,
-    bb_value: Rc<RefCell<Box<dyn BlackBoardType>>>,
+    bb_value: Shared<BlackBoardValueCell>,
+    bb_codec: Option<BlackBoardCodec>,
+    #[cfg(feature = "expression_node")]
+    bb_expr_adapter: Option<ExpressionAdapter>,
+}
+impl Debug for SharedBlackBoardValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedBlackBoardValue")
+            .field("bb_name", &self.bb_name)
+            .field("bb_type", &self.bb_type)
+            .field("bb_value", &self.bb_value)
+            .finish()
+    }
 }
 impl SharedBlackBoardValue {
     pub fn new(bb_name: String, bb_type: &'static str, bb_value: Box<dyn BlackBoardType>) -> Self {
         Self {
             bb_type,
-            bb_name: Rc::new(bb_name),
-            bb_value: Rc::new(RefCell::new(bb_value)),
+            bb_name: Shared::new(bb_name),
+            bb_value: Shared::new(BlackBoardValueCell::new(bb_value)),
+            bb_codec: None,
+            #[cfg(feature = "expression_node")]
+            bb_expr_adapter: None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn share(&self) -> Self {
+        Self {
+            bb_name: self.bb_name.clone(),
+            bb_type: self.bb_type,
+            bb_value: self.bb_value.clone(),
+            bb_codec: self.bb_codec,
+            #[cfg(feature = "expression_node")]
+            bb_expr_adapter: self.bb_expr_adapter,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_same_value(&self, other: &SharedBlackBoardValue) -> bool {
+        Shared::ptr_eq(&self.bb_value, &other.bb_value)
+    }
+
+    #[inline]
+    pub(crate) fn set_codec(&mut self, codec: BlackBoardCodec) {
+        self.bb_codec = Some(codec);
+    }
+
+    // None if the type is not registered for snapshots.
+    pub fn save_value(&self) -> Option<Vec<u8>> {
+        self.bb_codec
+            .and_then(|codec| (codec.encode)(self.borrow().as_ref()))
+    }
+
+    pub fn load_value(&self, data: &[u8]) -> bool {
+        self.bb_codec
+            .is_some_and(|codec| (codec.decode)(self.borrow_mut().as_mut(), data))
+    }
+
+    // Copies the value of another blackboard with the same name and type, keeps the cells
+    // pointing into this one valid.
+    pub fn clone_value_from(&self, source: &SharedBlackBoardValue) -> bool {
+        if self.is_same_value(source) {
+            return true;
         }
+        self.bb_name == source.bb_name
+            && self.bb_type == source.bb_type
+            && self
+                .borrow_mut()
+                .as_mut()
+                .clone_from_dyn(source.borrow().as_ref())
+    }
+
+    // Copies the value of another handle of the same type, e.g. another blackboard value.
+    pub fn assign_from(&self, source: &SharedBlackBoardValue) -> bool {
+        self.is_same_value(source) || self.assign(source.borrow().as_ref())
+    }
+
+    // False if the value is of another type.
+    #[inline]
+    pub fn assign(&self, value: &dyn BlackBoardType) -> bool {
+        self.borrow_mut().as_mut().clone_from_dyn(value)
     }
 
     #[inline]
@@ -54,17 +425,33 @@ impl SharedBlackBoardValue {
 
     #[inline]
     pub fn bb_type_id(&self) -> TypeId {
-        self.bb_value.borrow().clone().type_id()
+        self.borrow().clone().type_id()
     }
 
-    #[inline]
-    pub fn borrow(&self) -> std::cell::Ref<Box<dyn BlackBoardType>> {
-        self.bb_value.borrow()
+    cfg_not_thread_safe! {
+        #[inline]
+        pub fn borrow(&self) -> Ref<'_, Box<dyn BlackBoardType>> {
+            self.bb_value.borrow()
+        }
+
+        #[inline]
+        pub fn borrow_mut(&self) -> RefMut<'_, Box<dyn BlackBoardType>> {
+            self.bb_value.borrow_mut()
+        }
     }
 
-    #[inline]
-    pub fn borrow_mut(&self) -> std::cell::RefMut<Box<dyn BlackBoardType>> {
-        self.bb_value.borrow_mut()
+    cfg_thread_safe! {
+        // Waits while another thread holds the value, panics if this thread already does.
+        #[inline]
+        pub fn borrow(&self) -> BlackBoardGuard<'_> {
+            self.bb_value.lock(&self.bb_name)
+        }
+
+        // Values are only written through a guard, so a shared and a mutable borrow are the same.
+        #[inline]
+        pub fn borrow_mut(&self) -> BlackBoardGuard<'_> {
+            self.borrow()
+        }
     }
 
     #[inline]
@@ -82,45 +469,51 @@ impl SharedBlackBoardValue {
             .is_some()
     }
 
+    // None for non numeric values.
+    pub fn get_as_f64(&self) -> Option<f64> {
+        match self.bb_type {
+            "i32" => self.get_value::<i32>().map(|v| v as f64),
+            "i64" => self.get_value::<i64>().map(|v| v as f64),
+            "f32" => self.get_value::<f32>().map(|v| v as f64),
+            "f64" => self.get_value::<f64>(),
+            _ => None,
+        }
+    }
+
     cfg_expression_node! {
+        #[inline]
+        pub(crate) fn set_expression_adapter(&mut self, adapter: ExpressionAdapter) {
+            self.bb_expr_adapter = Some(adapter);
+        }
+
+        // Only values of the types registered for expressions can be used in them.
         #[inline]
         pub fn is_expr_var(&self) -> bool {
-            self.bb_type == "i32"
-                || self.bb_type == "i64"
-                || self.bb_type == "f32"
-                || self.bb_type == "f64"
-        }
-
-        pub fn get_as_f64(&self) -> Option<f64> {
-            match self.bb_type {
-                "i32" => self.get_value::<i32>().map(|v| v as f64),
-                "i64" => self.get_value::<i64>().map(|v| v as f64),
-                "f32" => self.get_value::<f32>().map(|v| v as f64),
-                "f64" => self.get_value::<f64>(),
-                _ => None,
-            }
+            self.bb_expr_adapter.is_some()
         }
 
-        pub fn set_from_f64(&self, val: f64) -> bool {
-            match self.bb_type {
-                "i32" => i32::from_f64(val).filter(|v| self.set_value(*v)).is_some(),
-                "i64" => i64::from_f64(val).filter(|v| self.set_value(*v)).is_some(),
-                "f32" => f32::from_f64(val).filter(|v| self.set_value(*v)).is_some(),
-                "f64" => f64::from_f64(val).filter(|v| self.set_value(*v)).is_some(),
-                _ => false,
-            }
+        #[inline]
+        pub fn expression_type(&self) -> Option<ExpressionArgType> {
+            self.bb_expr_adapter.map(|adapter| adapter.value_type())
+        }
+
+        pub fn get_as_expr_value(&self) -> Option<evalexpr::Value> {
+            self.bb_expr_adapter
+                .and_then(|adapter| (adapter.read)(self.borrow().as_ref()))
+        }
+
+        // False if the expression value doesn't convert to the blackboard type.
+        pub fn set_from_expr_value(&self, value: &evalexpr::Value) -> bool {
+            self.bb_expr_adapter
+                .is_some_and(|adapter| (adapter.write)(self.borrow_mut().as_mut(), value))
         }
     }
 
     pub fn downcast_to_blackboard_cell<T: BlackBoardType>(&self) -> Option<BlackBoardCell<T>> {
-        let mut base = self.borrow_mut();
-        let base_mut = base.as_mut();
-        match base_mut.downcast_mut::<T>() {
-            None => None,
-            Some(value) => Some(BlackBoardCell::new(
-                self.bb_name.as_ref() as *const String,
-                value as *mut T,
-            )),
+        if self.borrow().is::<T>() {
+            Some(BlackBoardCell::new(self.share()))
+        } else {
+            None
         }
     }
 }
@@ -142,6 +535,8 @@ cfg_tree_visualization! {
         pub tree_index: i32,
         pub tree_depth: i32,
         pub self_visualization: [u64; SELF_VISUALIZATION_LEN],
+        // scores of the children of utility nodes by node index, from the last evaluation.
+        pub node_scores: Vec<(i32, Vec<f64>)>,
         pub children_visualization: Vec<Visualization>,
     }
 
@@ -151,6 +546,7 @@ cfg_tree_visualization! {
         pub tree_index: i32,
         pub tree_depth: i32,
         pub visualizetion: [u64; SELF_VISUALIZATION_LEN],
+        pub node_scores: Vec<(i32, Vec<f64>)>,
     }
 
     impl Visualization {
@@ -160,6 +556,7 @@ cfg_tree_visualization! {
                 tree_index,
                 tree_depth,
                 self_visualization: [0; SELF_VISUALIZATION_LEN],
+                node_scores: Vec::new(),
                 children_visualization: Vec::new(),
             }
         }
@@ -167,6 +564,7 @@ cfg_tree_visualization! {
         #[inline]
         pub fn reset(&mut self) {
             self.self_visualization = [0; SELF_VISUALIZATION_LEN];
+            self.node_scores.clear();
             self.children_visualization.clear();
         }
 
@@ -177,6 +575,7 @@ cfg_tree_visualization! {
                 tree_depth: self.tree_depth,
                 tree_name: self.tree_name.clone(),
                 visualizetion: self.self_visualization,
+                node_scores: self.node_scores.clone(),
             }
         }
 
@@ -225,6 +624,23 @@ cfg_tree_visualization! {
                 | (state_value << (status_bit_position * 2));
         }
 
+        pub fn update_node_scores(&mut self, node_index: i32, scores: &[f64]) {
+            match self.node_scores.iter_mut().find(|(index, _)| *index == node_index) {
+                Some((_, node_scores)) => {
+                    node_scores.clear();
+                    node_scores.extend_from_slice(scores);
+                }
+                None => self.node_scores.push((node_index, scores.to_vec())),
+            }
+        }
+
+        pub fn get_node_scores(&self, node_index: i32) -> Option<&[f64]> {
+            self.node_scores
+                .iter()
+                .find(|(index, _)| *index == node_index)
+                .map(|(_, scores)| scores.as_slice())
+        }
+
         pub fn get_node_status(&self, node_index: i32) -> Status {
             assert!(
                 node_index <= MAX_NODE_INDEX as i32,
@@ -258,6 +674,11 @@ pub struct BlackBoard<T> {
     #[cfg(feature = "tree_visualization")]
     visualization: Visualization,
     blackboard_map: FastHashMap<String, SharedBlackBoardValue>,
+    clock: SharedClock,
+    rng: Box<SeededRng>,
+    tree_name: String,
+    observer: Option<Box<dyn TreeObserver>>,
+    events: EventQueue,
 }
 
 impl<T> Deref for BlackBoard<T> {
@@ -276,7 +697,7 @@ impl<T> DerefMut for BlackBoard<T> {
 impl<T> BlackBoard<T> {
     pub fn new(
         context: T,
-        #[allow(unused)] tree_name: String,
+        tree_name: String,
         #[allow(unused)] tree_index: i32,
         #[allow(unused)] tree_depth: i32,
         blackboard_map: BlackBoardMap,
@@ -284,8 +705,13 @@ impl<T> BlackBoard<T> {
         Self {
             context,
             blackboard_map,
+            clock: Arc::new(WallClock),
+            rng: Box::new(seeded_rng(0)),
             #[cfg(feature = "tree_visualization")]
-            visualization: Visualization::new(tree_name, tree_index, tree_depth),
+            visualization: Visualization::new(tree_name.clone(), tree_index, tree_depth),
+            tree_name,
+            observer: None,
+            events: EventQueue::default(),
         }
     }
 
@@ -309,6 +735,173 @@ impl<T> BlackBoard<T> {
         &mut self.blackboard_map
     }
 
+    #[inline]
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
+    #[inline]
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.clock = clock;
+    }
+
+    #[inline]
+    pub fn now_millis(&self) -> i64 {
+        self.clock.now_millis()
+    }
+
+    // Random source of the randomized builtin nodes, pass it to the `behavior_util::*_with_rng`
+    // functions to draw from the same deterministic stream.
+    #[inline]
+    pub fn rng(&self) -> &SeededRng {
+        &self.rng
+    }
+
+    #[inline]
+    pub fn rng_mut(&mut self) -> &mut SeededRng {
+        &mut self.rng
+    }
+
+    #[inline]
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        *self.rng = seeded_rng(seed);
+    }
+
+    // subtrees tick with their own blackboard, the instance's rng, observer and events are lent
+    // to them meanwhile.
+    #[inline]
+    pub(crate) fn swap_shared_state(&mut self, other: &mut Self) {
+        std::mem::swap(&mut self.rng, &mut other.rng);
+        std::mem::swap(&mut self.observer, &mut other.observer);
+        std::mem::swap(&mut self.events, &mut other.events);
+    }
+
+    #[inline]
+    pub fn events(&self) -> &EventQueue {
+        &self.events
+    }
+
+    #[inline]
+    pub fn events_mut(&mut self) -> &mut EventQueue {
+        &mut self.events
+    }
+
+    #[inline]
+    pub fn tree_name(&self) -> &str {
+        self.tree_name.as_str()
+    }
+
+    #[inline]
+    pub(crate) fn set_observer(
+        &mut self,
+        observer: Option<Box<dyn TreeObserver>>,
+    ) -> Option<Box<dyn TreeObserver>> {
+        std::mem::replace(&mut self.observer, observer)
+    }
+
+    #[inline]
+    pub fn observer_ref(&self) -> Option<&dyn TreeObserver> {
+        self.observer.as_deref()
+    }
+
+    #[inline]
+    pub fn is_observed(&self) -> bool {
+        self.observer.is_some()
+    }
+
+    #[inline]
+    pub(crate) fn notify_enter(&mut self, node_index: i32, node_name: &str) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_enter(&self.tree_name, node_index, node_name);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn notify_status_change(
+        &mut self,
+        node_index: i32,
+        node_name: &str,
+        prev_status: Status,
+        status: Status,
+    ) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_status_change(&self.tree_name, node_index, node_name, prev_status, status);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn notify_reset(&mut self, node_index: i32, node_name: &str) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_reset(&self.tree_name, node_index, node_name);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn notify_subtree_enter(&mut self, node_index: i32, subtree_name: &str) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_subtree_enter(&self.tree_name, node_index, subtree_name);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn notify_subtree_exit(
+        &mut self,
+        node_index: i32,
+        subtree_name: &str,
+        status: Status,
+    ) {
+        if let Some(observer) = self.observer.as_mut() {
+            observer.on_subtree_exit(&self.tree_name, node_index, subtree_name, status);
+        }
+    }
+
+    // sorted by name so that equal blackboards give equal snapshots.
+    pub fn save_values(&self) -> Result<Vec<BlackBoardValueState>, BehaviorError> {
+        let mut states = self
+            .blackboard_map
+            .iter()
+            .map(|(name, value)| {
+                value
+                    .save_value()
+                    .map(|data| BlackBoardValueState {
+                        name: name.to_string(),
+                        value_type: value.bb_type().to_string(),
+                        data,
+                    })
+                    .ok_or_else(|| BehaviorError::SnapshotBlackBoardUnsupported {
+                        blackboard_name: name.to_string(),
+                        blackboard_type: value.bb_type().to_string(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        states.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(states)
+    }
+
+    pub fn load_values(&mut self, states: &[BlackBoardValueState]) -> Result<(), BehaviorError> {
+        for state in states {
+            let restore_error = |error_info: &str| BehaviorError::RestoreBlackBoardFailed {
+                blackboard_name: state.name.to_string(),
+                blackboard_type: state.value_type.to_string(),
+                error_info: error_info.to_string(),
+            };
+            let value = self
+                .blackboard_map
+                .get(&state.name)
+                .ok_or_else(|| restore_error("blackboard not found"))?;
+            if value.bb_type() != state.value_type {
+                return Err(restore_error(&format!(
+                    "blackboard type mismatch, bb_type={}",
+                    value.bb_type()
+                )));
+            }
+            if !value.load_value(&state.data) {
+                return Err(restore_error("decode failed"));
+            }
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn visualize_blackboard_map(&self, s: &mut String) -> std::fmt::Result {
         use std::fmt::Write;
@@ -319,11 +912,6 @@ impl<T> BlackBoard<T> {
     }
 
     cfg_tree_visualization! {
-        #[inline]
-        pub fn tree_name(&self) -> &str {
-            &self.visualization.tree_name
-        }
-
         #[inline]
         pub fn tree_index(&self) -> i32 {
             self.visualization.tree_index
@@ -354,6 +942,11 @@ impl<T> BlackBoard<T> {
             self.visualization.update_node_status(node_index, status);
         }
 
+        #[inline]
+        pub fn update_node_scores(&mut self, node_index: i32, scores: &[f64]) {
+            self.visualization.update_node_scores(node_index, scores);
+        }
+
         #[inline]
         pub fn update_children_visualization(&mut self, runtime: Visualization) {
             self.visualization.children_visualization.push(runtime);
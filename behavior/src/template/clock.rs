@@ -0,0 +1,105 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+// Time source of the time-based builtin nodes (Wait, TimeOut, Limiter), in milliseconds.
+pub trait BtClock: Debug + Send + Sync + 'static {
+    fn now_millis(&self) -> i64;
+}
+
+pub type SharedClock = Arc<dyn BtClock>;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WallClock;
+
+impl BtClock for WallClock {
+    #[inline]
+    fn now_millis(&self) -> i64 {
+        chrono::Utc::now().timestamp_millis()
+    }
+}
+
+// Manually advanced clock for game time, fixed-timestep simulation and tests.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: AtomicI64,
+}
+
+impl ManualClock {
+    pub fn new(now: i64) -> Self {
+        Self {
+            now: AtomicI64::new(now),
+        }
+    }
+
+    #[inline]
+    pub fn set(&self, now: i64) {
+        self.now.store(now, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn advance(&self, delta: i64) {
+        self.now.fetch_add(delta, Ordering::Relaxed);
+    }
+}
+
+impl BtClock for ManualClock {
+    #[inline]
+    fn now_millis(&self) -> i64 {
+        self.now.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::factory::BtFactory;
+    use crate::Status;
+
+    fn create_factory(tree_json_str: &str) -> (BtFactory<(), (), ()>, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(0));
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory.set_clock(clock.clone());
+        bt_factory
+            .compile_tree_template_from_json_str("test", tree_json_str)
+            .unwrap();
+        (bt_factory, clock)
+    }
+
+    #[test]
+    fn wait_with_manual_clock() {
+        let (bt_factory, clock) = create_factory(r#"{ "tree_structure": { "Wait": [1, 100] } }"#);
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        clock.advance(99);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        clock.advance(1);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+    }
+
+    #[test]
+    fn timeout_with_manual_clock() {
+        let (bt_factory, clock) = create_factory(
+            r#"{ "tree_structure": { "TimeOut": [1, 50, { "AlwaysRunning": 2 }] } }"#,
+        );
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        clock.advance(49);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        clock.advance(1);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+    }
+
+    #[test]
+    fn limiter_with_manual_clock() {
+        let (bt_factory, clock) = create_factory(
+            r#"{ "tree_structure": { "Limiter": [1, 100, 1, { "AlwaysSuccess": 2 }] } }"#,
+        );
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+        clock.advance(100);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+    }
+}
@@ -1,16 +1,25 @@
 use super::blackboard::BlackBoard;
 use super::status::Status::*;
-use super::tree_node::TreeNode;
+use super::tree_node::{MaybeSend, TreeNode};
+use super::BlackBoardMap;
+use super::BlackBoardType;
 use super::BranchData;
+use super::ExpressionScorer;
+use super::NodeState;
 use super::NodeType;
+use super::SharedBlackBoardValue;
 use super::Status;
 use super::TreeNodeBase;
 use super::TreeNodeStatus;
 use super::TreeNodeType;
+use crate::factory::BtFactory;
+use crate::BehaviorError;
+use crate::TreeLocation;
 use behavior_macros::TreeNodeStatus;
-use behavior_util::weight_select_index;
+use behavior_util::{random_shuffle_with_rng, weight_select_index_with_rng};
 use core::panic;
 use rand::distr::weighted::WeightedIndex;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 static SUCCESS_BRANCH: usize = 0;
@@ -60,7 +69,7 @@ where
         entity: &Self::Entity,
     ) -> Status {
         let now_branch = if self.can_abort || !self.is_running() {
-            match self.cond.control_tick(blackboard, func, world, entity) {
+            match self.cond.execute_tick(blackboard, func, world, entity) {
                 Success => SUCCESS_BRANCH,
                 Failure => FAILURE_BRANCH,
                 Running => return set_status!(self, blackboard, Running),
@@ -80,7 +89,7 @@ where
         }
 
         let status = if now_branch == SUCCESS_BRANCH {
-            self.success.control_tick(blackboard, func, world, entity)
+            self.success.execute_tick(blackboard, func, world, entity)
         } else {
             Failure
         };
@@ -89,14 +98,14 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.cond.reset(ctx, world, entity);
-            self.success.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.cond.reset(blackboard, world, entity);
+            self.success.reset(blackboard, world, entity);
         }
     }
 
@@ -123,6 +132,22 @@ where
     > {
         vec![&self.cond, &self.success]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.cond, &mut self.success]
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -177,7 +202,7 @@ where
     ) -> Status {
         let prev_branch = self.prev_branch.take();
         let now_branch = if self.can_abort || prev_branch.is_none() {
-            match self.cond.control_tick(blackboard, func, world, entity) {
+            match self.cond.execute_tick(blackboard, func, world, entity) {
                 Success => SUCCESS_BRANCH,
                 Failure => FAILURE_BRANCH,
                 Running => return set_status!(self, blackboard, Running),
@@ -203,9 +228,9 @@ where
         }
 
         let status = if now_branch == SUCCESS_BRANCH {
-            self.success.control_tick(blackboard, func, world, entity)
+            self.success.execute_tick(blackboard, func, world, entity)
         } else {
-            self.failure.control_tick(blackboard, func, world, entity)
+            self.failure.execute_tick(blackboard, func, world, entity)
         };
 
         if status == Running {
@@ -216,23 +241,32 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.cond.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.cond.reset(blackboard, world, entity);
             if let Some(prev_branch) = self.prev_branch.take() {
                 if prev_branch == SUCCESS_BRANCH {
-                    self.success.reset(ctx, world, entity);
+                    self.success.reset(blackboard, world, entity);
                 } else {
-                    self.failure.reset(ctx, world, entity);
+                    self.failure.reset(blackboard, world, entity);
                 }
             }
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.prev_branch)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.prev_branch = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -256,6 +290,22 @@ where
     > {
         vec![&self.cond, &self.success, &self.failure]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.cond, &mut self.success, &mut self.failure]
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -313,7 +363,7 @@ where
         let now_branch = if self.can_abort || prev_branch.is_none() {
             match self
                 .branch_cond
-                .control_tick(blackboard, func, world, entity)
+                .execute_tick(blackboard, func, world, entity)
             {
                 Success => SUCCESS_BRANCH,
                 Failure => FAILURE_BRANCH,
@@ -342,7 +392,7 @@ where
                     self.branch_children[prev_branch].reset(blackboard, world, entity);
                 }
             }
-            self.branch_children[now_branch].control_tick(blackboard, func, world, entity)
+            self.branch_children[now_branch].execute_tick(blackboard, func, world, entity)
         } else {
             eprintln!(
                 "BranchNode::control_tick error, invalid branch, branch={}, children_len={}, node_name={}, node_index={}, child_name={}, child_index={}",
@@ -364,19 +414,282 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.branch_cond.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.branch_cond.reset(blackboard, world, entity);
             if let Some(prev_branch) = self.prev_branch.take() {
-                self.branch_children[prev_branch].reset(ctx, world, entity);
+                self.branch_children[prev_branch].reset(blackboard, world, entity);
+            }
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.prev_branch)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.prev_branch = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::ControlNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        let mut children = vec![&self.branch_cond];
+        self.branch_children
+            .iter()
+            .for_each(|child| children.push(child));
+        children
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        let mut children = vec![&mut self.branch_cond];
+        self.branch_children
+            .iter_mut()
+            .for_each(|child| children.push(child));
+        children
+    }
+}
+
+// How `MultipleBranchNode` completes, named after the parallel node with the same behavior.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MultipleBranchPolicy {
+    // fails on the first failed branch, succeeds once all branches succeeded.
+    #[default]
+    And,
+    // succeeds on the first succeeded branch, fails once all branches failed.
+    Or,
+    // waits for all branches, fails if any of them failed.
+    Sequence,
+    // waits for all branches, succeeds if any of them succeeded.
+    Select,
+}
+
+#[derive(TreeNodeStatus)]
+pub struct MultipleBranchNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    can_abort: bool,
+    policy: MultipleBranchPolicy,
+    result: Status,
+    selected_branches: Vec<usize>,
+    running_branches: Vec<usize>,
+    branch_cond: TreeNodeType<A, C, F, W, E>,
+    branch_children: Vec<TreeNodeType<A, C, F, W, E>>,
+}
+impl<A, C, F: ?Sized, W, E> MultipleBranchNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        can_abort: bool,
+        policy: MultipleBranchPolicy,
+        branch_cond: TreeNodeType<A, C, F, W, E>,
+        branch_children: Vec<TreeNodeType<A, C, F, W, E>>,
+    ) -> Self {
+        assert!(
+            branch_children.len() <= BranchData::MAX_BRANCH + 1,
+            "MultipleBranchNode' branch children must not be more than {}, node_index={}",
+            BranchData::MAX_BRANCH + 1,
+            index
+        );
+        let base = TreeNodeBase::default();
+        let result = Self::initial_result(policy);
+        Self {
+            base,
+            index,
+            can_abort,
+            policy,
+            result,
+            selected_branches: Vec::new(),
+            running_branches: Vec::new(),
+            branch_cond,
+            branch_children,
+        }
+    }
+
+    #[inline]
+    fn initial_result(policy: MultipleBranchPolicy) -> Status {
+        match policy {
+            MultipleBranchPolicy::And | MultipleBranchPolicy::Sequence => Success,
+            MultipleBranchPolicy::Or | MultipleBranchPolicy::Select => Failure,
+        }
+    }
+
+    // Completes the activation, the branches selected next time all start over.
+    #[inline]
+    fn take_result(&mut self) -> Status {
+        self.selected_branches.clear();
+        self.running_branches.clear();
+        self.result.replace(Self::initial_result(self.policy))
+    }
+}
+impl<A, C, F, W, E> TreeNode for MultipleBranchNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let now_branches = if self.can_abort || !self.is_running() {
+            match self
+                .branch_cond
+                .execute_tick(blackboard, func, world, entity)
+            {
+                Success => vec![SUCCESS_BRANCH],
+                Failure => vec![FAILURE_BRANCH],
+                Running => return set_status!(self, blackboard, Running),
+                Branch(data) if data.is_single_branch() => vec![data.get_single_branch()],
+                Branch(data) => data.get_multiple_branch(None).0,
+                _ => panic_if_idle!(
+                    self.node_name(),
+                    self.index,
+                    self.branch_cond.node_name(),
+                    self.branch_cond.node_index()
+                ),
+            }
+        } else {
+            self.selected_branches.clone()
+        };
+        if let Some(invalid_branch) = now_branches
+            .iter()
+            .find(|branch| **branch >= self.branch_children.len())
+        {
+            eprintln!(
+                "MultipleBranchNode::control_tick error, invalid branch, branch={}, children_len={}, node_name={}, node_index={}, child_name={}, child_index={}",
+                invalid_branch,
+                self.branch_children.len(),
+                self.node_name(),
+                self.node_index(),
+                self.branch_cond.node_name(),
+                self.branch_cond.node_index()
+            );
+            for index in self.running_branches.iter() {
+                self.branch_children[*index].reset(blackboard, world, entity);
+            }
+            self.take_result();
+            return set_status!(self, blackboard, Failure);
+        }
+
+        // branches dropped from the set are reset, the ones completed before are not run again.
+        let mut pending_queue = VecDeque::new();
+        for index in now_branches.iter() {
+            if self.running_branches.contains(index) || !self.selected_branches.contains(index) {
+                pending_queue.push_back(*index);
+            }
+        }
+        for index in self.running_branches.iter() {
+            if !now_branches.contains(index) {
+                self.branch_children[*index].reset(blackboard, world, entity);
+            }
+        }
+        self.selected_branches = now_branches;
+        self.running_branches.clear();
+
+        let mut running_branches = Vec::new();
+        while let Some(index) = pending_queue.pop_front() {
+            match self.branch_children[index].execute_tick(blackboard, func, world, entity) {
+                Running => running_branches.push(index),
+                status @ (Success | Failure) => match (self.policy, status) {
+                    (MultipleBranchPolicy::And, Failure) | (MultipleBranchPolicy::Or, Success) => {
+                        for index in running_branches.into_iter().chain(pending_queue) {
+                            self.branch_children[index].reset(blackboard, world, entity);
+                        }
+                        self.take_result();
+                        return set_status!(self, blackboard, status);
+                    }
+                    (MultipleBranchPolicy::Sequence, Failure)
+                    | (MultipleBranchPolicy::Select, Success) => self.result = status,
+                    _ => {}
+                },
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.branch_children[index].node_name(),
+                    self.branch_children[index].node_index()
+                ),
+            };
+        }
+        let status = if running_branches.is_empty() {
+            self.take_result()
+        } else {
+            self.running_branches = running_branches;
+            Running
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.branch_cond.reset(blackboard, world, entity);
+            for index in self.running_branches.iter() {
+                self.branch_children[*index].reset(blackboard, world, entity);
             }
+            self.take_result();
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(self.result, &self.selected_branches, &self.running_branches))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        let (result, selected_branches, running_branches) = state.decode()?;
+        self.result = result;
+        self.selected_branches = selected_branches;
+        self.running_branches = running_branches;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -404,8 +717,27 @@ where
             .for_each(|child| children.push(child));
         children
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        let mut children = vec![&mut self.branch_cond];
+        self.branch_children
+            .iter_mut()
+            .for_each(|child| children.push(child));
+        children
+    }
 }
-// TODO: MultipleBranchNode
 
 #[derive(TreeNodeStatus)]
 pub struct PriorityBranchNode<A, C, F: ?Sized, W, E> {
@@ -476,7 +808,7 @@ where
         let mut now_branch = if self.can_abort || prev_branch.is_none() {
             match self
                 .branch_cond
-                .control_tick(blackboard, func, world, entity)
+                .execute_tick(blackboard, func, world, entity)
             {
                 Success => SUCCESS_BRANCH,
                 Failure => FAILURE_BRANCH,
@@ -508,7 +840,7 @@ where
                     self.branch_children[prev_branch].reset(blackboard, world, entity);
                 }
             }
-            self.branch_children[now_branch].control_tick(blackboard, func, world, entity)
+            self.branch_children[now_branch].execute_tick(blackboard, func, world, entity)
         } else {
             eprintln!(
                 "PriorityBranchNode::control_tick error, invalid branch, branch={}, children_len={}, node_name={}, node_index={}, child_name={}, child_index={}",
@@ -530,19 +862,28 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.branch_cond.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.branch_cond.reset(blackboard, world, entity);
             if let Some(prev_branch) = self.prev_branch.take() {
-                self.branch_children[prev_branch].reset(ctx, world, entity);
+                self.branch_children[prev_branch].reset(blackboard, world, entity);
             }
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.prev_branch)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.prev_branch = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -570,6 +911,26 @@ where
             .for_each(|child| children.push(child));
         children
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        let mut children = vec![&mut self.branch_cond];
+        self.branch_children
+            .iter_mut()
+            .for_each(|child| children.push(child));
+        children
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -615,7 +976,7 @@ where
         };
         let mut running_queue = VecDeque::new();
         while let Some(index) = pending_queue.pop_front() {
-            match self.branch_conditions[index].control_tick(blackboard, func, world, entity) {
+            match self.branch_conditions[index].execute_tick(blackboard, func, world, entity) {
                 Failure => {}
                 Running => {
                     running_queue.push_back(index);
@@ -652,20 +1013,29 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
+            reset_status!(self, blackboard);
             if let Some(running_queue) = self.running_queue.take() {
                 for index in running_queue.into_iter() {
-                    self.branch_conditions[index].reset(ctx, world, entity);
+                    self.branch_conditions[index].reset(blackboard, world, entity);
                 }
             }
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.running_queue)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.running_queue = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -689,27 +1059,164 @@ where
     > {
         self.branch_conditions.iter().collect()
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.branch_conditions.iter_mut().collect()
+    }
 }
 
-#[derive(TreeNodeStatus)]
-pub struct SelectNode<A, C, F: ?Sized, W, E> {
-    base: TreeNodeBase,
-    index: i32,
-    cursor: usize,
+// Parses a case of an enum blackboard value, see `BtFactory::register_switch_enum_type`.
+pub type SwitchEnumParseFunc = fn(case_str: &str) -> Option<Box<dyn BlackBoardType>>;
+// Finds the case of an enum blackboard value.
+pub type SwitchEnumMatchFunc =
+    fn(value: &dyn BlackBoardType, cases: &[Box<dyn BlackBoardType>]) -> Option<usize>;
+
+// Case values of a `SwitchNode`, parsed for the type of the blackboard value it reads.
+pub enum SwitchCases {
+    // i32 and i64 values.
+    Int(Vec<i64>),
+    Str(Vec<String>),
+    Enum(Vec<Box<dyn BlackBoardType>>, SwitchEnumMatchFunc),
+}
+impl SwitchCases {
+    pub fn new(
+        tree_name: &str,
+        tree_index: i32,
+        tree_depth: i32,
+        index: i32,
+        value: &SharedBlackBoardValue,
+        case_strs: &[&str],
+        enum_funcs: Option<(SwitchEnumParseFunc, SwitchEnumMatchFunc)>,
+    ) -> Result<Self, BehaviorError> {
+        fn parse_cases<T: std::str::FromStr + PartialEq>(
+            case_strs: &[&str],
+            invalid_case: impl Fn(&str, &str) -> BehaviorError,
+        ) -> Result<Vec<T>, BehaviorError> {
+            let mut cases: Vec<T> = Vec::with_capacity(case_strs.len());
+            for case_str in case_strs {
+                let case = case_str
+                    .parse::<T>()
+                    .map_err(|_| invalid_case(case_str, "parse failed"))?;
+                if cases.contains(&case) {
+                    return Err(invalid_case(case_str, "duplicate case"));
+                }
+                cases.push(case);
+            }
+            Ok(cases)
+        }
+
+        let invalid_case = |case_str: &str, error_info: &str| BehaviorError::InvalidSwitchCase {
+            tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
+            node_index: index,
+            blackboard_type: value.bb_type().to_string(),
+            case_str: case_str.to_string(),
+            error_info: error_info.to_string(),
+        };
+        match (value.bb_type(), enum_funcs) {
+            ("i32" | "i64", _) => parse_cases(case_strs, invalid_case).map(SwitchCases::Int),
+            ("String", _) => parse_cases(case_strs, invalid_case).map(SwitchCases::Str),
+            (_, Some((enum_parse, enum_match))) => {
+                let mut cases: Vec<Box<dyn BlackBoardType>> = Vec::with_capacity(case_strs.len());
+                for case_str in case_strs {
+                    let case = enum_parse(case_str)
+                        .ok_or_else(|| invalid_case(case_str, "parse failed"))?;
+                    if enum_match(case.as_ref(), &cases).is_some() {
+                        return Err(invalid_case(case_str, "duplicate case"));
+                    }
+                    cases.push(case);
+                }
+                Ok(SwitchCases::Enum(cases, enum_match))
+            }
+            (bb_type, None) => Err(BehaviorError::SwitchTypeUnsupported {
+                tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
+                node_index: index,
+                blackboard_name: value.bb_name().to_string(),
+                blackboard_type: bb_type.to_string(),
+            }),
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            SwitchCases::Int(cases) => cases.len(),
+            SwitchCases::Str(cases) => cases.len(),
+            SwitchCases::Enum(cases, _) => cases.len(),
+        }
+    }
+
+    // None if no case matches.
+    fn find(&self, value: &SharedBlackBoardValue) -> Option<usize> {
+        let value = value.borrow();
+        match self {
+            SwitchCases::Int(cases) => {
+                let value = match value.downcast_ref::<i32>() {
+                    Some(value) => *value as i64,
+                    None => *value.downcast_ref::<i64>()?,
+                };
+                cases.iter().position(|case| *case == value)
+            }
+            SwitchCases::Str(cases) => {
+                let value = value.downcast_ref::<String>()?;
+                cases.iter().position(|case| case == value)
+            }
+            SwitchCases::Enum(cases, enum_match) => enum_match(value.as_ref(), cases),
+        }
+    }
+}
+
+// Runs the child of the case matching a blackboard value, the last child if none matches.
+#[derive(TreeNodeStatus)]
+pub struct SwitchNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    can_abort: bool,
+    prev_branch: Option<usize>,
+    value: SharedBlackBoardValue,
+    cases: SwitchCases,
     children: Vec<TreeNodeType<A, C, F, W, E>>,
 }
-impl<A, C, F: ?Sized, W, E> SelectNode<A, C, F, W, E> {
-    pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
+impl<A, C, F: ?Sized, W, E> SwitchNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        can_abort: bool,
+        value: SharedBlackBoardValue,
+        cases: SwitchCases,
+        children: Vec<TreeNodeType<A, C, F, W, E>>,
+    ) -> Self {
+        assert!(
+            children.len() == cases.len() + 1,
+            "SwitchNode' children must be the cases and a default child, node_index={}, cases_len={}, children_len={}",
+            index,
+            cases.len(),
+            children.len()
+        );
         let base = TreeNodeBase::default();
+        let prev_branch = None;
         Self {
             base,
             index,
-            cursor: 0,
+            can_abort,
+            prev_branch,
+            value,
+            cases,
             children,
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for SelectNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for SwitchNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -727,35 +1234,56 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        while self.cursor < self.children.len() {
-            match self.children[self.cursor].control_tick(blackboard, func, world, entity) {
-                Failure => self.cursor += 1,
-                Running => return set_status!(self, blackboard, Running),
-                Success => {
-                    self.cursor = 0;
-                    return set_status!(self, blackboard, Success);
-                }
-                _ => panic_if_idle_or_branch!(
-                    self.node_name(),
-                    self.index,
-                    self.children[self.cursor].node_name(),
-                    self.children[self.cursor].node_index()
-                ),
-            };
+        let prev_branch = self.prev_branch.take();
+        let now_branch = match prev_branch {
+            Some(prev_branch) if !self.can_abort => prev_branch,
+            _ => self
+                .cases
+                .find(&self.value)
+                .unwrap_or(self.children.len() - 1),
+        };
+        if let Some(prev_branch) = prev_branch {
+            if now_branch != prev_branch {
+                self.children[prev_branch].reset(blackboard, world, entity);
+            }
         }
-        self.cursor = 0;
-        set_status!(self, blackboard, Failure)
+
+        let status = match self.children[now_branch].execute_tick(blackboard, func, world, entity) {
+            status @ (Success | Failure | Running) => status,
+            _ => panic_if_idle_or_branch!(
+                self.node_name(),
+                self.index,
+                self.children[now_branch].node_name(),
+                self.children[now_branch].node_index()
+            ),
+        };
+        if status == Running {
+            self.prev_branch = Some(now_branch);
+        }
+        set_status!(self, blackboard, status)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
-        self.reset_status();
-        self.children[self.cursor].reset(ctx, world, entity);
-        self.cursor = 0;
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            if let Some(prev_branch) = self.prev_branch.take() {
+                self.children[prev_branch].reset(blackboard, world, entity);
+            }
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.prev_branch)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.prev_branch = state.decode()?;
+        Ok(())
     }
 
     fn node_index(&self) -> i32 {
@@ -781,16 +1309,32 @@ where
     > {
         self.children.iter().collect()
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect()
+    }
 }
 
 #[derive(TreeNodeStatus)]
-pub struct SequenceNode<A, C, F: ?Sized, W, E> {
+pub struct SelectNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
     cursor: usize,
     children: Vec<TreeNodeType<A, C, F, W, E>>,
 }
-impl<A, C, F: ?Sized, W, E> SequenceNode<A, C, F, W, E> {
+impl<A, C, F: ?Sized, W, E> SelectNode<A, C, F, W, E> {
     pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
         let base = TreeNodeBase::default();
         Self {
@@ -801,7 +1345,7 @@ impl<A, C, F: ?Sized, W, E> SequenceNode<A, C, F, W, E> {
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for SequenceNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for SelectNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -820,13 +1364,13 @@ where
         entity: &Self::Entity,
     ) -> Status {
         while self.cursor < self.children.len() {
-            match self.children[self.cursor].control_tick(blackboard, func, world, entity) {
-                Success => self.cursor += 1,
-                Failure => {
+            match self.children[self.cursor].execute_tick(blackboard, func, world, entity) {
+                Failure => self.cursor += 1,
+                Running => return set_status!(self, blackboard, Running),
+                Success => {
                     self.cursor = 0;
-                    return set_status!(self, blackboard, Failure);
+                    return set_status!(self, blackboard, Success);
                 }
-                Running => return set_status!(self, blackboard, Running),
                 _ => panic_if_idle_or_branch!(
                     self.node_name(),
                     self.index,
@@ -836,20 +1380,27 @@ where
             };
         }
         self.cursor = 0;
-        set_status!(self, blackboard, Success)
+        set_status!(self, blackboard, Failure)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
-        if self.is_running() {
-            self.reset_status();
-            self.children[self.cursor].reset(ctx, world, entity);
-            self.cursor = 0;
-        }
+        reset_status!(self, blackboard);
+        self.children[self.cursor].reset(blackboard, world, entity);
+        self.cursor = 0;
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.cursor)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.cursor = state.decode()?;
+        Ok(())
     }
 
     fn node_index(&self) -> i32 {
@@ -875,31 +1426,43 @@ where
     > {
         self.children.iter().collect()
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect()
+    }
 }
 
 #[derive(TreeNodeStatus)]
-pub struct WhileNode<A, C, F: ?Sized, W, E> {
+pub struct SequenceNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    while_cond: TreeNodeType<A, C, F, W, E>,
+    cursor: usize,
     children: Vec<TreeNodeType<A, C, F, W, E>>,
 }
-impl<A, C, F: ?Sized, W, E> WhileNode<A, C, F, W, E> {
-    pub fn new(
-        index: i32,
-        while_cond: TreeNodeType<A, C, F, W, E>,
-        children: Vec<TreeNodeType<A, C, F, W, E>>,
-    ) -> Self {
+impl<A, C, F: ?Sized, W, E> SequenceNode<A, C, F, W, E> {
+    pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
         let base = TreeNodeBase::default();
         Self {
             base,
             index,
-            while_cond,
+            cursor: 0,
             children,
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for WhileNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for SequenceNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -917,47 +1480,48 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = match self
-            .while_cond
-            .control_tick(blackboard, func, world, entity)
-        {
-            Running => {
-                self.children.iter_mut().for_each(|child| {
-                    child.control_tick(blackboard, func, world, entity);
-                });
-                Running
-            }
-            status @ (Success | Failure) => {
-                self.children.iter_mut().for_each(|child| {
-                    child.reset(blackboard, world, entity);
-                });
-                status
-            }
-            _ => panic_if_idle_or_branch!(
-                self.node_name(),
-                self.index,
-                self.while_cond.node_name(),
-                self.while_cond.node_index()
-            ),
-        };
-        set_status!(self, blackboard, status)
+        while self.cursor < self.children.len() {
+            match self.children[self.cursor].execute_tick(blackboard, func, world, entity) {
+                Success => self.cursor += 1,
+                Failure => {
+                    self.cursor = 0;
+                    return set_status!(self, blackboard, Failure);
+                }
+                Running => return set_status!(self, blackboard, Running),
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.children[self.cursor].node_name(),
+                    self.children[self.cursor].node_index()
+                ),
+            };
+        }
+        self.cursor = 0;
+        set_status!(self, blackboard, Success)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.while_cond.reset(ctx, world, entity);
-            self.children.iter_mut().for_each(|child| {
-                child.reset(ctx, world, entity);
-            });
+            reset_status!(self, blackboard);
+            self.children[self.cursor].reset(blackboard, world, entity);
+            self.cursor = 0;
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.cursor)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.cursor = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -979,37 +1543,68 @@ where
             >,
         >,
     > {
-        let mut children = vec![&self.while_cond];
-        self.children
-            .iter()
-            .for_each(|child| children.push(child));
-        children
+        self.children.iter().collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect()
     }
 }
 
+// Like `SequenceNode` but ticks the children from the first one on every tick, so that earlier
+// children are re-checked while a later one is running. The running child is reset once an
+// earlier child fails or starts running. Known as ReactiveSequence elsewhere.
 #[derive(TreeNodeStatus)]
-pub struct ParallelSequenceNode<A, C, F: ?Sized, W, E> {
+pub struct ReactiveSequenceNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    result: Status,
-    running_queue: Option<VecDeque<usize>>,
+    running_child: Option<usize>,
     children: Vec<TreeNodeType<A, C, F, W, E>>,
 }
-impl<A, C, F: ?Sized, W, E> ParallelSequenceNode<A, C, F, W, E> {
+impl<A, C, F: ?Sized, W, E> ReactiveSequenceNode<A, C, F, W, E> {
     pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
         let base = TreeNodeBase::default();
-        let result = Success;
-        let running_queue = None;
         Self {
             base,
             index,
-            result,
-            running_queue,
+            running_child: None,
             children,
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for ParallelSequenceNode<A, C, F, W, E>
+impl<A, C, F: ?Sized, W, E> ReactiveSequenceNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+{
+    // Resets the running child unless it is `now_child`, which runs from now on.
+    fn reset_running_child(
+        &mut self,
+        now_child: Option<usize>,
+        blackboard: &mut BlackBoard<C>,
+        world: &mut W,
+        entity: &E,
+    ) {
+        if let Some(running_child) = self.running_child.take() {
+            if Some(running_child) != now_child {
+                self.children[running_child].reset(blackboard, world, entity);
+            }
+        }
+        self.running_child = now_child;
+    }
+}
+impl<A, C, F, W, E> TreeNode for ReactiveSequenceNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -1027,51 +1622,50 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let mut pending_queue = match self.running_queue.take() {
-            Some(running_queue) => running_queue,
-            None => (0..self.children.len()).collect(),
-        };
-
-        let mut running_queue = VecDeque::new();
-        while let Some(index) = pending_queue.pop_front() {
-            match self.children[index].control_tick(blackboard, func, world, entity) {
+        for cursor in 0..self.children.len() {
+            match self.children[cursor].execute_tick(blackboard, func, world, entity) {
                 Success => {}
-                Failure => self.result = Failure,
-                Running => running_queue.push_back(index),
+                Failure => {
+                    self.reset_running_child(None, blackboard, world, entity);
+                    return set_status!(self, blackboard, Failure);
+                }
+                Running => {
+                    self.reset_running_child(Some(cursor), blackboard, world, entity);
+                    return set_status!(self, blackboard, Running);
+                }
                 _ => panic_if_idle_or_branch!(
                     self.node_name(),
                     self.index,
-                    self.children[index].node_name(),
-                    self.children[index].node_index()
+                    self.children[cursor].node_name(),
+                    self.children[cursor].node_index()
                 ),
             };
         }
-        let status = if running_queue.is_empty() {
-            self.result.replace(Success)
-        } else {
-            self.running_queue = Some(running_queue);
-            Running
-        };
-        set_status!(self, blackboard, status)
+        self.reset_running_child(None, blackboard, world, entity);
+        set_status!(self, blackboard, Success)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.result = Success;
-            if let Some(running_queue) = self.running_queue.take() {
-                for index in running_queue.into_iter() {
-                    self.children[index].reset(ctx, world, entity);
-                }
-            }
+            reset_status!(self, blackboard);
+            self.reset_running_child(None, blackboard, world, entity);
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.running_child)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.running_child = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -1093,33 +1687,68 @@ where
             >,
         >,
     > {
-        self.children.iter().collect::<Vec<_>>()
+        self.children.iter().collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect()
     }
 }
 
+// Like `SelectNode` but ticks the children from the first one on every tick, so that earlier
+// children are re-checked while a later one is running. The running child is reset once an
+// earlier child succeeds or starts running. Known as ReactiveFallback elsewhere.
 #[derive(TreeNodeStatus)]
-pub struct ParallelSelectNode<A, C, F: ?Sized, W, E> {
+pub struct ReactiveSelectNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    result: Status,
-    running_queue: Option<VecDeque<usize>>,
+    running_child: Option<usize>,
     children: Vec<TreeNodeType<A, C, F, W, E>>,
 }
-impl<A, C, F: ?Sized, W, E> ParallelSelectNode<A, C, F, W, E> {
+impl<A, C, F: ?Sized, W, E> ReactiveSelectNode<A, C, F, W, E> {
     pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
         let base = TreeNodeBase::default();
-        let result = Failure;
-        let running_queue = None;
         Self {
             base,
             index,
-            result,
-            running_queue,
+            running_child: None,
             children,
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for ParallelSelectNode<A, C, F, W, E>
+impl<A, C, F: ?Sized, W, E> ReactiveSelectNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+{
+    // Resets the running child unless it is `now_child`, which runs from now on.
+    fn reset_running_child(
+        &mut self,
+        now_child: Option<usize>,
+        blackboard: &mut BlackBoard<C>,
+        world: &mut W,
+        entity: &E,
+    ) {
+        if let Some(running_child) = self.running_child.take() {
+            if Some(running_child) != now_child {
+                self.children[running_child].reset(blackboard, world, entity);
+            }
+        }
+        self.running_child = now_child;
+    }
+}
+impl<A, C, F, W, E> TreeNode for ReactiveSelectNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -1137,51 +1766,50 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let mut pending_queue = match self.running_queue.take() {
-            Some(running_queue) => running_queue,
-            None => (0..self.children.len()).collect(),
-        };
-
-        let mut running_queue = VecDeque::<usize>::new();
-        while let Some(index) = pending_queue.pop_front() {
-            match self.children[index].control_tick(blackboard, func, world, entity) {
+        for cursor in 0..self.children.len() {
+            match self.children[cursor].execute_tick(blackboard, func, world, entity) {
                 Failure => {}
-                Success => self.result = Success,
-                Running => running_queue.push_back(index),
+                Success => {
+                    self.reset_running_child(None, blackboard, world, entity);
+                    return set_status!(self, blackboard, Success);
+                }
+                Running => {
+                    self.reset_running_child(Some(cursor), blackboard, world, entity);
+                    return set_status!(self, blackboard, Running);
+                }
                 _ => panic_if_idle_or_branch!(
                     self.node_name(),
                     self.index,
-                    self.children[index].node_name(),
-                    self.children[index].node_index()
+                    self.children[cursor].node_name(),
+                    self.children[cursor].node_index()
                 ),
             };
         }
-        let status = if running_queue.is_empty() {
-            self.result.replace(Failure)
-        } else {
-            self.running_queue = Some(running_queue);
-            Running
-        };
-        set_status!(self, blackboard, status)
+        self.reset_running_child(None, blackboard, world, entity);
+        set_status!(self, blackboard, Failure)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.result = Failure;
-            if let Some(running_queue) = self.running_queue.take() {
-                for index in running_queue.into_iter() {
-                    self.children[index].reset(ctx, world, entity);
-                }
-            }
+            reset_status!(self, blackboard);
+            self.reset_running_child(None, blackboard, world, entity);
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.running_child)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.running_child = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -1203,30 +1831,49 @@ where
             >,
         >,
     > {
-        self.children.iter().collect::<Vec<_>>()
+        self.children.iter().collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect()
     }
 }
 
 #[derive(TreeNodeStatus)]
-pub struct ParallelAndNode<A, C, F: ?Sized, W, E> {
+pub struct WhileNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    running_queue: Option<VecDeque<usize>>,
+    while_cond: TreeNodeType<A, C, F, W, E>,
     children: Vec<TreeNodeType<A, C, F, W, E>>,
 }
-impl<A, C, F: ?Sized, W, E> ParallelAndNode<A, C, F, W, E> {
-    pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
+impl<A, C, F: ?Sized, W, E> WhileNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        while_cond: TreeNodeType<A, C, F, W, E>,
+        children: Vec<TreeNodeType<A, C, F, W, E>>,
+    ) -> Self {
         let base = TreeNodeBase::default();
-        let running_queue = None;
         Self {
             base,
             index,
-            running_queue,
+            while_cond,
             children,
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for ParallelAndNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for WhileNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -1244,57 +1891,44 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let mut pending_queue = match self.running_queue.take() {
-            Some(running_queue) => running_queue,
-            None => (0..self.children.len()).collect(),
-        };
-
-        let mut running_queue = VecDeque::new();
-        while let Some(index) = pending_queue.pop_front() {
-            match self.children[index].control_tick(blackboard, func, world, entity) {
-                Success => {}
-                Running => running_queue.push_back(index),
-                Failure => {
-                    for index in running_queue.into_iter() {
-                        self.children[index].reset(blackboard, world, entity);
-                    }
-                    if self.is_running() {
-                        for index in pending_queue.into_iter() {
-                            self.children[index].reset(blackboard, world, entity);
-                        }
-                    }
-                    return set_status!(self, blackboard, Failure);
-                }
-                _ => panic_if_idle_or_branch!(
-                    self.node_name(),
-                    self.index,
-                    self.children[index].node_name(),
-                    self.children[index].node_index()
-                ),
-            };
-        }
-        let status = if running_queue.is_empty() {
-            Success
-        } else {
-            self.running_queue = Some(running_queue);
-            Running
+        let status = match self
+            .while_cond
+            .execute_tick(blackboard, func, world, entity)
+        {
+            Running => {
+                self.children.iter_mut().for_each(|child| {
+                    child.execute_tick(blackboard, func, world, entity);
+                });
+                Running
+            }
+            status @ (Success | Failure) => {
+                self.children.iter_mut().for_each(|child| {
+                    child.reset(blackboard, world, entity);
+                });
+                status
+            }
+            _ => panic_if_idle_or_branch!(
+                self.node_name(),
+                self.index,
+                self.while_cond.node_name(),
+                self.while_cond.node_index()
+            ),
         };
         set_status!(self, blackboard, status)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            if let Some(running_queue) = self.running_queue.take() {
-                for index in running_queue.into_iter() {
-                    self.children[index].reset(ctx, world, entity);
-                }
-            }
+            reset_status!(self, blackboard);
+            self.while_cond.reset(blackboard, world, entity);
+            self.children.iter_mut().for_each(|child| {
+                child.reset(blackboard, world, entity);
+            });
         }
     }
 
@@ -1319,30 +1953,57 @@ where
             >,
         >,
     > {
-        self.children.iter().collect::<Vec<_>>()
+        let mut children = vec![&self.while_cond];
+        self.children
+            .iter()
+            .for_each(|child| children.push(child));
+        children
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        let mut children = vec![&mut self.while_cond];
+        self.children
+            .iter_mut()
+            .for_each(|child| children.push(child));
+        children
     }
 }
 
 #[derive(TreeNodeStatus)]
-pub struct ParallelOrNode<A, C, F: ?Sized, W, E> {
+pub struct ParallelSequenceNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
+    result: Status,
     running_queue: Option<VecDeque<usize>>,
     children: Vec<TreeNodeType<A, C, F, W, E>>,
 }
-impl<A, C, F: ?Sized, W, E> ParallelOrNode<A, C, F, W, E> {
+impl<A, C, F: ?Sized, W, E> ParallelSequenceNode<A, C, F, W, E> {
     pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
         let base = TreeNodeBase::default();
+        let result = Success;
         let running_queue = None;
         Self {
             base,
             index,
+            result,
             running_queue,
             children,
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for ParallelOrNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for ParallelSequenceNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -1367,20 +2028,10 @@ where
 
         let mut running_queue = VecDeque::new();
         while let Some(index) = pending_queue.pop_front() {
-            match self.children[index].control_tick(blackboard, func, world, entity) {
-                Failure => {}
+            match self.children[index].execute_tick(blackboard, func, world, entity) {
+                Success => {}
+                Failure => self.result = Failure,
                 Running => running_queue.push_back(index),
-                Success => {
-                    for index in running_queue.into_iter() {
-                        self.children[index].reset(blackboard, world, entity);
-                    }
-                    if self.is_running() {
-                        for index in pending_queue.into_iter() {
-                            self.children[index].reset(blackboard, world, entity);
-                        }
-                    }
-                    return set_status!(self, blackboard, Success);
-                }
                 _ => panic_if_idle_or_branch!(
                     self.node_name(),
                     self.index,
@@ -1390,7 +2041,7 @@ where
             };
         }
         let status = if running_queue.is_empty() {
-            Failure
+            self.result.replace(Success)
         } else {
             self.running_queue = Some(running_queue);
             Running
@@ -1400,20 +2051,32 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
+            reset_status!(self, blackboard);
+            self.result = Success;
             if let Some(running_queue) = self.running_queue.take() {
                 for index in running_queue.into_iter() {
-                    self.children[index].reset(ctx, world, entity);
+                    self.children[index].reset(blackboard, world, entity);
                 }
             }
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(self.result, &self.running_queue))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        let (result, running_queue) = state.decode()?;
+        self.result = result;
+        self.running_queue = running_queue;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -1437,58 +2100,1536 @@ where
     > {
         self.children.iter().collect::<Vec<_>>()
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect::<Vec<_>>()
+    }
 }
 
 #[derive(TreeNodeStatus)]
-pub struct WeightSelectNode<A, C, F: ?Sized, W, E> {
+pub struct ParallelSelectNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    prev_branch: Option<usize>,
-    select_weights: Vec<f64>,
-    select_children: Vec<TreeNodeType<A, C, F, W, E>>,
+    result: Status,
+    running_queue: Option<VecDeque<usize>>,
+    children: Vec<TreeNodeType<A, C, F, W, E>>,
 }
-impl<A, C, F: ?Sized, W, E> WeightSelectNode<A, C, F, W, E> {
-    pub fn new(
-        index: i32,
-        select_weights_str: &str,
-        select_children: Vec<TreeNodeType<A, C, F, W, E>>,
-    ) -> Self {
-        assert!(
-            !select_children.is_empty(),
-            "WeightSelectNode' select children must not be empty, node_index={}",
+impl<A, C, F: ?Sized, W, E> ParallelSelectNode<A, C, F, W, E> {
+    pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
+        let base = TreeNodeBase::default();
+        let result = Failure;
+        let running_queue = None;
+        Self {
+            base,
+            index,
+            result,
+            running_queue,
+            children,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for ParallelSelectNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let mut pending_queue = match self.running_queue.take() {
+            Some(running_queue) => running_queue,
+            None => (0..self.children.len()).collect(),
+        };
+
+        let mut running_queue = VecDeque::<usize>::new();
+        while let Some(index) = pending_queue.pop_front() {
+            match self.children[index].execute_tick(blackboard, func, world, entity) {
+                Failure => {}
+                Success => self.result = Success,
+                Running => running_queue.push_back(index),
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.children[index].node_name(),
+                    self.children[index].node_index()
+                ),
+            };
+        }
+        let status = if running_queue.is_empty() {
+            self.result.replace(Failure)
+        } else {
+            self.running_queue = Some(running_queue);
+            Running
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.result = Failure;
+            if let Some(running_queue) = self.running_queue.take() {
+                for index in running_queue.into_iter() {
+                    self.children[index].reset(blackboard, world, entity);
+                }
+            }
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(self.result, &self.running_queue))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        let (result, running_queue) = state.decode()?;
+        self.result = result;
+        self.running_queue = running_queue;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::ControlNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter().collect::<Vec<_>>()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect::<Vec<_>>()
+    }
+}
+
+#[derive(TreeNodeStatus)]
+pub struct ParallelAndNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    running_queue: Option<VecDeque<usize>>,
+    children: Vec<TreeNodeType<A, C, F, W, E>>,
+}
+impl<A, C, F: ?Sized, W, E> ParallelAndNode<A, C, F, W, E> {
+    pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
+        let base = TreeNodeBase::default();
+        let running_queue = None;
+        Self {
+            base,
+            index,
+            running_queue,
+            children,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for ParallelAndNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let mut pending_queue = match self.running_queue.take() {
+            Some(running_queue) => running_queue,
+            None => (0..self.children.len()).collect(),
+        };
+
+        let mut running_queue = VecDeque::new();
+        while let Some(index) = pending_queue.pop_front() {
+            match self.children[index].execute_tick(blackboard, func, world, entity) {
+                Success => {}
+                Running => running_queue.push_back(index),
+                Failure => {
+                    for index in running_queue.into_iter() {
+                        self.children[index].reset(blackboard, world, entity);
+                    }
+                    if self.is_running() {
+                        for index in pending_queue.into_iter() {
+                            self.children[index].reset(blackboard, world, entity);
+                        }
+                    }
+                    return set_status!(self, blackboard, Failure);
+                }
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.children[index].node_name(),
+                    self.children[index].node_index()
+                ),
+            };
+        }
+        let status = if running_queue.is_empty() {
+            Success
+        } else {
+            self.running_queue = Some(running_queue);
+            Running
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            if let Some(running_queue) = self.running_queue.take() {
+                for index in running_queue.into_iter() {
+                    self.children[index].reset(blackboard, world, entity);
+                }
+            }
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.running_queue)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.running_queue = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::ControlNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter().collect::<Vec<_>>()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect::<Vec<_>>()
+    }
+}
+
+#[derive(TreeNodeStatus)]
+pub struct ParallelOrNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    running_queue: Option<VecDeque<usize>>,
+    children: Vec<TreeNodeType<A, C, F, W, E>>,
+}
+impl<A, C, F: ?Sized, W, E> ParallelOrNode<A, C, F, W, E> {
+    pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
+        let base = TreeNodeBase::default();
+        let running_queue = None;
+        Self {
+            base,
+            index,
+            running_queue,
+            children,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for ParallelOrNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let mut pending_queue = match self.running_queue.take() {
+            Some(running_queue) => running_queue,
+            None => (0..self.children.len()).collect(),
+        };
+
+        let mut running_queue = VecDeque::new();
+        while let Some(index) = pending_queue.pop_front() {
+            match self.children[index].execute_tick(blackboard, func, world, entity) {
+                Failure => {}
+                Running => running_queue.push_back(index),
+                Success => {
+                    for index in running_queue.into_iter() {
+                        self.children[index].reset(blackboard, world, entity);
+                    }
+                    if self.is_running() {
+                        for index in pending_queue.into_iter() {
+                            self.children[index].reset(blackboard, world, entity);
+                        }
+                    }
+                    return set_status!(self, blackboard, Success);
+                }
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.children[index].node_name(),
+                    self.children[index].node_index()
+                ),
+            };
+        }
+        let status = if running_queue.is_empty() {
+            Failure
+        } else {
+            self.running_queue = Some(running_queue);
+            Running
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            if let Some(running_queue) = self.running_queue.take() {
+                for index in running_queue.into_iter() {
+                    self.children[index].reset(blackboard, world, entity);
+                }
+            }
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.running_queue)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.running_queue = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::ControlNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter().collect::<Vec<_>>()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect::<Vec<_>>()
+    }
+}
+
+// What `ParallelNode` does with its still running children once a threshold is reached.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ParallelPolicy {
+    // resets them and completes right away.
+    #[default]
+    HaltRunning,
+    // keeps ticking them until all are done, their results don't change the outcome.
+    WaitRunning,
+}
+
+#[derive(TreeNodeStatus)]
+pub struct ParallelNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    success_threshold: usize,
+    failure_threshold: usize,
+    policy: ParallelPolicy,
+    success_count: usize,
+    failure_count: usize,
+    result: Option<Status>,
+    running_queue: Option<VecDeque<usize>>,
+    children: Vec<TreeNodeType<A, C, F, W, E>>,
+}
+impl<A, C, F: ?Sized, W, E> ParallelNode<A, C, F, W, E> {
+    // A negative threshold counts from the number of children, -1 means all of them.
+    pub fn new(
+        index: i32,
+        success_threshold: i32,
+        failure_threshold: i32,
+        policy: ParallelPolicy,
+        children: Vec<TreeNodeType<A, C, F, W, E>>,
+    ) -> Self {
+        assert!(
+            !children.is_empty(),
+            "ParallelNode' children must not be empty, node_index={}",
+            index
+        );
+        let resolve_threshold = |threshold: i32| {
+            let resolved = match threshold {
+                threshold if threshold < 0 => children.len() as i32 + 1 + threshold,
+                threshold => threshold,
+            };
+            assert!(
+                resolved >= 1 && resolved <= children.len() as i32,
+                "ParallelNode' threshold must be in [1, children_len], node_index={}, threshold={}, children_len={}",
+                index,
+                threshold,
+                children.len()
+            );
+            resolved as usize
+        };
+        let success_threshold = resolve_threshold(success_threshold);
+        let failure_threshold = resolve_threshold(failure_threshold);
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            success_threshold,
+            failure_threshold,
+            policy,
+            success_count: 0,
+            failure_count: 0,
+            result: None,
+            running_queue: None,
+            children,
+        }
+    }
+
+    // Success wins over failure, failure is also decided once too few children are left to
+    // reach the success threshold.
+    fn decide(&self) -> Option<Status> {
+        if self.success_count >= self.success_threshold {
+            Some(Success)
+        } else if self.failure_count >= self.failure_threshold
+            || self.children.len() - self.failure_count < self.success_threshold
+        {
+            Some(Failure)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn clear_result(&mut self) -> Option<Status> {
+        self.success_count = 0;
+        self.failure_count = 0;
+        self.result.take()
+    }
+}
+impl<A, C, F, W, E> TreeNode for ParallelNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let mut pending_queue = match self.running_queue.take() {
+            Some(running_queue) => running_queue,
+            None => (0..self.children.len()).collect(),
+        };
+
+        let mut running_queue = VecDeque::new();
+        while let Some(index) = pending_queue.pop_front() {
+            match self.children[index].execute_tick(blackboard, func, world, entity) {
+                Success => self.success_count += 1,
+                Failure => self.failure_count += 1,
+                Running => running_queue.push_back(index),
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.children[index].node_name(),
+                    self.children[index].node_index()
+                ),
+            };
+            if self.result.is_none() {
+                self.result = self.decide();
+            }
+            if self.result.is_some() && self.policy == ParallelPolicy::HaltRunning {
+                for index in running_queue.into_iter() {
+                    self.children[index].reset(blackboard, world, entity);
+                }
+                if self.is_running() {
+                    for index in pending_queue.into_iter() {
+                        self.children[index].reset(blackboard, world, entity);
+                    }
+                }
+                let status = self.clear_result().unwrap();
+                return set_status!(self, blackboard, status);
+            }
+        }
+        let status = if running_queue.is_empty() {
+            // all children done, the thresholds always decide by then.
+            self.clear_result().unwrap()
+        } else {
+            self.running_queue = Some(running_queue);
+            Running
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.clear_result();
+            if let Some(running_queue) = self.running_queue.take() {
+                for index in running_queue.into_iter() {
+                    self.children[index].reset(blackboard, world, entity);
+                }
+            }
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(
+            self.success_count,
+            self.failure_count,
+            self.result,
+            &self.running_queue,
+        ))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        let (success_count, failure_count, result, running_queue) = state.decode()?;
+        self.success_count = success_count;
+        self.failure_count = failure_count;
+        self.result = result;
+        self.running_queue = running_queue;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::ControlNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter().collect::<Vec<_>>()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect::<Vec<_>>()
+    }
+}
+
+// Weight of a `WeightSelectNode` branch or probability of a `ChanceNode`: a number, a numeric
+// blackboard value as `<name>`, or an expression, read again at each selection.
+pub enum SelectWeight<C, W, E> {
+    Constant(f64),
+    BlackBoard(SharedBlackBoardValue),
+    Expression(ExpressionScorer<C, W, E>),
+}
+impl<C: Unpin + Default + MaybeSend + 'static, W: 'static, E: 'static> SelectWeight<C, W, E> {
+    // Expressions can call the functions registered to the factory.
+    pub fn parse(
+        tree_name: &str,
+        tree_index: i32,
+        tree_depth: i32,
+        index: i32,
+        weight_str: &str,
+        bb_map: &BlackBoardMap,
+        #[allow(unused)] factory: &BtFactory<C, W, E>,
+    ) -> Result<Self, BehaviorError> {
+        let weight_str = weight_str.trim();
+        let invalid_weight = |error_info: String| BehaviorError::InvalidSelectWeight {
+            tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
+            node_index: index,
+            weight_str: weight_str.to_string(),
+            error_info,
+        };
+        if let Some(bb_name) = weight_str
+            .strip_prefix('<')
+            .and_then(|weight_str| weight_str.strip_suffix('>'))
+        {
+            let bb_value = bb_map
+                .get(bb_name)
+                .ok_or_else(|| invalid_weight(format!("blackboard {} not found", bb_name)))?;
+            if bb_value.get_as_f64().is_none() {
+                return Err(invalid_weight(format!(
+                    "blackboard {} is not numeric, bb_type={}",
+                    bb_name,
+                    bb_value.bb_type()
+                )));
+            }
+            return Ok(SelectWeight::BlackBoard(bb_value.share()));
+        }
+        if let Ok(weight) = weight_str.parse::<f64>() {
+            return Ok(SelectWeight::Constant(weight));
+        }
+        #[cfg(feature = "expression_node")]
+        {
+            ExpressionScorer::new(
+                tree_name,
+                tree_index,
+                tree_depth,
+                index,
+                weight_str,
+                bb_map,
+                factory.expression_functions(),
+            )
+            .map(SelectWeight::Expression)
+        }
+        #[cfg(not(feature = "expression_node"))]
+        Err(invalid_weight(
+            "not a number or blackboard reference".to_string(),
+        ))
+    }
+
+    // Parses weights separated by `|`.
+    pub fn parse_weights(
+        tree_name: &str,
+        tree_index: i32,
+        tree_depth: i32,
+        index: i32,
+        select_weights_str: &str,
+        bb_map: &BlackBoardMap,
+        factory: &BtFactory<C, W, E>,
+    ) -> Result<Vec<Self>, BehaviorError> {
+        select_weights_str
+            .split('|')
+            .map(|weight_str| {
+                Self::parse(
+                    tree_name, tree_index, tree_depth, index, weight_str, bb_map, factory,
+                )
+            })
+            .collect()
+    }
+}
+impl<C, W, E> SelectWeight<C, W, E> {
+    // None if the value can't be read as a number.
+    pub(crate) fn weight(
+        &mut self,
+        blackboard: &mut BlackBoard<C>,
+        world: &mut W,
+        entity: &E,
+    ) -> Option<f64> {
+        match self {
+            SelectWeight::Constant(weight) => Some(*weight),
+            SelectWeight::BlackBoard(bb_value) => bb_value.get_as_f64(),
+            SelectWeight::Expression(scorer) => scorer.score(blackboard, world, entity),
+        }
+    }
+
+    // Negative and unreadable weights count as 0.
+    #[inline]
+    fn positive_weight(
+        &mut self,
+        blackboard: &mut BlackBoard<C>,
+        world: &mut W,
+        entity: &E,
+    ) -> f64 {
+        self.weight(blackboard, world, entity)
+            .filter(|weight| *weight > 0.0 && weight.is_finite())
+            .unwrap_or(0.0)
+    }
+}
+
+#[derive(TreeNodeStatus)]
+pub struct WeightSelectNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    prev_branch: Option<usize>,
+    select_weights: Vec<SelectWeight<C, W, E>>,
+    // weights of the last selection, negative and unreadable weights count as 0.
+    weights: Vec<f64>,
+    select_children: Vec<TreeNodeType<A, C, F, W, E>>,
+}
+impl<A, C, F: ?Sized, W, E> WeightSelectNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        select_weights: Vec<SelectWeight<C, W, E>>,
+        select_children: Vec<TreeNodeType<A, C, F, W, E>>,
+    ) -> Self {
+        assert!(
+            !select_children.is_empty(),
+            "WeightSelectNode' select children must not be empty, node_index={}",
+            index
+        );
+        assert!(
+            select_weights.len() == select_children.len(),
+            "WeightSelectNode' select weights must equal to select children, node_index={}, select_weights_len={}, select_children_len={}",
+            index,
+            select_weights.len(),
+            select_children.len()
+        );
+        let constant_weights: Vec<_> = select_weights
+            .iter()
+            .filter_map(|weight| match weight {
+                SelectWeight::Constant(weight) => Some(*weight),
+                _ => None,
+            })
+            .collect();
+        // dynamic weights are checked at each selection.
+        if constant_weights.len() == select_weights.len() {
+            if let Err(error) = WeightedIndex::new(&constant_weights) {
+                panic!(
+                    "WeightSelectNode' select weights is not invalid, node_index={}, select_weights={:?}, error={:?}",
+                    index, constant_weights, error
+                );
+            }
+        }
+        let base = TreeNodeBase::default();
+        let prev_branch = None;
+        let weights = vec![0.0; select_weights.len()];
+        Self {
+            base,
+            index,
+            prev_branch,
+            select_weights,
+            weights,
+            select_children,
+        }
+    }
+
+    fn update_weights(
+        &mut self,
+        blackboard: &mut BlackBoard<C>,
+        world: &mut W,
+        entity: &E,
+    ) -> bool {
+        for (weight, select_weight) in self.weights.iter_mut().zip(self.select_weights.iter_mut()) {
+            *weight = select_weight.positive_weight(blackboard, world, entity);
+        }
+        self.weights.iter().any(|weight| *weight > 0.0)
+    }
+}
+impl<A, C, F, W, E> TreeNode for WeightSelectNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let now_branch = match self.prev_branch.take() {
+            Some(prev_branch) => prev_branch,
+            None => {
+                if !self.update_weights(blackboard, world, entity) {
+                    eprintln!(
+                        "WeightSelectNode::control_tick error, all select weights are zero or negative, node_name={}, node_index={}, select_weights={:?}",
+                        self.node_name(),
+                        self.index,
+                        self.weights
+                    );
+                    return set_status!(self, blackboard, Failure);
+                }
+                match weight_select_index_with_rng(blackboard.rng_mut(), &self.weights) {
+                    Some(now_branch) => now_branch,
+                    None => {
+                        eprintln!(
+                            "WeightSelectNode::control_tick error, invalid select weights, node_name={}, node_index={}, select_weights={:?}",
+                            self.node_name(),
+                            self.index,
+                            self.weights
+                        );
+                        return set_status!(self, blackboard, Failure);
+                    }
+                }
+            }
+        };
+
+        let status =
+            match self.select_children[now_branch].execute_tick(blackboard, func, world, entity) {
+                status @ (Success | Failure | Running) => status,
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.select_children[now_branch].node_name(),
+                    self.select_children[now_branch].node_index()
+                ),
+            };
+
+        if status == Running {
+            self.prev_branch = Some(now_branch);
+        }
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            if let Some(running_branch) = self.prev_branch.take() {
+                self.select_children[running_branch].reset(blackboard, world, entity);
+            }
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.prev_branch)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.prev_branch = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::ControlNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.select_children.iter().collect::<Vec<_>>()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.select_children.iter_mut().collect::<Vec<_>>()
+    }
+}
+
+// Order in which a `RandomSelectNode` or `RandomSequenceNode` tries its children, drawn with
+// the rng of the instance each time the node starts. With weights the children are drawn one
+// after another in proportion to them, children without a positive weight come last.
+struct RandomOrder<C, W, E> {
+    select_weights: Option<Vec<SelectWeight<C, W, E>>>,
+    weights: Vec<f64>,
+    order: Vec<usize>,
+}
+impl<C, W, E> RandomOrder<C, W, E> {
+    fn new(
+        index: i32,
+        select_weights: Option<Vec<SelectWeight<C, W, E>>>,
+        children_len: usize,
+    ) -> Self {
+        if let Some(select_weights) = &select_weights {
+            assert!(
+                select_weights.len() == children_len,
+                "RandomOrder' select weights must equal to children, node_index={}, select_weights_len={}, children_len={}",
+                index,
+                select_weights.len(),
+                children_len
+            );
+        }
+        Self {
+            select_weights,
+            weights: vec![0.0; children_len],
+            order: (0..children_len).collect(),
+        }
+    }
+
+    fn shuffle(&mut self, blackboard: &mut BlackBoard<C>, world: &mut W, entity: &E) {
+        match &mut self.select_weights {
+            None => random_shuffle_with_rng(blackboard.rng_mut(), &mut self.order),
+            Some(select_weights) => {
+                for (weight, select_weight) in self.weights.iter_mut().zip(select_weights) {
+                    *weight = select_weight.positive_weight(blackboard, world, entity);
+                }
+                self.order.clear();
+                while let Some(child) =
+                    weight_select_index_with_rng(blackboard.rng_mut(), &self.weights)
+                {
+                    self.order.push(child);
+                    self.weights[child] = 0.0;
+                }
+                for child in 0..self.weights.len() {
+                    if !self.order.contains(&child) {
+                        self.order.push(child);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// `SelectNode` trying its children in random order, see `RandomOrder`.
+#[derive(TreeNodeStatus)]
+pub struct RandomSelectNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    cursor: usize,
+    random_order: RandomOrder<C, W, E>,
+    children: Vec<TreeNodeType<A, C, F, W, E>>,
+}
+impl<A, C, F: ?Sized, W, E> RandomSelectNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        select_weights: Option<Vec<SelectWeight<C, W, E>>>,
+        children: Vec<TreeNodeType<A, C, F, W, E>>,
+    ) -> Self {
+        let base = TreeNodeBase::default();
+        let random_order = RandomOrder::new(index, select_weights, children.len());
+        Self {
+            base,
+            index,
+            cursor: 0,
+            random_order,
+            children,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for RandomSelectNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        if !self.is_running() {
+            self.random_order.shuffle(blackboard, world, entity);
+        }
+        while self.cursor < self.children.len() {
+            let child = self.random_order.order[self.cursor];
+            match self.children[child].execute_tick(blackboard, func, world, entity) {
+                Failure => self.cursor += 1,
+                Success => {
+                    self.cursor = 0;
+                    return set_status!(self, blackboard, Success);
+                }
+                Running => return set_status!(self, blackboard, Running),
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.children[child].node_name(),
+                    self.children[child].node_index()
+                ),
+            };
+        }
+        self.cursor = 0;
+        set_status!(self, blackboard, Failure)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.children[self.random_order.order[self.cursor]].reset(blackboard, world, entity);
+            self.cursor = 0;
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(self.cursor, &self.random_order.order))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        (self.cursor, self.random_order.order) = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::ControlNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter().collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect()
+    }
+}
+
+// `SequenceNode` trying its children in random order, see `RandomOrder`.
+#[derive(TreeNodeStatus)]
+pub struct RandomSequenceNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    cursor: usize,
+    random_order: RandomOrder<C, W, E>,
+    children: Vec<TreeNodeType<A, C, F, W, E>>,
+}
+impl<A, C, F: ?Sized, W, E> RandomSequenceNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        select_weights: Option<Vec<SelectWeight<C, W, E>>>,
+        children: Vec<TreeNodeType<A, C, F, W, E>>,
+    ) -> Self {
+        let base = TreeNodeBase::default();
+        let random_order = RandomOrder::new(index, select_weights, children.len());
+        Self {
+            base,
+            index,
+            cursor: 0,
+            random_order,
+            children,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for RandomSequenceNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        if !self.is_running() {
+            self.random_order.shuffle(blackboard, world, entity);
+        }
+        while self.cursor < self.children.len() {
+            let child = self.random_order.order[self.cursor];
+            match self.children[child].execute_tick(blackboard, func, world, entity) {
+                Success => self.cursor += 1,
+                Failure => {
+                    self.cursor = 0;
+                    return set_status!(self, blackboard, Failure);
+                }
+                Running => return set_status!(self, blackboard, Running),
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.children[child].node_name(),
+                    self.children[child].node_index()
+                ),
+            };
+        }
+        self.cursor = 0;
+        set_status!(self, blackboard, Success)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.children[self.random_order.order[self.cursor]].reset(blackboard, world, entity);
+            self.cursor = 0;
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(self.cursor, &self.random_order.order))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        (self.cursor, self.random_order.order) = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::ControlNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter().collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect()
+    }
+}
+
+// `SelectNode` starting with the child after the one that succeeded last time, or one child
+// further if none succeeded, so that the children take turns.
+#[derive(TreeNodeStatus)]
+pub struct RoundRobinSelectNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    start: usize,
+    cursor: usize,
+    children: Vec<TreeNodeType<A, C, F, W, E>>,
+}
+impl<A, C, F: ?Sized, W, E> RoundRobinSelectNode<A, C, F, W, E> {
+    pub fn new(index: i32, children: Vec<TreeNodeType<A, C, F, W, E>>) -> Self {
+        assert!(
+            !children.is_empty(),
+            "RoundRobinSelectNode' children must not be empty, node_index={}",
+            index
+        );
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            start: 0,
+            cursor: 0,
+            children,
+        }
+    }
+
+    #[inline]
+    fn child(&self) -> usize {
+        (self.start + self.cursor) % self.children.len()
+    }
+}
+impl<A, C, F, W, E> TreeNode for RoundRobinSelectNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        while self.cursor < self.children.len() {
+            let child = self.child();
+            match self.children[child].execute_tick(blackboard, func, world, entity) {
+                Failure => self.cursor += 1,
+                Running => return set_status!(self, blackboard, Running),
+                Success => {
+                    self.start = (child + 1) % self.children.len();
+                    self.cursor = 0;
+                    return set_status!(self, blackboard, Success);
+                }
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.children[child].node_name(),
+                    self.children[child].node_index()
+                ),
+            };
+        }
+        self.start = (self.start + 1) % self.children.len();
+        self.cursor = 0;
+        set_status!(self, blackboard, Failure)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            let child = self.child();
+            self.children[child].reset(blackboard, world, entity);
+            self.cursor = 0;
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(self.start, self.cursor))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        (self.start, self.cursor) = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::ControlNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter().collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect()
+    }
+}
+
+// Runtime scorer of a `UtilitySelectNode` child.
+pub enum Scorer<A, C, W, E> {
+    Constant(f64),
+    Expression(ExpressionScorer<C, W, E>),
+    Action(A),
+}
+impl<A, C, W, E> Scorer<A, C, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+{
+    // NaN and failed expressions never win.
+    fn score(&mut self, blackboard: &mut BlackBoard<C>, world: &mut W, entity: &E) -> f64 {
+        let score = match self {
+            Scorer::Constant(score) => *score,
+            Scorer::Expression(scorer) => scorer
+                .score(blackboard, world, entity)
+                .unwrap_or(f64::NEG_INFINITY),
+            Scorer::Action(action) => action.action_score(blackboard.context_mut(), world, entity),
+        };
+        if score.is_nan() {
+            f64::NEG_INFINITY
+        } else {
+            score
+        }
+    }
+}
+
+#[derive(TreeNodeStatus)]
+pub struct UtilitySelectNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    reevaluate: bool,
+    hysteresis: f64,
+    // children ordered by score for the current activation, tried one after another on failure.
+    order: Vec<usize>,
+    cursor: usize,
+    scores: Vec<f64>,
+    scorers: Vec<Scorer<A, C, W, E>>,
+    children: Vec<TreeNodeType<A, C, F, W, E>>,
+}
+impl<A, C, F: ?Sized, W, E> UtilitySelectNode<A, C, F, W, E> {
+    // While running with `reevaluate`, another child takes over only if it scores more than
+    // `hysteresis` above the running one.
+    pub fn new(
+        index: i32,
+        reevaluate: bool,
+        hysteresis: f64,
+        scorers: Vec<Scorer<A, C, W, E>>,
+        children: Vec<TreeNodeType<A, C, F, W, E>>,
+    ) -> Self {
+        assert!(
+            !children.is_empty(),
+            "UtilitySelectNode' children must not be empty, node_index={}",
             index
         );
-        let select_weights: Vec<_> = select_weights_str
-            .split('|')
-            .filter_map(|weight_str| weight_str.parse::<f64>().ok())
-            .collect();
         assert!(
-            select_weights.len() == select_children.len(),
-            "WeightSelectNode' branch priorities must equal to branch children, node_index={}, branch_priorities_str={}, branch_children_len={}",
+            scorers.len() == children.len(),
+            "UtilitySelectNode' scorers must equal to children, node_index={}, scorers_len={}, children_len={}",
             index,
-            select_weights_str,
-            select_children.len()
+            scorers.len(),
+            children.len()
+        );
+        assert!(
+            hysteresis >= 0.0,
+            "UtilitySelectNode' hysteresis must not be negative, node_index={}, hysteresis={}",
+            index,
+            hysteresis
         );
-        if let Err(error) = WeightedIndex::new(&select_weights) {
-            panic!(
-                "WeightSelectNode' select weights is not invalid, node_index={}, select_weights_str={}, error={:?}",
-                index, 
-                select_weights_str,
-                error
-            );
-        }
         let base = TreeNodeBase::default();
-        let prev_branch = None;
         Self {
             base,
             index,
-            prev_branch,
-            select_weights,
-            select_children,
+            reevaluate,
+            hysteresis,
+            order: Vec::new(),
+            cursor: 0,
+            scores: vec![0.0; children.len()],
+            scorers,
+            children,
+        }
+    }
+
+    // Descending by score, ties keep the order of the children.
+    #[inline]
+    fn sort_by_score(&mut self) {
+        let scores = &self.scores;
+        self.order = (0..scores.len()).collect();
+        self.order
+            .sort_by(|left, right| scores[*right].total_cmp(&scores[*left]));
+        self.cursor = 0;
+    }
+}
+impl<A, C, F: ?Sized, W, E> UtilitySelectNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+{
+    #[inline]
+    fn update_scores(&mut self, blackboard: &mut BlackBoard<C>, world: &mut W, entity: &E) {
+        for (score, scorer) in self.scores.iter_mut().zip(self.scorers.iter_mut()) {
+            *score = scorer.score(blackboard, world, entity);
         }
+        #[cfg(feature = "tree_visualization")]
+        blackboard.update_node_scores(self.index, &self.scores);
     }
 }
-impl<A, C, F, W, E> TreeNode for WeightSelectNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for UtilitySelectNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -1506,42 +3647,71 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let now_branch = match self.prev_branch.take() {
-            Some(prev_branch) => prev_branch,
-            None => weight_select_index(&self.select_weights).unwrap(),
-        };
+        if !self.is_running() {
+            self.update_scores(blackboard, world, entity);
+            self.sort_by_score();
+        } else if self.reevaluate {
+            self.update_scores(blackboard, world, entity);
+            let running = self.order[self.cursor];
+            let best = (0..self.scores.len())
+                .max_by(|left, right| {
+                    self.scores[*left]
+                        .total_cmp(&self.scores[*right])
+                        .then(right.cmp(left))
+                })
+                .unwrap();
+            if best != running && self.scores[best] > self.scores[running] + self.hysteresis {
+                self.children[running].reset(blackboard, world, entity);
+                self.sort_by_score();
+            }
+        }
 
-        let status =
-            match self.select_children[now_branch].control_tick(blackboard, func, world, entity) {
-                status @ (Success | Failure | Running) => status,
+        while self.cursor < self.order.len() {
+            let index = self.order[self.cursor];
+            match self.children[index].execute_tick(blackboard, func, world, entity) {
+                Failure => self.cursor += 1,
+                Running => return set_status!(self, blackboard, Running),
+                Success => {
+                    self.cursor = 0;
+                    return set_status!(self, blackboard, Success);
+                }
                 _ => panic_if_idle_or_branch!(
                     self.node_name(),
                     self.index,
-                    self.select_children[now_branch].node_name(),
-                    self.select_children[now_branch].node_index()
+                    self.children[index].node_name(),
+                    self.children[index].node_index()
                 ),
             };
-
-        if status == Running {
-            self.prev_branch = Some(now_branch);
         }
-        set_status!(self, blackboard, status)
+        self.cursor = 0;
+        set_status!(self, blackboard, Failure)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            if let Some(running_branch) = self.prev_branch.take() {
-                self.select_children[running_branch].reset(ctx, world, entity);
-            }
+            reset_status!(self, blackboard);
+            self.children[self.order[self.cursor]].reset(blackboard, world, entity);
+            self.cursor = 0;
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(&self.order, self.cursor, &self.scores))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        let (order, cursor, scores) = state.decode()?;
+        self.order = order;
+        self.cursor = cursor;
+        self.scores = scores;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -1563,6 +3733,573 @@ where
             >,
         >,
     > {
-        self.select_children.iter().collect::<Vec<_>>()
+        self.children.iter().collect()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        self.children.iter_mut().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::factory::{BtFactory, BtInstance, BtInstanceOptions};
+    use crate::node::{BtNode, BtNodeGenerator, Executor};
+    use crate::{
+        BehaviorError, BlackBoardMap, BranchData, ManualClock, Status, TreeNodeBase, TreeNodeStatus,
+    };
+    use behavior_macros::TreeNodeStatus;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // a test action configured by its `op` and `slot` metadata, "push" pushes the slot to the
+    // world and succeeds, "branches" selects the branches listed in the world, the default one
+    // succeeds while the world value at the slot is positive. The score is that value.
+    #[derive(Debug, Default, TreeNodeStatus)]
+    struct WorldNode {
+        base: TreeNodeBase,
+        op: String,
+        slot: usize,
+    }
+
+    impl BtNodeGenerator for WorldNode {
+        type Context = ();
+        type World = Vec<f64>;
+        type Entity = ();
+
+        fn generate_node(
+            _tree_name: &str,
+            _tree_index: i32,
+            _tree_depth: i32,
+            _node_name: &str,
+            _node_index: i32,
+            _blackboard_map: &BlackBoardMap,
+            metadata_map: Option<&HashMap<String, String>>,
+            _blackboard_ref_map: Option<&HashMap<String, String>>,
+            _dynamic_ref_map: Option<&HashMap<String, String>>,
+        ) -> Result<Executor<(), Vec<f64>, ()>, BehaviorError> {
+            let op = metadata_map
+                .and_then(|map| map.get("op"))
+                .cloned()
+                .unwrap_or_default();
+            let slot = metadata_map
+                .and_then(|map| map.get("slot"))
+                .and_then(|slot| slot.parse().ok())
+                .unwrap_or_default();
+            Ok(Box::new(WorldNode {
+                base: TreeNodeBase::default(),
+                op,
+                slot,
+            }))
+        }
+    }
+
+    #[cfg(feature = "gen_editor_data")]
+    impl crate::EditorNodeDataGenerator for WorldNode {
+        fn generate_editor_node_data() -> serde_json::Value {
+            serde_json::Value::Null
+        }
+    }
+
+    impl BtNode for WorldNode {
+        type Context = ();
+        type World = Vec<f64>;
+        type Entity = ();
+
+        fn tick(&mut self, _ctx: &mut (), world: &mut Vec<f64>, _entity: &()) -> Status {
+            match self.op.as_str() {
+                "push" => {
+                    world.push(self.slot as f64);
+                    Status::Success
+                }
+                "branches" => Status::Branch(BranchData::multiple_branch(
+                    world
+                        .iter()
+                        .map(|branch| *branch as usize)
+                        .collect::<Vec<_>>(),
+                )),
+                _ if world[self.slot] > 0.0 => Status::Success,
+                _ => Status::Failure,
+            }
+        }
+
+        fn score(&mut self, _ctx: &mut (), world: &mut Vec<f64>, _entity: &()) -> f64 {
+            world[self.slot]
+        }
+    }
+
+    fn node_status<W>(instance: &BtInstance<(), W, ()>, position: usize) -> Status {
+        instance.snapshot().unwrap().nodes[position].status
+    }
+
+    fn tick_statuses(
+        bt_factory: &BtFactory<(), (), ()>,
+        tree_name: &str,
+        seed: u64,
+    ) -> Vec<Status> {
+        let mut instance = bt_factory
+            .create_tree_instance_with_options(
+                tree_name,
+                BtInstanceOptions::default().with_rng_seed(seed),
+            )
+            .unwrap();
+        assert_eq!(instance.rng_seed(), seed);
+        (0..64)
+            .map(|_| instance.as_mut().tick(&mut (), &()))
+            .collect()
+    }
+
+    #[test]
+    fn weight_select_with_seeded_rng() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "weight",
+                r#"{ "tree_structure": { "WeightSelect": [1, "1|1", [{ "AlwaysSuccess": 2 }, { "AlwaysFailure": 3 }]] } }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "subtree",
+                r#"{ "tree_structure": { "SubTree": [1, "weight", {}] } }"#,
+            )
+            .unwrap();
+
+        let statuses = tick_statuses(&bt_factory, "weight", 42);
+        assert!(statuses.contains(&Status::Success) && statuses.contains(&Status::Failure));
+        assert_eq!(statuses, tick_statuses(&bt_factory, "weight", 42));
+        // subtrees draw from the stream of the instance they belong to.
+        assert_eq!(statuses, tick_statuses(&bt_factory, "subtree", 42));
+    }
+
+    #[test]
+    fn weight_select_with_blackboard_weights() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "weight",
+                r#"{
+                    "tree_blackboard": [
+                        { "bb_name": "attack", "bb_type": "f32", "bb_value": "0" },
+                        { "bb_name": "flee", "bb_type": "i32", "bb_value": "1" }
+                    ],
+                    "tree_structure": { "WeightSelect": [1, "<attack>| <flee> |0", [
+                        { "AlwaysSuccess": 2 }, { "AlwaysFailure": 3 }, { "AlwaysRunning": 4 }
+                    ]] }
+                }"#,
+            )
+            .unwrap();
+        for (weights, error) in [("<missing>|1", true), ("<attack>|1", false), ("x|1", true)] {
+            let tree_json = format!(
+                r#"{{
+                    "tree_blackboard": [{{ "bb_name": "attack", "bb_type": "f32", "bb_value": "0" }}],
+                    "tree_structure": {{ "WeightSelect": [1, "{}", [{{ "AlwaysSuccess": 2 }}, {{ "AlwaysFailure": 3 }}]] }}
+                }}"#,
+                weights
+            );
+            bt_factory
+                .compile_tree_template_from_json_str("invalid", &tree_json)
+                .unwrap();
+            assert_eq!(
+                bt_factory.create_tree_instance("invalid").is_err(),
+                error,
+                "{}",
+                weights
+            );
+        }
+
+        let mut instance = bt_factory.create_tree_instance("weight").unwrap();
+        let set_weight = |instance: &BtInstance<(), (), ()>, attack: f32, flee: i32| {
+            let bb_map = instance.blackboard_map_ref();
+            *bb_map["attack"].borrow_mut().downcast_mut::<f32>().unwrap() = attack;
+            *bb_map["flee"].borrow_mut().downcast_mut::<i32>().unwrap() = flee;
+        };
+        for _ in 0..16 {
+            assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+        }
+        set_weight(&instance, 2.0, -1);
+        for _ in 0..16 {
+            assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        }
+        // nothing left to select from.
+        set_weight(&instance, -2.0, 0);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Stance {
+        Patrol = 0,
+        Flee = 2,
+    }
+
+    impl crate::node::FromI32 for Stance {
+        fn from_i32(value: i32) -> Option<Self> {
+            match value {
+                0 => Some(Stance::Patrol),
+                2 => Some(Stance::Flee),
+                _ => None,
+            }
+        }
+    }
+
+    impl crate::convert::ConvertFromStr for Stance {
+        fn convert_from_str(s: &str) -> Option<Self> {
+            match s {
+                "Patrol" => Some(Stance::Patrol),
+                "Flee" => Some(Stance::Flee),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn switch_on_blackboard_value() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory.register_switch_enum_type::<Stance>();
+        let compile_switch = |bt_factory: &mut BtFactory<(), (), ()>,
+                              bb_type: &str,
+                              bb_value: &str,
+                              cases: [&str; 2]| {
+            let tree_json = format!(
+                r#"{{
+                    "tree_blackboard": [{{ "bb_name": "key", "bb_type": "{}", "bb_value": "{}" }}],
+                    "tree_structure": {{ "Switch": [1, true, "key", [
+                        ["{}", {{ "AlwaysSuccess": 2 }}],
+                        ["{}", {{ "AlwaysRunning": 3 }}]
+                    ], {{ "AlwaysFailure": 4 }}] }}
+                }}"#,
+                bb_type, bb_value, cases[0], cases[1]
+            );
+            bt_factory
+                .compile_tree_template_from_json_str(bb_type, &tree_json)
+                .unwrap();
+            bt_factory.create_tree_instance(bb_type)
+        };
+
+        let mut instance = compile_switch(&mut bt_factory, "i32", "1", ["0", "1"]).unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        let set_key = |instance: &BtInstance<(), (), ()>, key: i32| {
+            *instance.blackboard_map_ref()["key"]
+                .borrow_mut()
+                .downcast_mut::<i32>()
+                .unwrap() = key;
+        };
+        // the running case is left for the new one.
+        set_key(&instance, 0);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        assert_eq!(instance.snapshot().unwrap().nodes[2].status, Status::Idle);
+        set_key(&instance, 7);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+
+        let mut instance =
+            compile_switch(&mut bt_factory, "String", "guard", ["guard", "scout"]).unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        let mut instance = compile_switch(&mut bt_factory, "Stance", "Flee", ["0", "2"]).unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        let mut instance =
+            compile_switch(&mut bt_factory, "Stance", "Flee", ["Flee", "Patrol"]).unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        assert!(compile_switch(&mut bt_factory, "Stance", "Flee", ["Flee", "2"]).is_err());
+        assert!(compile_switch(&mut bt_factory, "Stance", "Flee", ["Flee", "Hide"]).is_err());
+
+        assert!(compile_switch(&mut bt_factory, "f32", "1.0", ["0", "1"]).is_err());
+        assert!(compile_switch(&mut bt_factory, "i64", "1", ["0", "x"]).is_err());
+        assert!(compile_switch(&mut bt_factory, "i64", "1", ["1", "1"]).is_err());
+    }
+
+    fn push_slot(node_index: i32, slot: usize) -> String {
+        format!(
+            r#"{{ "Action": [{}, {{ "name": "WorldNode", "meta_map": {{ "op": "push", "slot": "{}" }} }}] }}"#,
+            node_index, slot
+        )
+    }
+
+    #[test]
+    fn random_and_round_robin_order() {
+        let mut bt_factory = BtFactory::<(), Vec<f64>, ()>::new();
+        bt_factory.register_tree_node::<WorldNode>();
+        let sequence_children = (0..4)
+            .map(|slot| push_slot(slot as i32 + 2, slot))
+            .collect::<Vec<_>>()
+            .join(", ");
+        for (tree_name, tree_structure) in [
+            (
+                "RandomSequence",
+                format!(
+                    r#"{{ "RandomSequence": [1, null, [{}]] }}"#,
+                    sequence_children
+                ),
+            ),
+            (
+                "WeightedSequence",
+                format!(
+                    r#"{{ "RandomSequence": [1, "0|0|0|1", [{}]] }}"#,
+                    sequence_children
+                ),
+            ),
+            (
+                "RandomSelect",
+                format!(
+                    r#"{{ "RandomSelect": [1, "1|1", [{{ "Invert": [2, {}] }}, {}]] }}"#,
+                    push_slot(3, 0),
+                    push_slot(4, 1)
+                ),
+            ),
+            (
+                "RoundRobinSelect",
+                format!(
+                    r#"{{ "RoundRobinSelect": [1, [{}, {{ "Invert": [3, {}] }}, {}]] }}"#,
+                    push_slot(2, 0),
+                    push_slot(4, 1),
+                    push_slot(5, 2)
+                ),
+            ),
+        ] {
+            bt_factory
+                .compile_tree_template_from_json_str(
+                    tree_name,
+                    &format!(r#"{{ "tree_structure": {} }}"#, tree_structure),
+                )
+                .unwrap();
+        }
+        let tick_orders = |tree_name: &str, seed: u64| {
+            let mut instance = bt_factory
+                .create_tree_instance_with_options(
+                    tree_name,
+                    BtInstanceOptions::default().with_rng_seed(seed),
+                )
+                .unwrap();
+            (0..16)
+                .map(|_| {
+                    let mut world = Vec::new();
+                    instance.as_mut().tick(&mut world, &());
+                    world.into_iter().map(|slot| slot as usize).collect()
+                })
+                .collect::<Vec<Vec<_>>>()
+        };
+
+        let orders = tick_orders("RandomSequence", 7);
+        assert!(orders.iter().all(|order| {
+            let mut order = order.clone();
+            order.sort();
+            order == vec![0, 1, 2, 3]
+        }));
+        assert!(orders.iter().any(|order| *order != orders[0]));
+        assert_eq!(orders, tick_orders("RandomSequence", 7));
+        // children without weight keep their order after the weighted ones.
+        assert!(tick_orders("WeightedSequence", 7)
+            .iter()
+            .all(|order| *order == vec![3, 0, 1, 2]));
+
+        let orders = tick_orders("RandomSelect", 7);
+        assert!(orders.contains(&vec![0, 1]) && orders.contains(&vec![1]));
+
+        assert_eq!(
+            tick_orders("RoundRobinSelect", 7)[..4],
+            [vec![0], vec![1, 2], vec![0], vec![1, 2]]
+        );
+    }
+
+    #[test]
+    fn utility_select_with_hysteresis() {
+        let mut bt_factory = BtFactory::<(), Vec<f64>, ()>::new();
+        bt_factory.register_tree_node::<WorldNode>();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "reevaluate",
+                r#"{ "tree_structure": { "UtilitySelect": [1, true, 0.5, [
+                    [{ "Action": { "name": "WorldNode", "meta_map": { "slot": "0" } } }, { "AlwaysRunning": 2 }],
+                    [{ "Action": { "name": "WorldNode", "meta_map": { "slot": "1" } } }, { "AlwaysRunning": 3 }]
+                ]] } }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "fallback",
+                r#"{ "tree_structure": { "UtilitySelect": [1, false, 0.0, [
+                    [{ "Constant": 1.0 }, { "AlwaysSuccess": 2 }],
+                    [{ "Constant": 2.0 }, { "AlwaysFailure": 3 }]
+                ]] } }"#,
+            )
+            .unwrap();
+        let mut instance = bt_factory.create_tree_instance("reevaluate").unwrap();
+        assert_eq!(
+            instance.as_mut().tick(&mut vec![1.0, 0.0], &()),
+            Status::Running
+        );
+        assert_eq!(node_status(&instance, 1), Status::Running);
+        // within the hysteresis margin the running child keeps going.
+        instance.as_mut().tick(&mut vec![1.0, 1.2], &());
+        assert_eq!(node_status(&instance, 1), Status::Running);
+        instance.as_mut().tick(&mut vec![1.0, 2.0], &());
+        assert_eq!(node_status(&instance, 1), Status::Idle);
+        assert_eq!(node_status(&instance, 2), Status::Running);
+        #[cfg(feature = "tree_visualization")]
+        assert_eq!(
+            instance.visualization_ref().get_node_scores(1),
+            Some([1.0, 2.0].as_slice())
+        );
+
+        let mut instance = bt_factory.create_tree_instance("fallback").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut vec![], &()), Status::Success);
+    }
+
+    #[test]
+    fn reactive_nodes_recheck_earlier_children() {
+        let mut bt_factory = BtFactory::<(), Vec<f64>, ()>::new();
+        bt_factory.register_tree_node::<WorldNode>();
+        for tree_name in ["ReactiveSequence", "ReactiveSelect"] {
+            bt_factory
+                .compile_tree_template_from_json_str(
+                    tree_name,
+                    &format!(
+                        r#"{{ "tree_structure": {{ "{}": [1, [
+                            {{ "Action": [2, {{ "name": "WorldNode", "meta_map": {{ "slot": "0" }} }}] }},
+                            {{ "AlwaysRunning": 3 }}
+                        ]] }} }}"#,
+                        tree_name
+                    ),
+                )
+                .unwrap();
+        }
+        let mut instance = bt_factory.create_tree_instance("ReactiveSequence").unwrap();
+        for _ in 0..2 {
+            assert_eq!(instance.as_mut().tick(&mut vec![1.0], &()), Status::Running);
+            assert_eq!(node_status(&instance, 2), Status::Running);
+        }
+        // the condition is checked again and aborts the running child.
+        assert_eq!(instance.as_mut().tick(&mut vec![0.0], &()), Status::Failure);
+        assert_eq!(node_status(&instance, 2), Status::Idle);
+
+        let mut instance = bt_factory.create_tree_instance("ReactiveSelect").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut vec![0.0], &()), Status::Running);
+        assert_eq!(node_status(&instance, 2), Status::Running);
+        assert_eq!(instance.as_mut().tick(&mut vec![1.0], &()), Status::Success);
+        assert_eq!(node_status(&instance, 2), Status::Idle);
+    }
+
+    #[cfg(feature = "expression_node")]
+    #[test]
+    fn utility_select_with_expression_scorer() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "expression",
+                r#"{
+                    "tree_blackboard": [{ "bb_name": "hp", "bb_type": "f32", "bb_value": "20.0" }],
+                    "tree_structure": { "UtilitySelect": [1, false, 0.0, [
+                        [{ "Expression": "hp / 100.0" }, { "AlwaysFailure": 2 }],
+                        [{ "Expression": "1.0 - hp / 100.0" }, { "AlwaysSuccess": 3 }]
+                    ]] }
+                }"#,
+            )
+            .unwrap();
+        let mut instance = bt_factory.create_tree_instance("expression").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        assert_eq!(instance.snapshot().unwrap().nodes[1].status, Status::Idle);
+    }
+
+    #[test]
+    fn multiple_branch_follows_selected_set() {
+        let mut bt_factory = BtFactory::<(), Vec<f64>, ()>::new();
+        bt_factory.register_tree_node::<WorldNode>();
+        for policy in ["And", "Or"] {
+            bt_factory
+                .compile_tree_template_from_json_str(
+                    policy,
+                    &format!(
+                        r#"{{ "tree_structure": {{ "MultipleBranch": [1, true, "{}",
+                            {{ "Action": [2, {{ "name": "WorldNode", "meta_map": {{ "op": "branches" }} }}] }},
+                            [{{ "AlwaysRunning": 3 }}, {{ "AlwaysSuccess": 4 }}, {{ "AlwaysRunning": 5 }}]
+                        ] }} }}"#,
+                        policy
+                    ),
+                )
+                .unwrap();
+        }
+        let mut instance = bt_factory.create_tree_instance("And").unwrap();
+        assert_eq!(
+            instance.as_mut().tick(&mut vec![0.0, 1.0], &()),
+            Status::Running
+        );
+        assert_eq!(node_status(&instance, 2), Status::Running);
+        // branch 0 drops out and is reset, branch 1 already succeeded and is not run again.
+        assert_eq!(
+            instance.as_mut().tick(&mut vec![1.0, 2.0], &()),
+            Status::Running
+        );
+        assert_eq!(node_status(&instance, 2), Status::Idle);
+        assert_eq!(node_status(&instance, 4), Status::Running);
+        assert_eq!(instance.as_mut().tick(&mut vec![1.0], &()), Status::Success);
+        assert_eq!(node_status(&instance, 4), Status::Idle);
+        assert_eq!(instance.as_mut().tick(&mut vec![3.0], &()), Status::Failure);
+
+        let mut instance = bt_factory.create_tree_instance("Or").unwrap();
+        assert_eq!(
+            instance.as_mut().tick(&mut vec![0.0, 1.0], &()),
+            Status::Success
+        );
+        assert_eq!(node_status(&instance, 2), Status::Idle);
+        assert_eq!(instance.as_mut().tick(&mut vec![], &()), Status::Failure);
+    }
+
+    #[test]
+    fn parallel_thresholds_and_policy() {
+        let clock = Arc::new(ManualClock::new(0));
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory.set_clock(clock.clone());
+        for (tree_name, tree_structure) in [
+            (
+                "two_of_three",
+                r#"{ "Parallel": [1, 2, 1, "HaltRunning", [{ "AlwaysSuccess": 2 }, { "AlwaysRunning": 3 }, { "AlwaysSuccess": 4 }]] }"#,
+            ),
+            (
+                "unreachable",
+                r#"{ "Parallel": [1, 2, -1, "HaltRunning", [{ "AlwaysFailure": 2 }, { "AlwaysRunning": 3 }, { "AlwaysFailure": 4 }]] }"#,
+            ),
+            (
+                "halt",
+                r#"{ "Parallel": [1, 1, -1, "HaltRunning", [{ "Wait": [2, 100] }, { "Wait": [3, 50] }]] }"#,
+            ),
+            (
+                "wait",
+                r#"{ "Parallel": [1, 1, -1, "WaitRunning", [{ "Wait": [2, 100] }, { "Wait": [3, 50] }]] }"#,
+            ),
+        ] {
+            bt_factory
+                .compile_tree_template_from_json_str(
+                    tree_name,
+                    &format!(r#"{{ "tree_structure": {} }}"#, tree_structure),
+                )
+                .unwrap();
+        }
+        let mut instance = bt_factory.create_tree_instance("two_of_three").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        let mut instance = bt_factory.create_tree_instance("unreachable").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+
+        let mut halt = bt_factory.create_tree_instance("halt").unwrap();
+        let mut wait = bt_factory.create_tree_instance("wait").unwrap();
+        assert_eq!(halt.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(wait.as_mut().tick(&mut (), &()), Status::Running);
+        clock.advance(60);
+        assert_eq!(halt.as_mut().tick(&mut (), &()), Status::Success);
+        assert_eq!(halt.snapshot().unwrap().nodes[1].status, Status::Idle);
+        assert_eq!(wait.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(wait.snapshot().unwrap().nodes[1].status, Status::Running);
+        clock.advance(60);
+        assert_eq!(wait.as_mut().tick(&mut (), &()), Status::Success);
     }
 }
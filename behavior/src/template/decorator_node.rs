@@ -1,9 +1,13 @@
 use super::status::Status::*;
 use super::{
-    BlackBoard, BlackBoardMap, SharedBlackBoardValue, Status, TreeNode, TreeNodeBase, TreeNodeType,
+    BlackBoard, BlackBoardMap, BlackBoardType, BlackBoardValueState, NodeState,
+    SharedBlackBoardValue, SharedSemaphore, Status, TreeNode, TreeNodeBase, TreeNodeType,
 };
-use super::{NodeType, TreeNodeStatus};
+use super::{MaybeSend, NodeType, SelectWeight, TreeNodeStatus};
+use crate::{BehaviorError, NodeLocation};
 use behavior_macros::TreeNodeStatus;
+use behavior_util::random_pass_with_rng;
+use serde::{Deserialize, Serialize};
 
 #[derive(TreeNodeStatus)]
 pub struct InvertNode<A, C, F: ?Sized, W, E> {
@@ -35,7 +39,7 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
             Running => Running,
             Failure => Success,
             Success => Failure,
@@ -51,13 +55,13 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.child.reset(blackboard, world, entity);
         }
     }
 
@@ -84,6 +88,22 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -116,7 +136,7 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
             Running => Running,
             Success | Failure => Success,
             _ => panic_if_idle_or_branch!(
@@ -131,13 +151,13 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.child.reset(blackboard, world, entity);
         }
     }
 
@@ -164,6 +184,22 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -196,7 +232,7 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
             Running => Running,
             Success | Failure => Failure,
             _ => panic_if_idle_or_branch!(
@@ -211,13 +247,13 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.child.reset(blackboard, world, entity);
         }
     }
 
@@ -244,6 +280,22 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -276,7 +328,7 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
             Success => Success,
             Running | Failure => Running,
             _ => panic_if_idle_or_branch!(
@@ -291,13 +343,13 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.child.reset(blackboard, world, entity);
         }
     }
 
@@ -324,6 +376,22 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -356,7 +424,7 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
             Failure => Failure,
             Running | Success => Running,
             _ => panic_if_idle_or_branch!(
@@ -371,13 +439,13 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.child.reset(blackboard, world, entity);
         }
     }
 
@@ -404,6 +472,22 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -451,14 +535,14 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = blackboard.now_millis();
         if self.start_time.is_some() && now >= self.start_time.unwrap() + self.timeout {
             self.start_time = None;
             self.child.reset(blackboard, world, entity);
             return set_status!(self, blackboard, Failure);
         }
 
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
             status @ (Success | Failure) => {
                 self.start_time = None;
                 status
@@ -481,17 +565,26 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
+            reset_status!(self, blackboard);
             self.start_time = None;
-            self.child.reset(ctx, world, entity);
+            self.child.reset(blackboard, world, entity);
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.start_time)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.start_time = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -515,47 +608,52 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
+// Same as `TimeoutNode`, the child fails after running for `timeout` ticks of this node.
 #[derive(TreeNodeStatus)]
-pub struct LimiterNode<A, C, F: ?Sized, W, E> {
+pub struct TimeoutTicksNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    duration: i64,
-    start_time: Option<i64>,
-    max_executions: i32,
-    execution_count: i32,
+    timeout: i64,
+    elapsed_ticks: i64,
     child: TreeNodeType<A, C, F, W, E>,
 }
-impl<A, C, F: ?Sized, W, E> LimiterNode<A, C, F, W, E> {
-    pub fn new(
-        index: i32,
-        duration: i64,
-        max_executions: i32,
-        child: TreeNodeType<A, C, F, W, E>,
-    ) -> Self {
+impl<A, C, F: ?Sized, W, E> TimeoutTicksNode<A, C, F, W, E> {
+    pub fn new(index: i32, timeout: i64, child: TreeNodeType<A, C, F, W, E>) -> Self {
         assert!(
-            duration > 0 && max_executions > 0,
-            "LimiterNode argument duration and max_executions must be greater than 0, index={}, duration={}, max_executions={}",
+            timeout > 0,
+            "TimeoutTicksNode argument timeout must be greater than 0, index={}, timeout={}",
             index,
-            duration,
-            max_executions
+            timeout
         );
         let base = TreeNodeBase::default();
-        let start_time = None;
-        let execution_count = 0;
         Self {
             base,
             index,
-            duration,
-            start_time,
-            max_executions,
-            execution_count,
+            timeout,
+            elapsed_ticks: 0,
             child,
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for LimiterNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for TimeoutTicksNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -573,21 +671,24 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let now = chrono::Utc::now().timestamp_millis();
-        if self.start_time.is_some() && now >= (self.start_time.unwrap() + self.duration) {
-            self.start_time = None;
-            self.execution_count = 0;
-        }
-        if self.execution_count >= self.max_executions {
+        if self.is_running() && self.elapsed_ticks >= self.timeout {
+            self.elapsed_ticks = 0;
+            self.child.reset(blackboard, world, entity);
             return set_status!(self, blackboard, Failure);
         }
 
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
-            Running => Running,
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
             status @ (Success | Failure) => {
-                self.execution_count += 1;
+                self.elapsed_ticks = 0;
                 status
             }
+            Running => {
+                if !self.is_running() {
+                    self.elapsed_ticks = 0;
+                }
+                self.elapsed_ticks += 1;
+                Running
+            }
             _ => panic_if_idle_or_branch!(
                 self.node_name(),
                 self.index,
@@ -595,26 +696,31 @@ where
                 self.child.node_index()
             ),
         };
-        if self.start_time.is_none() {
-            self.start_time = Some(now);
-        }
         set_status!(self, blackboard, status)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.start_time = None;
-            self.execution_count = 0;
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.elapsed_ticks = 0;
+            self.child.reset(blackboard, world, entity);
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.elapsed_ticks)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.elapsed_ticks = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -638,36 +744,91 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
+}
+
+// What the `duration` of a `LimiterNode` is measured in, ticks are the ones the limiter receives.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LimiterWindow {
+    #[default]
+    Millis,
+    Ticks,
 }
 
 #[derive(TreeNodeStatus)]
-pub struct RepeatNode<A, C, F: ?Sized, W, E> {
+pub struct LimiterNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    max_repeats: i32,
-    repeat_count: i32,
+    window: LimiterWindow,
+    duration: i64,
+    start_time: Option<i64>,
+    tick_count: i64,
+    max_executions: i32,
+    execution_count: i32,
     child: TreeNodeType<A, C, F, W, E>,
 }
-impl<A, C, F: ?Sized, W, E> RepeatNode<A, C, F, W, E> {
-    pub fn new(index: i32, max_repeats: i32, child: TreeNodeType<A, C, F, W, E>) -> Self {
+impl<A, C, F: ?Sized, W, E> LimiterNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        duration: i64,
+        max_executions: i32,
+        child: TreeNodeType<A, C, F, W, E>,
+    ) -> Self {
+        Self::with_window(
+            index,
+            LimiterWindow::Millis,
+            duration,
+            max_executions,
+            child,
+        )
+    }
+
+    pub fn with_window(
+        index: i32,
+        window: LimiterWindow,
+        duration: i64,
+        max_executions: i32,
+        child: TreeNodeType<A, C, F, W, E>,
+    ) -> Self {
         assert!(
-            max_repeats == -1 || max_repeats > 0,
-            "RepeatNode argument max_repeats must be -1 or greater than 0, index={}, max_repeats={}",
+            duration > 0 && max_executions > 0,
+            "LimiterNode argument duration and max_executions must be greater than 0, index={}, duration={}, max_executions={}",
             index,
-            max_repeats
+            duration,
+            max_executions
         );
         let base = TreeNodeBase::default();
-        let repeat_count = 0;
+        let start_time = None;
+        let execution_count = 0;
         Self {
             base,
             index,
-            repeat_count,
-            max_repeats,
+            window,
+            duration,
+            start_time,
+            tick_count: 0,
+            max_executions,
+            execution_count,
             child,
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for RepeatNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for LimiterNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -685,18 +846,26 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
+        let now = match self.window {
+            LimiterWindow::Millis => blackboard.now_millis(),
+            LimiterWindow::Ticks => {
+                self.tick_count += 1;
+                self.tick_count
+            }
+        };
+        if self.start_time.is_some() && now >= (self.start_time.unwrap() + self.duration) {
+            self.start_time = None;
+            self.execution_count = 0;
+        }
+        if self.execution_count >= self.max_executions {
+            return set_status!(self, blackboard, Failure);
+        }
+
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
             Running => Running,
             status @ (Success | Failure) => {
-                if self.max_repeats != -1 {
-                    self.repeat_count += 1;
-                }
-                if self.repeat_count < self.max_repeats || self.max_repeats == -1 {
-                    Running
-                } else {
-                    self.repeat_count = 0;
-                    status
-                }
+                self.execution_count += 1;
+                status
             }
             _ => panic_if_idle_or_branch!(
                 self.node_name(),
@@ -705,22 +874,38 @@ where
                 self.child.node_index()
             ),
         };
+        if self.start_time.is_none() {
+            self.start_time = Some(now);
+        }
         set_status!(self, blackboard, status)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.repeat_count = 0;
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.start_time = None;
+            self.execution_count = 0;
+            self.child.reset(blackboard, world, entity);
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&(self.start_time, self.tick_count, self.execution_count))
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        let (start_time, tick_count, execution_count) = state.decode()?;
+        self.start_time = start_time;
+        self.tick_count = tick_count;
+        self.execution_count = execution_count;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -744,36 +929,1004 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
-#[derive(TreeNodeStatus)]
-pub struct ImmediateRepeatNode<A, C, F: ?Sized, W, E> {
-    base: TreeNodeBase,
+// Which completions of the child start the cooldown of a `CooldownNode`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CooldownTrigger {
+    #[default]
+    Success,
+    Failure,
+    Completion,
+}
+impl CooldownTrigger {
+    #[inline]
+    fn is_triggered(&self, status: Status) -> bool {
+        matches!(
+            (self, status),
+            (CooldownTrigger::Success, Success)
+                | (CooldownTrigger::Failure, Failure)
+                | (CooldownTrigger::Completion, Success | Failure)
+        )
+    }
+}
+
+// An i64 blackboard value holding the time the cooldown ends, shared by all cooldown nodes
+// naming it, also across linked subtrees.
+pub struct CooldownGroup {
+    ready_time: SharedBlackBoardValue,
+}
+impl CooldownGroup {
+    pub fn new(
+        tree_name: &str,
+        tree_index: i32,
+        tree_depth: i32,
+        index: i32,
+        group_name: &str,
+        bb_map: &BlackBoardMap,
+    ) -> Result<Self, BehaviorError> {
+        let location =
+            || NodeLocation::new(tree_name, tree_index, tree_depth, "CooldownNode", index);
+        let ready_time =
+            bb_map
+                .get(group_name)
+                .ok_or_else(|| BehaviorError::BlackBoardNotFound {
+                    location: location(),
+                    blackboard_ref_name: "cooldown_group".to_string(),
+                    blackboard_name: group_name.to_string(),
+                })?;
+        if ready_time.borrow().downcast_ref::<i64>().is_none() {
+            return Err(BehaviorError::BlackBoardDowncastFailed {
+                location: location(),
+                blackboard_ref_name: "cooldown_group".to_string(),
+                blackboard_name: group_name.to_string(),
+                blackboard_type: "i64".to_string(),
+            });
+        }
+        Ok(Self {
+            ready_time: ready_time.share(),
+        })
+    }
+
+    #[inline]
+    fn ready_time(&self) -> i64 {
+        self.ready_time
+            .borrow()
+            .downcast_ref::<i64>()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    fn set_ready_time(&self, ready_time: i64) {
+        if let Some(value) = self.ready_time.borrow_mut().downcast_mut::<i64>() {
+            *value = ready_time;
+        }
+    }
+}
+
+// Fails without ticking the child for `duration` ms after the child completed as `trigger`
+// says. Without a group the cooldown belongs to the node.
+#[derive(TreeNodeStatus)]
+pub struct CooldownNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    duration: i64,
+    trigger: CooldownTrigger,
+    ready_time: Option<i64>,
+    group: Option<CooldownGroup>,
+    child: TreeNodeType<A, C, F, W, E>,
+}
+impl<A, C, F: ?Sized, W, E> CooldownNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        duration: i64,
+        trigger: CooldownTrigger,
+        group: Option<CooldownGroup>,
+        child: TreeNodeType<A, C, F, W, E>,
+    ) -> Self {
+        assert!(
+            duration > 0,
+            "CooldownNode argument duration must be greater than 0, index={}, duration={}",
+            index,
+            duration
+        );
+        let base = TreeNodeBase::default();
+        let ready_time = None;
+        Self {
+            base,
+            index,
+            duration,
+            trigger,
+            ready_time,
+            group,
+            child,
+        }
+    }
+
+    #[inline]
+    fn ready_time(&self) -> Option<i64> {
+        match &self.group {
+            Some(group) => Some(group.ready_time()),
+            None => self.ready_time,
+        }
+    }
+
+    #[inline]
+    fn set_ready_time(&mut self, ready_time: i64) {
+        match &self.group {
+            Some(group) => group.set_ready_time(ready_time),
+            None => self.ready_time = Some(ready_time),
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for CooldownNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let now = blackboard.now_millis();
+        // a running child finishes even if a group member started the cooldown meanwhile.
+        if !self.is_running() && self.ready_time().is_some_and(|ready_time| now < ready_time) {
+            return set_status!(self, blackboard, Failure);
+        }
+
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
+            status @ (Success | Failure | Running) => status,
+            _ => panic_if_idle_or_branch!(
+                self.node_name(),
+                self.index,
+                self.child.node_name(),
+                self.child.node_index()
+            ),
+        };
+        if self.trigger.is_triggered(status) {
+            self.set_ready_time(now + self.duration);
+        }
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        // the cooldown outlives a reset, a halted child didn't complete.
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.child.reset(blackboard, world, entity);
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.ready_time)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.ready_time = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::DecoratorNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&self.child]
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
+}
+
+// Ticks the child with the given probability when activated, fails otherwise. The draw is kept
+// while the child is running, unreadable probabilities never pass.
+#[derive(TreeNodeStatus)]
+pub struct ChanceNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    probability: SelectWeight<C, W, E>,
+    child: TreeNodeType<A, C, F, W, E>,
+}
+impl<A, C, F: ?Sized, W, E> ChanceNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        probability: SelectWeight<C, W, E>,
+        child: TreeNodeType<A, C, F, W, E>,
+    ) -> Self {
+        if let SelectWeight::Constant(probability) = probability {
+            assert!(
+                (0.0..=1.0).contains(&probability),
+                "ChanceNode argument probability must be in [0, 1], index={}, probability={}",
+                index,
+                probability
+            );
+        }
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            probability,
+            child,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for ChanceNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        if !self.is_running() {
+            let probability = self
+                .probability
+                .weight(blackboard, world, entity)
+                .unwrap_or(0.0);
+            if !random_pass_with_rng(blackboard.rng_mut(), probability) {
+                return set_status!(self, blackboard, Failure);
+            }
+        }
+
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
+            status @ (Success | Failure | Running) => status,
+            _ => panic_if_idle_or_branch!(
+                self.node_name(),
+                self.index,
+                self.child.node_name(),
+                self.child.node_index()
+            ),
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.child.reset(blackboard, world, entity);
+        }
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::DecoratorNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&self.child]
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
+}
+
+#[derive(TreeNodeStatus)]
+pub struct RepeatNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    max_repeats: i32,
+    repeat_count: i32,
+    child: TreeNodeType<A, C, F, W, E>,
+}
+impl<A, C, F: ?Sized, W, E> RepeatNode<A, C, F, W, E> {
+    pub fn new(index: i32, max_repeats: i32, child: TreeNodeType<A, C, F, W, E>) -> Self {
+        assert!(
+            max_repeats == -1 || max_repeats > 0,
+            "RepeatNode argument max_repeats must be -1 or greater than 0, index={}, max_repeats={}",
+            index,
+            max_repeats
+        );
+        let base = TreeNodeBase::default();
+        let repeat_count = 0;
+        Self {
+            base,
+            index,
+            repeat_count,
+            max_repeats,
+            child,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for RepeatNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
+            Running => Running,
+            status @ (Success | Failure) => {
+                if self.max_repeats != -1 {
+                    self.repeat_count += 1;
+                }
+                if self.repeat_count < self.max_repeats || self.max_repeats == -1 {
+                    Running
+                } else {
+                    self.repeat_count = 0;
+                    status
+                }
+            }
+            _ => panic_if_idle_or_branch!(
+                self.node_name(),
+                self.index,
+                self.child.node_name(),
+                self.child.node_index()
+            ),
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.repeat_count = 0;
+            self.child.reset(blackboard, world, entity);
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.repeat_count)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.repeat_count = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::DecoratorNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&self.child]
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
+}
+
+#[derive(TreeNodeStatus)]
+pub struct ImmediateRepeatNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    max_repeats: i32,
+    repeat_count: i32,
+    child: TreeNodeType<A, C, F, W, E>,
+}
+impl<A, C, F: ?Sized, W, E> ImmediateRepeatNode<A, C, F, W, E> {
+    pub fn new(index: i32, max_repeats: i32, child: TreeNodeType<A, C, F, W, E>) -> Self {
+        assert!(
+            max_repeats == -1 || max_repeats > 0,
+            "ImmediateRepeatNode argument max_repeats must be -1 or greater than 0, index={}, max_repeats={}",
+            index,
+            max_repeats
+        );
+        let base = TreeNodeBase::default();
+        let repeat_count = 0;
+        Self {
+            base,
+            index,
+            repeat_count,
+            max_repeats,
+            child,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for ImmediateRepeatNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let status = loop {
+            match self.child.execute_tick(blackboard, func, world, entity) {
+                Running => break Running,
+                status @ (Success | Failure) => {
+                    if self.max_repeats != -1 {
+                        self.repeat_count += 1;
+                    }
+                    if !(self.repeat_count < self.max_repeats || self.max_repeats == -1) {
+                        self.repeat_count = 0;
+                        break status;
+                    }
+                }
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.child.node_name(),
+                    self.child.node_index()
+                ),
+            };
+        };
+
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.repeat_count = 0;
+            self.child.reset(blackboard, world, entity);
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.repeat_count)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.repeat_count = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::DecoratorNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&self.child]
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
+}
+
+#[derive(TreeNodeStatus)]
+pub struct RetryNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    try_count: i32,
+    max_attempts: i32,
+    child: TreeNodeType<A, C, F, W, E>,
+}
+impl<A, C, F: ?Sized, W, E> RetryNode<A, C, F, W, E> {
+    pub fn new(index: i32, max_attempts: i32, child: TreeNodeType<A, C, F, W, E>) -> Self {
+        assert!(
+            max_attempts == -1 || max_attempts > 0,
+            "RetryNode argument max_attempts must be -1 or greater than 0, index={}, max_attempts={}",
+            index,
+            max_attempts
+        );
+        let base = TreeNodeBase::default();
+        let try_count = 0;
+        Self {
+            base,
+            index,
+            try_count,
+            max_attempts,
+            child,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for RetryNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
+            Running => Running,
+            Failure => {
+                if self.max_attempts != -1 {
+                    self.try_count += 1;
+                }
+                if self.try_count < self.max_attempts || self.max_attempts == -1 {
+                    Running
+                } else {
+                    self.try_count = 0;
+                    Failure
+                }
+            }
+            Success => {
+                self.try_count = 0;
+                Success
+            }
+            _ => panic_if_idle_or_branch!(
+                self.node_name(),
+                self.index,
+                self.child.node_name(),
+                self.child.node_index()
+            ),
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.try_count = 0;
+            self.child.reset(blackboard, world, entity);
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.try_count)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.try_count = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::DecoratorNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&self.child]
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
+}
+
+#[derive(TreeNodeStatus)]
+pub struct ImmediateRetryNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    try_count: i32,
+    max_attempts: i32,
+    child: TreeNodeType<A, C, F, W, E>,
+}
+impl<A, C, F: ?Sized, W, E> ImmediateRetryNode<A, C, F, W, E> {
+    pub fn new(index: i32, max_attempts: i32, child: TreeNodeType<A, C, F, W, E>) -> Self {
+        assert!(
+            max_attempts == -1 || max_attempts > 0,
+            "ImmediateRetryNode argument max_attempts must be -1 or greater than 0, index={}, max_attempts={}",
+            index,
+            max_attempts
+        );
+        let base = TreeNodeBase::default();
+        let try_count = 0;
+        Self {
+            base,
+            index,
+            try_count,
+            max_attempts,
+            child,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for ImmediateRetryNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        let status = loop {
+            match self.child.execute_tick(blackboard, func, world, entity) {
+                Running => break Running,
+                Failure => {
+                    if self.max_attempts != -1 {
+                        self.try_count += 1;
+                    }
+                    if !(self.try_count < self.max_attempts || self.max_attempts == -1) {
+                        self.try_count = 0;
+                        break Failure;
+                    }
+                }
+                Success => {
+                    self.try_count = 0;
+                    break Success;
+                }
+                _ => panic_if_idle_or_branch!(
+                    self.node_name(),
+                    self.index,
+                    self.child.node_name(),
+                    self.child.node_index()
+                ),
+            };
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.try_count = 0;
+            self.child.reset(blackboard, world, entity);
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.try_count)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.try_count = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::DecoratorNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&self.child]
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
+}
+
+// Writes the element at `index` of a container blackboard value into the item blackboard value,
+// false once the index is past the end. Without an item it only checks the index.
+pub type ForEachItemFunc = fn(&dyn BlackBoardType, usize, Option<&mut dyn BlackBoardType>) -> bool;
+
+// When a `ForEachNode` stops before the last element, with the status of the child that stopped it.
+// Otherwise it completes with Success, or with Failure for `BreakOnSuccess` as a select does.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ForEachPolicy {
+    #[default]
+    BreakOnFailure,
+    BreakOnSuccess,
+    Never,
+}
+impl ForEachPolicy {
+    #[inline]
+    fn is_break(&self, status: Status) -> bool {
+        matches!(
+            (self, status),
+            (ForEachPolicy::BreakOnFailure, Failure) | (ForEachPolicy::BreakOnSuccess, Success)
+        )
+    }
+
+    #[inline]
+    fn completed_status(&self) -> Status {
+        match self {
+            ForEachPolicy::BreakOnSuccess => Failure,
+            _ => Success,
+        }
+    }
+}
+
+// Ticks the child once per element of the container, which is read again for every element so
+// that it may change in between. All elements are visited in one tick unless `one_per_tick`.
+#[derive(TreeNodeStatus)]
+pub struct ForEachNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
     index: i32,
-    max_repeats: i32,
-    repeat_count: i32,
+    container: SharedBlackBoardValue,
+    item: SharedBlackBoardValue,
+    read_item: ForEachItemFunc,
+    policy: ForEachPolicy,
+    one_per_tick: bool,
+    cursor: usize,
     child: TreeNodeType<A, C, F, W, E>,
 }
-impl<A, C, F: ?Sized, W, E> ImmediateRepeatNode<A, C, F, W, E> {
-    pub fn new(index: i32, max_repeats: i32, child: TreeNodeType<A, C, F, W, E>) -> Self {
+impl<A, C, F: ?Sized, W, E> ForEachNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        container: SharedBlackBoardValue,
+        item: SharedBlackBoardValue,
+        read_item: ForEachItemFunc,
+        policy: ForEachPolicy,
+        one_per_tick: bool,
+        child: TreeNodeType<A, C, F, W, E>,
+    ) -> Self {
         assert!(
-            max_repeats == -1 || max_repeats > 0,
-            "ImmediateRepeatNode argument max_repeats must be -1 or greater than 0, index={}, max_repeats={}",
+            container.bb_name() != item.bb_name(),
+            "ForEachNode container and item must be different blackboard values, index={}, bb_name={}",
             index,
-            max_repeats
+            item.bb_name()
         );
         let base = TreeNodeBase::default();
-        let repeat_count = 0;
+        let cursor = 0;
         Self {
             base,
             index,
-            repeat_count,
-            max_repeats,
+            container,
+            item,
+            read_item,
+            policy,
+            one_per_tick,
+            cursor,
             child,
         }
     }
+
+    #[inline]
+    fn read_next_item(&self) -> bool {
+        (self.read_item)(
+            self.container.borrow().as_ref(),
+            self.cursor,
+            Some(self.item.borrow_mut().as_mut()),
+        )
+    }
+
+    #[inline]
+    fn has_next_item(&self) -> bool {
+        (self.read_item)(self.container.borrow().as_ref(), self.cursor, None)
+    }
 }
-impl<A, C, F, W, E> TreeNode for ImmediateRepeatNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for ForEachNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -791,16 +1944,26 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = loop {
-            match self.child.control_tick(blackboard, func, world, entity) {
-                Running => break Running,
+        if !self.is_running() {
+            self.cursor = 0;
+        }
+        loop {
+            // a running child keeps the item it started with.
+            if !self.child.is_running() && !self.read_next_item() {
+                self.cursor = 0;
+                let status = self.policy.completed_status();
+                return set_status!(self, blackboard, status);
+            }
+            match self.child.execute_tick(blackboard, func, world, entity) {
+                Running => return set_status!(self, blackboard, Running),
                 status @ (Success | Failure) => {
-                    if self.max_repeats != -1 {
-                        self.repeat_count += 1;
+                    if self.policy.is_break(status) {
+                        self.cursor = 0;
+                        return set_status!(self, blackboard, status);
                     }
-                    if !(self.repeat_count < self.max_repeats || self.max_repeats == -1) {
-                        self.repeat_count = 0;
-                        break status;
+                    self.cursor += 1;
+                    if self.one_per_tick && self.has_next_item() {
+                        return set_status!(self, blackboard, Running);
                     }
                 }
                 _ => panic_if_idle_or_branch!(
@@ -809,25 +1972,32 @@ where
                     self.child.node_name(),
                     self.child.node_index()
                 ),
-            };
-        };
-
-        set_status!(self, blackboard, status)
+            }
+        }
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.repeat_count = 0;
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.cursor = 0;
+            self.child.reset(blackboard, world, entity);
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.cursor)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.cursor = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -851,36 +2021,52 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
+// Ticks the child until the event is delivered, the tick it is delivered in the running child is
+// reset and the node fails. The payload is written as by `WaitForEventNode`.
 #[derive(TreeNodeStatus)]
-pub struct RetryNode<A, C, F: ?Sized, W, E> {
+pub struct OnEventNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    try_count: i32,
-    max_attempts: i32,
+    event_name: String,
+    payload: Option<SharedBlackBoardValue>,
     child: TreeNodeType<A, C, F, W, E>,
 }
-impl<A, C, F: ?Sized, W, E> RetryNode<A, C, F, W, E> {
-    pub fn new(index: i32, max_attempts: i32, child: TreeNodeType<A, C, F, W, E>) -> Self {
-        assert!(
-            max_attempts == -1 || max_attempts > 0,
-            "RetryNode argument max_attempts must be -1 or greater than 0, index={}, max_attempts={}",
-            index,
-            max_attempts
-        );
+impl<A, C, F: ?Sized, W, E> OnEventNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        event_name: String,
+        payload: Option<SharedBlackBoardValue>,
+        child: TreeNodeType<A, C, F, W, E>,
+    ) -> Self {
         let base = TreeNodeBase::default();
-        let try_count = 0;
         Self {
             base,
             index,
-            try_count,
-            max_attempts,
+            event_name,
+            payload,
             child,
         }
     }
 }
-impl<A, C, F, W, E> TreeNode for RetryNode<A, C, F, W, E>
+impl<A, C, F, W, E> TreeNode for OnEventNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -898,23 +2084,16 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
-            Running => Running,
-            Failure => {
-                if self.max_attempts != -1 {
-                    self.try_count += 1;
-                }
-                if self.try_count < self.max_attempts || self.max_attempts == -1 {
-                    Running
-                } else {
-                    self.try_count = 0;
-                    Failure
-                }
-            }
-            Success => {
-                self.try_count = 0;
-                Success
+        if let Some(event) = blackboard.events().find_delivered(&self.event_name) {
+            if let Some(payload) = self.payload.as_ref() {
+                event.write_payload(payload);
             }
+            self.child.reset(blackboard, world, entity);
+            return set_status!(self, blackboard, Failure);
+        }
+
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
+            status @ (Success | Failure | Running) => status,
             _ => panic_if_idle_or_branch!(
                 self.node_name(),
                 self.index,
@@ -927,14 +2106,13 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.try_count = 0;
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.child.reset(blackboard, world, entity);
         }
     }
 
@@ -961,36 +2139,69 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
+// Ticks the child while holding a slot of the semaphore, the slot is released once the child
+// completes, the node is reset or dropped. Without a free slot the node fails, or keeps running
+// and tries again on the next tick if `wait`.
 #[derive(TreeNodeStatus)]
-pub struct ImmediateRetryNode<A, C, F: ?Sized, W, E> {
+pub struct SemaphoreNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    try_count: i32,
-    max_attempts: i32,
+    semaphore: SharedSemaphore,
+    wait: bool,
+    acquired: bool,
     child: TreeNodeType<A, C, F, W, E>,
 }
-impl<A, C, F: ?Sized, W, E> ImmediateRetryNode<A, C, F, W, E> {
-    pub fn new(index: i32, max_attempts: i32, child: TreeNodeType<A, C, F, W, E>) -> Self {
-        assert!(
-            max_attempts == -1 || max_attempts > 0,
-            "ImmediateRetryNode argument max_attempts must be -1 or greater than 0, index={}, max_attempts={}",
-            index,
-            max_attempts
-        );
+impl<A, C, F: ?Sized, W, E> SemaphoreNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        semaphore: SharedSemaphore,
+        wait: bool,
+        child: TreeNodeType<A, C, F, W, E>,
+    ) -> Self {
         let base = TreeNodeBase::default();
-        let try_count = 0;
+        let acquired = false;
         Self {
             base,
             index,
-            try_count,
-            max_attempts,
+            semaphore,
+            wait,
+            acquired,
             child,
         }
     }
+
+    #[inline]
+    fn release(&mut self) {
+        if self.acquired {
+            self.acquired = false;
+            self.semaphore.release();
+        }
+    }
 }
-impl<A, C, F, W, E> TreeNode for ImmediateRetryNode<A, C, F, W, E>
+impl<A, C, F: ?Sized, W, E> Drop for SemaphoreNode<A, C, F, W, E> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+impl<A, C, F, W, E> TreeNode for SemaphoreNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
@@ -1008,46 +2219,58 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = loop {
-            match self.child.control_tick(blackboard, func, world, entity) {
-                Running => break Running,
-                Failure => {
-                    if self.max_attempts != -1 {
-                        self.try_count += 1;
-                    }
-                    if !(self.try_count < self.max_attempts || self.max_attempts == -1) {
-                        self.try_count = 0;
-                        break Failure;
-                    }
-                }
-                Success => {
-                    self.try_count = 0;
-                    break Success;
-                }
-                _ => panic_if_idle_or_branch!(
-                    self.node_name(),
-                    self.index,
-                    self.child.node_name(),
-                    self.child.node_index()
-                ),
-            };
+        if !self.acquired {
+            if !self.semaphore.try_acquire() {
+                let status = if self.wait { Running } else { Failure };
+                return set_status!(self, blackboard, status);
+            }
+            self.acquired = true;
+        }
+
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
+            Running => Running,
+            status @ (Success | Failure) => {
+                self.release();
+                status
+            }
+            _ => panic_if_idle_or_branch!(
+                self.node_name(),
+                self.index,
+                self.child.node_name(),
+                self.child.node_index()
+            ),
         };
         set_status!(self, blackboard, status)
     }
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.try_count = 0;
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.release();
+            self.child.reset(blackboard, world, entity);
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.acquired)
+    }
+
+    // The restored node holds a slot again if it held one, free or not.
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        let acquired: bool = state.decode()?;
+        self.release();
+        if acquired {
+            self.semaphore.force_acquire();
+            self.acquired = true;
+        }
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -1071,6 +2294,22 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -1091,8 +2330,8 @@ impl<A, C, F: ?Sized, W, E> LogNode<A, C, F, W, E> {
         let blackboards: Vec<_> = blackboards_str
             .split('|')
             .map(
-                |blackbaord_name| match blackboard_map.get(blackbaord_name).cloned() {
-                    Some(value) => value,
+                |blackbaord_name| match blackboard_map.get(blackbaord_name) {
+                    Some(value) => value.share(),
                     None => panic!(
                         "LogNode blackboard not found, blackboard_name={}, blackboards_str={}",
                         blackbaord_name, blackboards_str
@@ -1126,7 +2365,7 @@ where
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
-        let status = match self.child.control_tick(blackboard, func, world, entity) {
+        let status = match self.child.execute_tick(blackboard, func, world, entity) {
             status @ (Success | Failure | Running) => status,
             _ => panic_if_idle_or_branch!(
                 self.node_name(),
@@ -1143,13 +2382,13 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.child.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.child.reset(blackboard, world, entity);
         }
     }
 
@@ -1176,6 +2415,22 @@ where
     > {
         vec![&self.child]
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
 }
 
 #[derive(TreeNodeStatus)]
@@ -1207,6 +2462,7 @@ impl<A, C, F: ?Sized, W, E> SubTreeNode<A, C, F, W, E> {
 impl<A, C, F, W, E> TreeNode for SubTreeNode<A, C, F, W, E>
 where
     A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    C: MaybeSend,
     F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
 {
     type Action = A;
@@ -1217,17 +2473,21 @@ where
 
     fn control_tick(
         &mut self,
-        #[allow(unused_variables)] blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         func: &mut Self::ActionTickFunc,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) -> Status {
         #[cfg(feature = "tree_visualization")]
         self.blackboard.reset_visualization();
-        let status = match self
+        blackboard.notify_subtree_enter(self.index, &self.tree_name);
+        self.blackboard.swap_shared_state(blackboard);
+        let status = self
             .child
-            .control_tick(&mut self.blackboard, func, world, entity)
-        {
+            .execute_tick(&mut self.blackboard, func, world, entity);
+        self.blackboard.swap_shared_state(blackboard);
+        blackboard.notify_subtree_exit(self.index, &self.tree_name, status);
+        let status = match status {
             status @ (Success | Failure | Running) => status,
             _ => panic_if_idle_or_branch!(
                 self.node_name(),
@@ -1241,17 +2501,28 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
+            reset_status!(self, blackboard);
             // self.blackboard.reset_runtime();
-            self.child.reset(ctx, world, entity);
+            self.blackboard.swap_shared_state(blackboard);
+            self.child.reset(&mut self.blackboard, world, entity);
+            self.blackboard.swap_shared_state(blackboard);
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.blackboard.save_values()?)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        let values: Vec<BlackBoardValueState> = state.decode()?;
+        self.blackboard.load_values(&values)
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -1276,6 +2547,22 @@ where
         vec![&self.child]
     }
 
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        vec![&mut self.child]
+    }
+
     cfg_tree_visualization! {
         fn visualize(
             &self,
@@ -1313,3 +2600,269 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::factory::BtFactory;
+    use crate::{ManualClock, Status};
+    use std::sync::Arc;
+
+    #[test]
+    fn cooldown_with_shared_group() {
+        let clock = Arc::new(ManualClock::new(0));
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory.set_clock(clock.clone());
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "local",
+                r#"{ "tree_structure": { "Cooldown": [1, 100, "Success", null, { "AlwaysSuccess": 2 }] } }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "group",
+                r#"{
+                    "tree_blackboard": [{ "bb_name": "grenade", "bb_type": "i64", "bb_value": "0" }],
+                    "tree_structure": { "Sequence": [1, [
+                        { "Cooldown": [2, 100, "Completion", "grenade", { "AlwaysSuccess": 3 }] },
+                        { "Cooldown": [4, 50, "Success", "grenade", { "AlwaysSuccess": 5 }] }
+                    ]] }
+                }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "missing",
+                r#"{ "tree_structure": { "Cooldown": [1, 100, "Failure", "grenade", { "AlwaysSuccess": 2 }] } }"#,
+            )
+            .unwrap();
+        assert!(bt_factory.create_tree_instance("missing").is_err());
+
+        let mut instance = bt_factory.create_tree_instance("local").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        clock.advance(50);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+        clock.advance(50);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+
+        // the first throw puts the second one on cooldown.
+        let mut instance = bt_factory.create_tree_instance("group").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+        let ready_time = |instance: &crate::factory::BtInstance<(), (), ()>| {
+            *instance.blackboard_map_ref()["grenade"]
+                .borrow()
+                .downcast_ref::<i64>()
+                .unwrap()
+        };
+        assert_eq!(ready_time(&instance), 200);
+        clock.advance(150);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+        assert_eq!(ready_time(&instance), 350);
+        // still cooling down, the first child isn't ticked either.
+        clock.advance(50);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+        assert_eq!(ready_time(&instance), 350);
+    }
+
+    #[test]
+    fn tick_based_wait_timeout_and_limiter() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "wait",
+                r#"{
+                    "tree_blackboard": [{ "bb_name": "alert", "bb_type": "bool", "bb_value": "true" }],
+                    "tree_structure": { "ReactiveSequence": [1, [
+                        { "CompareBlackBoard": [2, "alert", "Eq", "true"] },
+                        { "WaitTicks": [3, 2] }
+                    ]] }
+                }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "timeout",
+                r#"{ "tree_structure": { "Sequence": [1, [
+                    { "TimeoutTicks": [2, 3, { "WaitTicks": [3, 2] }] },
+                    { "TimeoutTicks": [4, 2, { "AlwaysRunning": 5 }] }
+                ]] } }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "limiter",
+                r#"{ "tree_structure": { "LimiterTicks": [1, 3, 1, { "AlwaysSuccess": 2 }] } }"#,
+            )
+            .unwrap();
+
+        // the wait starts over after being reset by the reactive sequence.
+        let mut instance = bt_factory.create_tree_instance("wait").unwrap();
+        let tick = |instance: &mut std::pin::Pin<Box<crate::factory::BtInstance<(), (), ()>>>,
+                    alert: bool| {
+            *instance.blackboard_map_ref()["alert"]
+                .borrow_mut()
+                .downcast_mut::<bool>()
+                .unwrap() = alert;
+            instance.as_mut().tick(&mut (), &())
+        };
+        assert_eq!(tick(&mut instance, true), Status::Running);
+        assert_eq!(tick(&mut instance, true), Status::Running);
+        assert_eq!(tick(&mut instance, false), Status::Failure);
+        assert_eq!(tick(&mut instance, true), Status::Running);
+        assert_eq!(tick(&mut instance, true), Status::Running);
+        assert_eq!(tick(&mut instance, true), Status::Success);
+
+        let mut instance = bt_factory.create_tree_instance("timeout").unwrap();
+        let statuses: Vec<_> = (0..7)
+            .map(|_| instance.as_mut().tick(&mut (), &()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                Status::Running,
+                Status::Running,
+                Status::Running,
+                Status::Running,
+                Status::Failure,
+                Status::Running,
+                Status::Running,
+            ]
+        );
+
+        let mut instance = bt_factory.create_tree_instance("limiter").unwrap();
+        let statuses: Vec<_> = (0..5)
+            .map(|_| instance.as_mut().tick(&mut (), &()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                Status::Success,
+                Status::Failure,
+                Status::Failure,
+                Status::Success,
+                Status::Failure,
+            ]
+        );
+    }
+
+    #[test]
+    fn foreach_over_blackboard_container() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory.register_foreach_item_type::<i32>();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "test",
+                r#"{
+                    "tree_blackboard": [
+                        { "bb_name": "targets", "bb_type": "Vec<i32>", "bb_value": "3|5|8" },
+                        { "bb_name": "queue", "bb_type": "VecDeque<i32>", "bb_value": "3|5|8" },
+                        { "bb_name": "target", "bb_type": "i32", "bb_value": "0" },
+                        { "bb_name": "sum", "bb_type": "i32", "bb_value": "0" }
+                    ],
+                    "tree_structure": { "Sequence": [1, [
+                        { "Invert": [2, { "ForEach": [3, "targets", "target", "BreakOnFailure", false,
+                            { "CompareBlackBoard": [4, "target", "Lt", "6"] }] }] },
+                        { "CompareBlackBoard": [5, "target", "Eq", "8"] },
+                        { "ForEach": [6, "queue", "target", "BreakOnSuccess", false,
+                            { "CompareBlackBoard": [7, "target", "Gt", "4"] }] },
+                        { "CompareBlackBoard": [8, "target", "Eq", "5"] },
+                        { "ForEach": [9, "targets", "target", "Never", true,
+                            { "SetBlackBoard": [10, "sum", "<target>"] }] }
+                    ]] }
+                }"#,
+            )
+            .unwrap();
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        let value = |instance: &crate::factory::BtInstance<(), (), ()>, name: &str| {
+            *instance.blackboard_map_ref()[name]
+                .borrow()
+                .downcast_ref::<i32>()
+                .unwrap()
+        };
+        // one element per tick for the last one.
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(value(&instance, "sum"), 3);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(value(&instance, "sum"), 5);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        assert_eq!(value(&instance, "sum"), 8);
+
+        // the container is read again for each element.
+        instance.blackboard_map_ref()["targets"]
+            .borrow_mut()
+            .downcast_mut::<Vec<i32>>()
+            .unwrap()
+            .clear();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+
+        for (container, item) in [
+            ("missing", "target"),
+            ("rate", "target"),
+            ("targets", "rate"),
+        ] {
+            bt_factory
+                .compile_tree_template_from_json_str(
+                    "invalid",
+                    &format!(
+                        r#"{{
+                            "tree_blackboard": [
+                                {{ "bb_name": "targets", "bb_type": "Vec<i32>", "bb_value": "None" }},
+                                {{ "bb_name": "target", "bb_type": "i32", "bb_value": "0" }},
+                                {{ "bb_name": "rate", "bb_type": "f32", "bb_value": "0" }}
+                            ],
+                            "tree_structure": {{ "ForEach": [1, "{}", "{}", "Never", false,
+                                {{ "AlwaysSuccess": 2 }}] }}
+                        }}"#,
+                        container, item
+                    ),
+                )
+                .unwrap();
+            assert!(bt_factory.create_tree_instance("invalid").is_err());
+        }
+    }
+
+    #[test]
+    fn chance_keeps_its_draw_while_running() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "test",
+                r#"{
+                    "tree_blackboard": [{ "bb_name": "chance", "bb_type": "f32", "bb_value": "0.5" }],
+                    "tree_structure": { "Chance": [1, "<chance>", { "WaitTicks": [2, 1] }] }
+                }"#,
+            )
+            .unwrap();
+        let mut instance = bt_factory
+            .create_tree_instance_with_options(
+                "test",
+                crate::factory::BtInstanceOptions::default().with_rng_seed(7),
+            )
+            .unwrap();
+        let statuses: Vec<_> = (0..200)
+            .map(|_| instance.as_mut().tick(&mut (), &()))
+            .collect();
+        let passes = statuses
+            .iter()
+            .filter(|status| **status == Status::Running)
+            .count();
+        assert!((40..=80).contains(&passes), "passes={}", passes);
+        // a running child always completes, it is never cut off by another draw.
+        assert!(statuses
+            .windows(2)
+            .all(|pair| pair[0] != Status::Running || pair[1] == Status::Success));
+
+        for (chance, status) in [(0.0f32, Status::Failure), (1.0, Status::Running)] {
+            *instance.blackboard_map_ref()["chance"]
+                .borrow_mut()
+                .downcast_mut::<f32>()
+                .unwrap() = chance;
+            instance.as_mut().halt(&mut (), &());
+            assert!((0..20).all(|_| {
+                let result = instance.as_mut().tick(&mut (), &());
+                instance.as_mut().halt(&mut (), &());
+                result == status
+            }));
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use super::{BlackBoardType, SharedBlackBoardValue};
+
+#[derive(Debug)]
+pub struct Event {
+    pub name: String,
+    pub payload: Option<Box<dyn BlackBoardType>>,
+}
+
+impl Event {
+    // False if the payload is of another type, an event without payload leaves the value as is.
+    #[inline]
+    pub fn write_payload(&self, target: &SharedBlackBoardValue) -> bool {
+        self.payload
+            .as_ref()
+            .is_none_or(|payload| target.assign(payload.as_ref()))
+    }
+}
+
+// Events of an instance, shared with its subtrees. Events pushed between two ticks are delivered
+// during the next tick only: every node ticked in it sees all of them in push order, whatever
+// the order the nodes run in. Events emitted while ticking are delivered on the tick after.
+// The queue is not part of snapshots.
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    pending: Vec<Event>,
+    delivered: Vec<Event>,
+}
+
+impl EventQueue {
+    #[inline]
+    pub fn push(&mut self, name: &str, payload: Option<Box<dyn BlackBoardType>>) {
+        self.pending.push(Event {
+            name: name.to_string(),
+            payload,
+        });
+    }
+
+    #[inline]
+    pub fn pending(&self) -> &[Event] {
+        &self.pending
+    }
+
+    #[inline]
+    pub fn delivered(&self) -> &[Event] {
+        &self.delivered
+    }
+
+    // The first event of that name delivered in the current tick.
+    #[inline]
+    pub fn find_delivered(&self, name: &str) -> Option<&Event> {
+        self.delivered.iter().find(|event| event.name == name)
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.delivered.clear();
+    }
+
+    // Drops the events of the previous tick and delivers the pending ones.
+    #[inline]
+    pub(crate) fn deliver(&mut self) {
+        self.delivered.clear();
+        std::mem::swap(&mut self.pending, &mut self.delivered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::factory::BtFactory;
+    use crate::Status;
+
+    const TREE_JSON: &str = r#"{
+        "tree_blackboard": [
+            { "bb_name": "damage", "bb_type": "i32", "bb_value": "0" },
+            { "bb_name": "alarm", "bb_type": "i32", "bb_value": "0" }
+        ],
+        "tree_structure": { "Select": [1, [
+            { "OnEvent": [2, "damage", "damage", { "WaitForever": 3 }] },
+            { "Sequence": [4, [
+                { "EmitEvent": [5, "alarm", "damage"] },
+                { "SubTree": [6, "listener", { "alarm": "alarm" }] }
+            ]] }
+        ]] }
+    }"#;
+    const LISTENER_JSON: &str = r#"{
+        "tree_blackboard": [{ "bb_name": "alarm", "bb_type": "i32", "bb_value": "0" }],
+        "tree_structure": { "WaitForEvent": [1, "alarm", "alarm"] }
+    }"#;
+
+    #[test]
+    fn events_are_delivered_on_the_next_tick() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory
+            .compile_tree_template_from_json_str("test", TREE_JSON)
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str("listener", LISTENER_JSON)
+            .unwrap();
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        let value = |instance: &crate::factory::BtInstance<(), (), ()>, name: &str| {
+            *instance.blackboard_map_ref()[name]
+                .borrow()
+                .downcast_ref::<i32>()
+                .unwrap()
+        };
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        // an event nobody waits for is dropped after the tick.
+        instance.as_mut().push_event("sound");
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(instance.events().delivered()[0].name, "sound");
+
+        // the damage interrupts the first branch, the alarm is emitted for the next tick.
+        instance.as_mut().push_event_with_payload("damage", 25);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(value(&instance, "damage"), 25);
+        assert_eq!(value(&instance, "alarm"), 0);
+        assert_eq!(instance.events().pending()[0].name, "alarm");
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        assert_eq!(value(&instance, "alarm"), 25);
+        assert!(instance.events().pending().is_empty());
+
+        // a payload of another type fails the waiting node.
+        let mut instance = bt_factory.create_tree_instance("listener").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        instance.as_mut().push_event_with_payload("alarm", 1.5f32);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "missing",
+                r#"{ "tree_structure": { "WaitForEvent": [1, "alarm", "alarm"] } }"#,
+            )
+            .unwrap();
+        assert!(bt_factory.create_tree_instance("missing").is_err());
+    }
+}
@@ -1,7 +1,12 @@
 use super::Status::{self, Failure, Running, Success};
-use super::{BlackBoard, TreeNode, TreeNodeBase};
+use super::{
+    BlackBoard, BlackBoardOps, BlackBoardType, NodeState, SharedBlackBoardValue, TreeNode,
+    TreeNodeBase,
+};
 use super::{NodeType, TreeNodeStatus};
+use crate::BehaviorError;
 use behavior_macros::TreeNodeStatus;
+use serde::{Deserialize, Serialize};
 use std::marker::PhantomData;
 
 #[derive(TreeNodeStatus)]
@@ -9,7 +14,7 @@ pub struct ActionNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
     action: A,
-    _marker: PhantomData<(C, W, E, F)>,
+    _marker: PhantomData<fn() -> (C, W, E, Box<F>)>,
 }
 impl<A, C, F: ?Sized, W, E> ActionNode<A, C, F, W, E> {
     pub fn new(index: i32, action: A) -> Self {
@@ -46,16 +51,24 @@ where
 
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
-            self.action.reset(ctx, world, entity);
+            reset_status!(self, blackboard);
+            self.action.reset(blackboard, world, entity);
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        self.action.save_state(state)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.action.load_state(state)
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -84,6 +97,22 @@ where
         Vec::default()
     }
 
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+
     cfg_tree_visualization! {
         fn visualize(
             &self,
@@ -104,7 +133,7 @@ pub struct WaitNode<A, C, F: ?Sized, W, E> {
     index: i32,
     duration: i64,
     start_time: Option<i64>,
-    _marker: PhantomData<(A, C, W, E, F)>,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
 }
 impl<A, C, F: ?Sized, W, E> WaitNode<A, C, F, W, E> {
     pub fn new(index: i32, duration: i64) -> Self {
@@ -132,12 +161,12 @@ where
 
     fn control_tick(
         &mut self,
-        #[allow(unused_variables)] blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         _func: &mut Self::ActionTickFunc,
         _world: &mut Self::World,
         _entity: &Self::Entity,
     ) -> Status {
-        let now = chrono::Utc::now().timestamp_millis();
+        let now = blackboard.now_millis();
         if let Some(start_time) = self.start_time {
             if now >= start_time + self.duration {
                 self.start_time = None;
@@ -152,16 +181,25 @@ where
 
     fn reset(
         &mut self,
-        _ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         _world: &mut Self::World,
         _entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
+            reset_status!(self, blackboard);
             self.start_time = None;
         }
     }
 
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.start_time)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.start_time = state.decode()?;
+        Ok(())
+    }
+
     fn node_index(&self) -> i32 {
         self.index
     }
@@ -185,13 +223,148 @@ where
     > {
         Vec::default()
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+}
+
+// Counts the ticks it receives instead of the time, Running for `ticks` ticks and Success on
+// the next one.
+#[derive(TreeNodeStatus)]
+pub struct WaitTicksNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    ticks: i64,
+    elapsed_ticks: i64,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
+}
+impl<A, C, F: ?Sized, W, E> WaitTicksNode<A, C, F, W, E> {
+    pub fn new(index: i32, ticks: i64) -> Self {
+        assert!(
+            ticks >= 0,
+            "WaitTicksNode argument ticks must not be negative, index={}, ticks={}",
+            index,
+            ticks
+        );
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            ticks,
+            elapsed_ticks: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for WaitTicksNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _func: &mut Self::ActionTickFunc,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) -> Status {
+        if !self.is_running() {
+            self.elapsed_ticks = 0;
+        }
+        if self.elapsed_ticks >= self.ticks {
+            self.elapsed_ticks = 0;
+            return set_status!(self, blackboard, Success);
+        }
+        self.elapsed_ticks += 1;
+        set_status!(self, blackboard, Running)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+            self.elapsed_ticks = 0;
+        }
+    }
+
+    fn save_state(&self, state: &mut NodeState) -> Result<(), BehaviorError> {
+        state.encode(&self.elapsed_ticks)
+    }
+
+    fn load_state(&mut self, state: &NodeState) -> Result<(), BehaviorError> {
+        self.elapsed_ticks = state.decode()?;
+        Ok(())
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::LeafNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
 }
 
 #[derive(TreeNodeStatus)]
 pub struct WaitForeverNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    _marker: PhantomData<(A, C, W, E, F)>,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
 }
 impl<A, C, F: ?Sized, W, E> WaitForeverNode<A, C, F, W, E> {
     pub fn new(index: i32) -> Self {
@@ -226,12 +399,12 @@ where
 
     fn reset(
         &mut self,
-        _ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         _world: &mut Self::World,
         _entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
+            reset_status!(self, blackboard);
         }
     }
 
@@ -258,13 +431,29 @@ where
     > {
         Vec::default()
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
 }
 
 #[derive(TreeNodeStatus)]
 pub struct AlwaysSuccessNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    _marker: PhantomData<(A, C, W, E, F)>,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
 }
 impl<A, C, F: ?Sized, W, E> AlwaysSuccessNode<A, C, F, W, E> {
     pub fn new(index: i32) -> Self {
@@ -299,7 +488,7 @@ where
 
     fn reset(
         &mut self,
-        _ctx: &mut Self::BlackBoardContext,
+        _blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         _world: &mut Self::World,
         _entity: &Self::Entity,
     ) {
@@ -328,13 +517,29 @@ where
     > {
         Vec::default()
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
 }
 
 #[derive(TreeNodeStatus)]
 pub struct AlwaysRunningNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    _marker: PhantomData<(A, C, W, E, F)>,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
 }
 impl<A, C, F: ?Sized, W, E> AlwaysRunningNode<A, C, F, W, E> {
     pub fn new(index: i32) -> Self {
@@ -369,12 +574,12 @@ where
 
     fn reset(
         &mut self,
-        _ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         _world: &mut Self::World,
         _entity: &Self::Entity,
     ) {
         if self.is_running() {
-            self.reset_status();
+            reset_status!(self, blackboard);
         }
     }
 
@@ -401,13 +606,29 @@ where
     > {
         Vec::default()
     }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
 }
 
 #[derive(TreeNodeStatus)]
 pub struct AlwaysFailureNode<A, C, F: ?Sized, W, E> {
     base: TreeNodeBase,
     index: i32,
-    _marker: PhantomData<(A, C, W, E, F)>,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
 }
 impl<A, C, F: ?Sized, W, E> AlwaysFailureNode<A, C, F, W, E> {
     pub fn new(index: i32) -> Self {
@@ -442,7 +663,7 @@ where
 
     fn reset(
         &mut self,
-        _ctx: &mut Self::BlackBoardContext,
+        _blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         _world: &mut Self::World,
         _entity: &Self::Entity,
     ) {
@@ -471,110 +692,1131 @@ where
     > {
         Vec::default()
     }
-}
 
-cfg_expression_node! {
-    use super::blackboard::{BlackBoardMap, SharedBlackBoardValue};
-    use ahash::{HashMapExt, RandomState};
-    use std::collections::HashMap;
-    use crate::{TreeLocation, BehaviorError};
-    use evalexpr::*;
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+}
 
-    // TODO: 指针优化访问变量优化
-    type VariableMap = HashMap<String, SharedBlackBoardValue, RandomState>;
-    struct ExpressionWrapper {
-        expr: Node,
-        raw_expr: String,
-        variable_map: VariableMap,
-        // context: HashMapContext::<DefaultNumericTypes>,
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+impl CompareOp {
+    // Eq and Ne only need an equatable type.
+    #[inline]
+    pub fn is_ordering(&self) -> bool {
+        !matches!(self, CompareOp::Eq | CompareOp::Ne)
     }
+}
 
-    impl ExpressionWrapper {
-        pub fn new(
-            tree_name: &str,
-            tree_index: i32,
-            tree_depth: i32,
-            node_index: i32,
-            expr: &str,
-            bb_map: &BlackBoardMap,
-        ) -> Result<Self, BehaviorError> {
-            let raw_expr = expr.to_string();
-            let expr = match build_operator_tree::<DefaultNumericTypes>(expr) {
-                Ok(expr) => expr,
-                Err(err) => {
-                    return Err(BehaviorError::ExpressionInvalidOperatorTree {
-                        tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
-                        node_index,
-                        expression: raw_expr,
-                        error_info: err.to_string(),
-                    });
-                }
-            };
-            let mut variable_map = VariableMap::new();
-            // let mut context = HashMapContext::<DefaultNumericTypes>::new();
-            for var in expr.iter_variable_identifiers() {
-                if !variable_map.contains_key(var) {
-                    match bb_map.get(var) {
-                        Some(value) => {
-                            if value.is_expr_var() {
-                                variable_map.insert(var.to_string(), value.clone());
-                                // let value = value.get_as_f64().unwrap();
-                                // context.set_value(var.to_string(), Value::from_float(value)).unwrap();
-                            } else {
-                                return Err(BehaviorError::ExpressionInvalidVariable {
-                                    tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
-                                    node_index,
-                                    expression: raw_expr,
-                                    blackboard_name: value.bb_name().to_string(),
-                                    blackboard_type: value.bb_type().to_string(),
-                                });
-                            }
-                        }
-                        None => {
-                            return Err(BehaviorError::ExpressionVariableNotExist {
-                                tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
-                                node_index,
-                                expression: raw_expr,
-                                blackboard_name: var.to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-            Ok(Self { expr, raw_expr, variable_map})
-        }
+// The value a builtin blackboard node compares with or assigns, another blackboard value
+// written as `<name>` or a literal parsed for the type of the target.
+pub enum BlackBoardOperand {
+    BlackBoard(SharedBlackBoardValue),
+    Literal(Box<dyn BlackBoardType>),
+}
 
-        pub fn eval(&mut self) -> Status {
-            let mut context = HashMapContext::<DefaultNumericTypes>::new();
-            for (var, value) in self.variable_map.iter() {
-                let value = value.get_as_f64().unwrap();
-                context.set_value(var.clone(), Value::from_float(value)).unwrap();
+// Succeeds if `value op operand` holds.
+#[derive(TreeNodeStatus)]
+pub struct CompareBlackBoardNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    op: CompareOp,
+    ops: BlackBoardOps,
+    value: SharedBlackBoardValue,
+    operand: BlackBoardOperand,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
+}
+impl<A, C, F: ?Sized, W, E> CompareBlackBoardNode<A, C, F, W, E> {
+    pub fn new(
+        index: i32,
+        op: CompareOp,
+        ops: BlackBoardOps,
+        value: SharedBlackBoardValue,
+        operand: BlackBoardOperand,
+    ) -> Self {
+        assert!(
+            if op.is_ordering() { ops.has_partial_cmp() } else { ops.has_eq() },
+            "CompareBlackBoardNode' blackboard type doesn't support the comparison, index={}, op={:?}, bb_type={}",
+            index,
+            op,
+            value.bb_type()
+        );
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            op,
+            ops,
+            value,
+            operand,
+            _marker: PhantomData,
+        }
+    }
+
+    fn compare(&self) -> bool {
+        let value = self.value.borrow();
+        let operand_value;
+        let operand = match &self.operand {
+            // the value is locked while it's borrowed, so comparing with itself reuses the borrow.
+            BlackBoardOperand::BlackBoard(operand) if operand.is_same_value(&self.value) => {
+                value.as_ref()
+            }
+            BlackBoardOperand::BlackBoard(operand) => {
+                operand_value = operand.borrow();
+                operand_value.as_ref()
+            }
+            BlackBoardOperand::Literal(operand) => operand.as_ref(),
+        };
+        match self.op {
+            CompareOp::Eq => self.ops.values_eq(value.as_ref(), operand),
+            CompareOp::Ne => !self.ops.values_eq(value.as_ref(), operand),
+            op => self
+                .ops
+                .values_cmp(value.as_ref(), operand)
+                .is_some_and(|ordering| match op {
+                    CompareOp::Lt => ordering.is_lt(),
+                    CompareOp::Le => ordering.is_le(),
+                    CompareOp::Gt => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                }),
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for CompareBlackBoardNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        #[allow(unused)] blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _func: &mut Self::ActionTickFunc,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) -> Status {
+        let status = if self.compare() { Success } else { Failure };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        _blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) {
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::LeafNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+}
+
+// Assigns the operand to a blackboard value.
+#[derive(TreeNodeStatus)]
+pub struct SetBlackBoardNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    value: SharedBlackBoardValue,
+    operand: BlackBoardOperand,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
+}
+impl<A, C, F: ?Sized, W, E> SetBlackBoardNode<A, C, F, W, E> {
+    pub fn new(index: i32, value: SharedBlackBoardValue, operand: BlackBoardOperand) -> Self {
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            value,
+            operand,
+            _marker: PhantomData,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for SetBlackBoardNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        #[allow(unused)] blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _func: &mut Self::ActionTickFunc,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) -> Status {
+        let assigned = match &self.operand {
+            BlackBoardOperand::BlackBoard(operand) => self.value.assign_from(operand),
+            BlackBoardOperand::Literal(operand) => self.value.assign(operand.as_ref()),
+        };
+        let status = if assigned { Success } else { Failure };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        _blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) {
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::LeafNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+}
+
+// Resets a blackboard value, an `Option<T>` to None and other types to their default value.
+#[derive(TreeNodeStatus)]
+pub struct ClearBlackBoardNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    ops: BlackBoardOps,
+    value: SharedBlackBoardValue,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
+}
+impl<A, C, F: ?Sized, W, E> ClearBlackBoardNode<A, C, F, W, E> {
+    pub fn new(index: i32, ops: BlackBoardOps, value: SharedBlackBoardValue) -> Self {
+        assert!(
+            ops.has_clear(),
+            "ClearBlackBoardNode' blackboard type can't be cleared, index={}, bb_type={}",
+            index,
+            value.bb_type()
+        );
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            ops,
+            value,
+            _marker: PhantomData,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for ClearBlackBoardNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        #[allow(unused)] blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _func: &mut Self::ActionTickFunc,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) -> Status {
+        let cleared = self.ops.clear(self.value.borrow_mut().as_mut());
+        let status = if cleared { Success } else { Failure };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        _blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) {
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::LeafNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+}
+
+// Succeeds if an `Option<T>` blackboard value is Some.
+#[derive(TreeNodeStatus)]
+pub struct IsBlackBoardSetNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    ops: BlackBoardOps,
+    value: SharedBlackBoardValue,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
+}
+impl<A, C, F: ?Sized, W, E> IsBlackBoardSetNode<A, C, F, W, E> {
+    pub fn new(index: i32, ops: BlackBoardOps, value: SharedBlackBoardValue) -> Self {
+        assert!(
+            ops.has_is_set(),
+            "IsBlackBoardSetNode' blackboard type is not an Option, index={}, bb_type={}",
+            index,
+            value.bb_type()
+        );
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            ops,
+            value,
+            _marker: PhantomData,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for IsBlackBoardSetNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        #[allow(unused)] blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _func: &mut Self::ActionTickFunc,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) -> Status {
+        let is_set = self.ops.is_set(self.value.borrow().as_ref());
+        let status = if is_set { Success } else { Failure };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        _blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) {
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::LeafNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+}
+
+// Pushes an event to the instance's queue, with a copy of the payload blackboard value if set.
+// The event is delivered on the next tick.
+#[derive(TreeNodeStatus)]
+pub struct EmitEventNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    event_name: String,
+    payload: Option<SharedBlackBoardValue>,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
+}
+impl<A, C, F: ?Sized, W, E> EmitEventNode<A, C, F, W, E> {
+    pub fn new(index: i32, event_name: String, payload: Option<SharedBlackBoardValue>) -> Self {
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            event_name,
+            payload,
+            _marker: PhantomData,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for EmitEventNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _func: &mut Self::ActionTickFunc,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) -> Status {
+        let payload = self
+            .payload
+            .as_ref()
+            .map(|payload| payload.borrow().as_ref().clone_box());
+        blackboard.events_mut().push(&self.event_name, payload);
+        set_status!(self, blackboard, Success)
+    }
+
+    fn reset(
+        &mut self,
+        _blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) {
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::LeafNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+}
+
+// Running until the event is delivered in a tick the node is ticked in, then writes its payload
+// to the payload blackboard value if set. Fails if the payload has another type.
+#[derive(TreeNodeStatus)]
+pub struct WaitForEventNode<A, C, F: ?Sized, W, E> {
+    base: TreeNodeBase,
+    index: i32,
+    event_name: String,
+    payload: Option<SharedBlackBoardValue>,
+    _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
+}
+impl<A, C, F: ?Sized, W, E> WaitForEventNode<A, C, F, W, E> {
+    pub fn new(index: i32, event_name: String, payload: Option<SharedBlackBoardValue>) -> Self {
+        let base = TreeNodeBase::default();
+        Self {
+            base,
+            index,
+            event_name,
+            payload,
+            _marker: PhantomData,
+        }
+    }
+}
+impl<A, C, F, W, E> TreeNode for WaitForEventNode<A, C, F, W, E>
+where
+    A: TreeNode<BlackBoardContext = C, World = W, Entity = E>,
+    F: ?Sized + FnMut(&mut A, &mut BlackBoard<C>, &mut W, &E) -> Status,
+{
+    type Action = A;
+    type BlackBoardContext = C;
+    type ActionTickFunc = F;
+    type World = W;
+    type Entity = E;
+
+    fn control_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _func: &mut Self::ActionTickFunc,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) -> Status {
+        let status = match blackboard.events().find_delivered(&self.event_name) {
+            Some(event)
+                if self
+                    .payload
+                    .as_ref()
+                    .is_none_or(|payload| event.write_payload(payload)) =>
+            {
+                Success
+            }
+            Some(_) => Failure,
+            None => Running,
+        };
+        set_status!(self, blackboard, status)
+    }
+
+    fn reset(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) {
+        if self.is_running() {
+            reset_status!(self, blackboard);
+        }
+    }
+
+    fn node_index(&self) -> i32 {
+        self.index
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::LeafNode
+    }
+
+    fn children(
+        &self,
+    ) -> Vec<
+        &Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    > {
+        Vec::default()
+    }
+}
+
+cfg_expression_node! {
+    use super::blackboard::BlackBoardMap;
+    use ahash::{HashMapExt, RandomState};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use crate::TreeLocation;
+    use evalexpr::*;
+
+    // Type of an argument of an expression function. Literal and blackboard arguments are
+    // checked when the tree is compiled, the other ones before each call.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ExpressionArgType {
+        Any,
+        // Int or Float
+        Number,
+        Boolean,
+        String,
+        Tuple,
+    }
+    impl ExpressionArgType {
+        // None for the empty value.
+        fn of(value: &Value) -> Option<Self> {
+            match value {
+                Value::Int(_) | Value::Float(_) => Some(Self::Number),
+                Value::Boolean(_) => Some(Self::Boolean),
+                Value::String(_) => Some(Self::String),
+                Value::Tuple(_) => Some(Self::Tuple),
+                Value::Empty => None,
+            }
+        }
+
+        #[inline]
+        pub fn accepts(self, value: &Value) -> bool {
+            self == Self::Any || Self::of(value) == Some(self)
+        }
+
+        fn expected_error(self, actual: &Value) -> EvalexprError {
+            let actual = actual.clone();
+            match self {
+                Self::Any => unreachable!(),
+                Self::Number => EvalexprError::expected_number(actual),
+                Self::Boolean => EvalexprError::expected_boolean(actual),
+                Self::String => EvalexprError::expected_string(actual),
+                Self::Tuple => EvalexprError::expected_tuple(actual),
+            }
+        }
+    }
+
+    // Host function callable from `Expression` nodes, registered by
+    // `BtFactory::register_expression_function`. The arguments come in call order and match the
+    // registered types.
+    pub type ExpressionFunc<C, W, E> =
+        fn(&mut BlackBoard<C>, &mut W, &E, &[Value]) -> EvalexprResult<Value>;
+
+    pub struct ExpressionFunction<C, W, E> {
+        arg_types: Vec<ExpressionArgType>,
+        func: ExpressionFunc<C, W, E>,
+    }
+    impl<C, W, E> Clone for ExpressionFunction<C, W, E> {
+        fn clone(&self) -> Self {
+            Self {
+                arg_types: self.arg_types.clone(),
+                func: self.func,
+            }
+        }
+    }
+    impl<C, W, E> ExpressionFunction<C, W, E> {
+        pub fn new(arg_types: &[ExpressionArgType], func: ExpressionFunc<C, W, E>) -> Self {
+            Self {
+                arg_types: arg_types.to_vec(),
+                func,
+            }
+        }
+
+        #[inline]
+        pub fn arg_types(&self) -> &[ExpressionArgType] {
+            &self.arg_types
+        }
+
+        // evalexpr passes no argument as the empty value and several ones as a tuple.
+        fn call(
+            &self,
+            blackboard: &mut BlackBoard<C>,
+            world: &mut W,
+            entity: &E,
+            argument: &Value,
+        ) -> EvalexprResult<Value> {
+            let arguments = match (self.arg_types.len(), argument) {
+                (0, Value::Empty) => &[],
+                (1, argument) => std::slice::from_ref(argument),
+                (_, Value::Tuple(arguments)) => arguments.as_slice(),
+                (arity, _) => return Err(EvalexprError::wrong_function_argument_amount(1, arity)),
+            };
+            if arguments.len() != self.arg_types.len() {
+                return Err(EvalexprError::wrong_function_argument_amount(
+                    arguments.len(),
+                    self.arg_types.len(),
+                ));
+            }
+            for (arg_type, argument) in self.arg_types.iter().zip(arguments) {
+                if !arg_type.accepts(argument) {
+                    return Err(arg_type.expected_error(argument));
+                }
+            }
+            (self.func)(blackboard, world, entity, arguments)
+        }
+    }
+
+    pub type ExpressionFunctionMap<C, W, E> =
+        HashMap<String, ExpressionFunction<C, W, E>, RandomState>;
+
+    // Blackboard variables of one evaluation. Unlike `HashMapContext` an assignment may change the
+    // value type, e.g. an Option from `()` to a number, the result is checked when written back.
+    #[derive(Default)]
+    struct VariableContext {
+        variables: HashMap<String, Value, RandomState>,
+    }
+    impl Context for VariableContext {
+        type NumericTypes = DefaultNumericTypes;
+
+        fn get_value(&self, identifier: &str) -> Option<&Value> {
+            self.variables.get(identifier)
+        }
+
+        // Functions for the Option and container values, `len` is an evalexpr builtin function.
+        fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+            match identifier {
+                "is_some" => Ok(Value::Boolean(!argument.is_empty())),
+                "get" => {
+                    let arguments = argument.as_fixed_len_tuple(2)?;
+                    let items = arguments[0].as_tuple()?;
+                    let index = arguments[1].as_number()?;
+                    if index < 0.0 || index.fract() != 0.0 || index as usize >= items.len() {
+                        return Err(EvalexprError::CustomMessage(format!(
+                            "index out of range, index={}, len={}",
+                            index,
+                            items.len()
+                        )));
+                    }
+                    Ok(items[index as usize].clone())
+                }
+                _ => Err(EvalexprError::FunctionIdentifierNotFound(identifier.to_string())),
+            }
+        }
+
+        fn are_builtin_functions_disabled(&self) -> bool {
+            false
+        }
+
+        fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+            if disabled {
+                Err(EvalexprError::BuiltinFunctionsCannotBeDisabled)
+            } else {
+                Ok(())
+            }
+        }
+    }
+    impl ContextWithMutableVariables for VariableContext {
+        fn set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
+            self.variables.insert(identifier, value);
+            Ok(())
+        }
+    }
+
+    // Blackboard variables plus the host functions, functions not registered fall back to the
+    // ones of `VariableContext`, then to the evalexpr builtin ones.
+    struct HostContext<'a, C, W, E> {
+        variables: VariableContext,
+        functions: &'a ExpressionFunctionMap<C, W, E>,
+        host: RefCell<(&'a mut BlackBoard<C>, &'a mut W)>,
+        entity: &'a E,
+    }
+    impl<C, W, E> Context for HostContext<'_, C, W, E> {
+        type NumericTypes = DefaultNumericTypes;
+
+        fn get_value(&self, identifier: &str) -> Option<&Value> {
+            self.variables.get_value(identifier)
+        }
+
+        fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+            match self.functions.get(identifier) {
+                Some(function) => {
+                    let (blackboard, world) = &mut *self.host.borrow_mut();
+                    function.call(blackboard, world, self.entity, argument)
+                }
+                None => self.variables.call_function(identifier, argument),
             }
-            let status = match self.expr.eval_with_context_mut(&mut context) {
+        }
+
+        fn are_builtin_functions_disabled(&self) -> bool {
+            false
+        }
+
+        fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+            self.variables.set_builtin_functions_disabled(disabled)
+        }
+    }
+    impl<C, W, E> ContextWithMutableVariables for HostContext<'_, C, W, E> {
+        fn set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
+            self.variables.set_value(identifier, value)
+        }
+    }
+
+    // TODO: 指针优化访问变量优化
+    type VariableMap = HashMap<String, SharedBlackBoardValue, RandomState>;
+    struct ExpressionWrapper {
+        expr: Node,
+        raw_expr: String,
+        variable_map: VariableMap,
+        // context: HashMapContext::<DefaultNumericTypes>,
+    }
+
+    impl ExpressionWrapper {
+        pub fn new(
+            tree_name: &str,
+            tree_index: i32,
+            tree_depth: i32,
+            node_index: i32,
+            expr: &str,
+            bb_map: &BlackBoardMap,
+        ) -> Result<Self, BehaviorError> {
+            let raw_expr = expr.to_string();
+            let expr = match build_operator_tree::<DefaultNumericTypes>(expr) {
+                Ok(expr) => expr,
                 Err(err) => {
-                    eprintln!("ExpressionWrapper::eval_with_context_mut failed, expression={}, error_info={}", self.raw_expr, err);
-                    return Status::Failure;
+                    return Err(BehaviorError::ExpressionInvalidOperatorTree {
+                        tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
+                        node_index,
+                        expression: raw_expr,
+                        error_info: err.to_string(),
+                    });
+                }
+            };
+            let mut variable_map = VariableMap::new();
+            // let mut context = HashMapContext::<DefaultNumericTypes>::new();
+            for var in expr.iter_variable_identifiers() {
+                if !variable_map.contains_key(var) {
+                    match bb_map.get(var) {
+                        Some(value) => {
+                            if value.is_expr_var() {
+                                variable_map.insert(var.to_string(), value.share());
+                                // let value = value.get_as_f64().unwrap();
+                                // context.set_value(var.to_string(), Value::from_float(value)).unwrap();
+                            } else {
+                                return Err(BehaviorError::ExpressionInvalidVariable {
+                                    tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
+                                    node_index,
+                                    expression: raw_expr,
+                                    blackboard_name: value.bb_name().to_string(),
+                                    blackboard_type: value.bb_type().to_string(),
+                                });
+                            }
+                        }
+                        None => {
+                            return Err(BehaviorError::ExpressionVariableNotExist {
+                                tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
+                                node_index,
+                                expression: raw_expr,
+                                blackboard_name: var.to_string(),
+                            });
+                        }
+                    }
+                }
+            }
+            Ok(Self { expr, raw_expr, variable_map})
+        }
+
+        // Checks the calls of the registered functions and keeps the ones used.
+        fn resolve_functions<C, W, E>(
+            &self,
+            tree_name: &str,
+            tree_index: i32,
+            tree_depth: i32,
+            node_index: i32,
+            functions: &ExpressionFunctionMap<C, W, E>,
+        ) -> Result<ExpressionFunctionMap<C, W, E>, BehaviorError> {
+            let mut used_functions = ExpressionFunctionMap::new();
+            for node in self.expr.iter() {
+                let Operator::FunctionIdentifier { identifier } = node.operator() else {
+                    continue;
+                };
+                let Some(function) = functions.get(identifier) else {
+                    continue;
+                };
+                let arguments = Self::call_arguments(node);
+                if arguments.len() != function.arg_types.len() {
+                    return Err(BehaviorError::ExpressionFunctionArityMismatch {
+                        tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
+                        node_index,
+                        expression: self.raw_expr.clone(),
+                        function_name: identifier.clone(),
+                        expected_arity: function.arg_types.len(),
+                        found_arity: arguments.len(),
+                    });
+                }
+                for (argument_index, (arg_type, argument)) in
+                    function.arg_types.iter().zip(arguments).enumerate()
+                {
+                    let Some(found_type) = self.static_type(argument) else {
+                        continue;
+                    };
+                    if *arg_type != ExpressionArgType::Any && *arg_type != found_type {
+                        return Err(BehaviorError::ExpressionFunctionArgumentMismatch {
+                            tree_location: TreeLocation::new(tree_name, tree_index, tree_depth),
+                            node_index,
+                            expression: self.raw_expr.clone(),
+                            function_name: identifier.clone(),
+                            argument_index,
+                            expected_type: format!("{:?}", arg_type),
+                            found_type: format!("{:?}", found_type),
+                        });
+                    }
+                }
+                used_functions.insert(identifier.clone(), function.clone());
+            }
+            Ok(used_functions)
+        }
+
+        // `f()` has a root node without children, `f(a, b)` a root node holding a tuple.
+        fn call_arguments(node: &Node) -> Vec<&Node> {
+            let mut argument = &node.children()[0];
+            while matches!(argument.operator(), Operator::RootNode)
+                && argument.children().len() == 1
+            {
+                argument = &argument.children()[0];
+            }
+            match argument.operator() {
+                Operator::RootNode => Vec::new(),
+                Operator::Tuple => argument.children().iter().collect(),
+                _ => vec![argument],
+            }
+        }
+
+        // The type of literals and blackboard variables, None if only known when evaluated.
+        fn static_type(&self, node: &Node) -> Option<ExpressionArgType> {
+            match node.operator() {
+                Operator::RootNode if node.children().len() == 1 => {
+                    self.static_type(&node.children()[0])
                 }
-                Ok(res) => match res {
-                    Value::Boolean(val) => Status::from(val),
-                    _ => Status::Success,
+                Operator::Const { value } => ExpressionArgType::of(value),
+                Operator::VariableIdentifierRead { identifier } => self
+                    .variable_map
+                    .get(identifier)
+                    .and_then(|value| value.expression_type())
+                    .filter(|value_type| *value_type != ExpressionArgType::Any),
+                _ => None,
+            }
+        }
+
+        pub fn eval<X>(&mut self, context: &mut X) -> Status
+        where
+            X: ContextWithMutableVariables + Context<NumericTypes = DefaultNumericTypes>,
+        {
+            match self.eval_value(context) {
+                None => Status::Failure,
+                Some(Value::Boolean(val)) => Status::from(val),
+                Some(_) => Status::Success,
+            }
+        }
+
+        // Booleans count as 1 and 0, other non-numeric results fail.
+        pub fn eval_number<X>(&mut self, context: &mut X) -> Option<f64>
+        where
+            X: ContextWithMutableVariables + Context<NumericTypes = DefaultNumericTypes>,
+        {
+            match self.eval_value(context)? {
+                Value::Boolean(val) => Some(if val { 1.0 } else { 0.0 }),
+                value => match value.as_number() {
+                    Ok(number) => Some(number),
+                    Err(err) => {
+                        eprintln!("ExpressionWrapper::eval_number failed, expression={}, error_info={}", self.raw_expr, err);
+                        None
+                    }
                 },
+            }
+        }
+
+        fn eval_value<X>(&mut self, context: &mut X) -> Option<Value>
+        where
+            X: ContextWithMutableVariables + Context<NumericTypes = DefaultNumericTypes>,
+        {
+            for (var, value) in self.variable_map.iter() {
+                let value = value.get_as_expr_value().unwrap();
+                context.set_value(var.clone(), value).unwrap();
+            }
+            let result = match self.expr.eval_with_context_mut(context) {
+                Err(err) => {
+                    eprintln!("ExpressionWrapper::eval_with_context_mut failed, expression={}, error_info={}", self.raw_expr, err);
+                    return None;
+                }
+                Ok(res) => res,
             };
             for (var, value) in self.variable_map.iter_mut() {
                 match context.get_value(var) {
-                    Some(val) => match val {
-                        Value::Float(val) => {
-                            if !value.set_from_f64(*val) {
-                                eprintln!("ExpressionWrapper::set_from_f64 failed, expression={}, type_name={}, var={}", self.raw_expr, value.bb_type(), var);
-                                return Status::Failure;
-                            };
-                        }
-                        _ => unreachable!(),
-                    },
+                    Some(val) => {
+                        if !value.set_from_expr_value(val) {
+                            eprintln!("ExpressionWrapper::set_from_expr_value failed, expression={}, type_name={}, var={}, value={}", self.raw_expr, value.bb_type(), var, val);
+                            return None;
+                        };
+                    }
                     _ => unreachable!(),
                 }
             }
-            status
+            Some(result)
+        }
+    }
+
+    // Scores a `UtilitySelect` child or weighs a branch with an expression over the blackboard.
+    pub struct ExpressionScorer<C, W, E> {
+        wrapper: ExpressionWrapper,
+        // the registered functions called by the expression.
+        functions: ExpressionFunctionMap<C, W, E>,
+    }
+    impl<C, W, E> ExpressionScorer<C, W, E> {
+        pub fn new(
+            tree_name: &str,
+            tree_index: i32,
+            tree_depth: i32,
+            index: i32,
+            expression: &str,
+            bb_map: &BlackBoardMap,
+            functions: &ExpressionFunctionMap<C, W, E>,
+        ) -> Result<Self, crate::BehaviorError> {
+            let wrapper =
+                ExpressionWrapper::new(tree_name, tree_index, tree_depth, index, expression, bb_map)?;
+            let functions =
+                wrapper.resolve_functions(tree_name, tree_index, tree_depth, index, functions)?;
+            Ok(Self { wrapper, functions })
+        }
+
+        pub fn score(
+            &mut self,
+            blackboard: &mut BlackBoard<C>,
+            world: &mut W,
+            entity: &E,
+        ) -> Option<f64> {
+            if self.functions.is_empty() {
+                self.wrapper.eval_number(&mut VariableContext::default())
+            } else {
+                let mut context = HostContext {
+                    variables: VariableContext::default(),
+                    functions: &self.functions,
+                    host: RefCell::new((blackboard, world)),
+                    entity,
+                };
+                self.wrapper.eval_number(&mut context)
+            }
         }
     }
 
@@ -583,7 +1825,9 @@ cfg_expression_node! {
         base: TreeNodeBase,
         index: i32,
         wrapper: ExpressionWrapper,
-        _marker: PhantomData<(A, C, W, E, F)>,
+        // the registered functions called by the expression.
+        functions: ExpressionFunctionMap<C, W, E>,
+        _marker: PhantomData<fn() -> (A, C, W, E, Box<F>)>,
     }
     impl<A, C, F: ?Sized, W, E> ExpressionNode<A, C, F, W, E> {
         pub fn new(
@@ -593,14 +1837,18 @@ cfg_expression_node! {
             index: i32,
             expression: &str,
             bb_map: &BlackBoardMap,
+            functions: &ExpressionFunctionMap<C, W, E>,
         ) -> Result<Self, crate::BehaviorError> {
             let base = TreeNodeBase::default();
             let wrapper =
                 ExpressionWrapper::new(tree_name, tree_index, tree_depth, index, expression, bb_map)?;
+            let functions =
+                wrapper.resolve_functions(tree_name, tree_index, tree_depth, index, functions)?;
             Ok(Self {
                 base,
                 index,
                 wrapper,
+                functions,
                 _marker: PhantomData,
             })
         }
@@ -620,16 +1868,26 @@ cfg_expression_node! {
             &mut self,
             #[allow(unused)] blackboard: &mut BlackBoard<Self::BlackBoardContext>,
             _func: &mut Self::ActionTickFunc,
-            _world: &mut Self::World,
-            _entity: &Self::Entity,
+            world: &mut Self::World,
+            entity: &Self::Entity,
         ) -> Status {
-            let status = self.wrapper.eval();
+            let status = if self.functions.is_empty() {
+                self.wrapper.eval(&mut VariableContext::default())
+            } else {
+                let mut context = HostContext {
+                    variables: VariableContext::default(),
+                    functions: &self.functions,
+                    host: RefCell::new((&mut *blackboard, world)),
+                    entity,
+                };
+                self.wrapper.eval(&mut context)
+            };
             set_status!(self, blackboard, status)
         }
 
         fn reset(
             &mut self,
-            _ctx: &mut Self::BlackBoardContext,
+            _blackboard: &mut BlackBoard<Self::BlackBoardContext>,
             _world: &mut Self::World,
             _entity: &Self::Entity,
         ) {
@@ -658,5 +1916,372 @@ cfg_expression_node! {
         > {
             Vec::new()
         }
+
+        fn children_mut(
+            &mut self,
+        ) -> Vec<
+            &mut Box<
+                dyn TreeNode<
+                    Action = Self::Action,
+                    BlackBoardContext = Self::BlackBoardContext,
+                    ActionTickFunc = Self::ActionTickFunc,
+                    World = Self::World,
+                    Entity = Self::Entity,
+                >,
+            >,
+        > {
+            Vec::new()
+        }
+    }
+}
+
+// Stands in for `ExpressionScorer` without the expression_node feature, it can't be created.
+#[cfg(not(feature = "expression_node"))]
+pub struct ExpressionScorer<C, W, E> {
+    never: std::convert::Infallible,
+    _marker: PhantomData<fn() -> (C, W, E)>,
+}
+#[cfg(not(feature = "expression_node"))]
+impl<C, W, E> ExpressionScorer<C, W, E> {
+    pub fn score(
+        &mut self,
+        _blackboard: &mut BlackBoard<C>,
+        _world: &mut W,
+        _entity: &E,
+    ) -> Option<f64> {
+        match self.never {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::factory::BtFactory;
+    use crate::{BehaviorError, Status};
+
+    const TREE_JSON: &str = r#"{
+        "tree_blackboard": [
+            { "bb_name": "hp", "bb_type": "i32", "bb_value": "30" },
+            { "bb_name": "max_hp", "bb_type": "i32", "bb_value": "100" },
+            { "bb_name": "name", "bb_type": "String", "bb_value": "None" },
+            { "bb_name": "target", "bb_type": "Option<i64>", "bb_value": "7" }
+        ],
+        "tree_structure": { "Sequence": [1, [
+            { "CompareBlackBoard": [2, "hp", "Lt", "<max_hp>"] },
+            { "CompareBlackBoard": [3, "hp", "Eq", "30"] },
+            { "CompareBlackBoard": [4, "hp", "Eq", "<hp>"] },
+            { "SetBlackBoard": [5, "hp", "<max_hp>"] },
+            { "SetBlackBoard": [6, "name", "healed"] },
+            { "CompareBlackBoard": [7, "name", "Ne", "None"] },
+            { "IsBlackBoardSet": [8, "target"] },
+            { "ClearBlackBoard": [9, "target"] },
+            { "IsBlackBoardSet": [10, "target"] }
+        ]] }
+    }"#;
+
+    fn create_factory() -> BtFactory<(), (), ()> {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory.register_comparable_blackboard_type::<Option<i64>>();
+        bt_factory.register_option_blackboard_type::<i64>();
+        bt_factory.register_default_blackboard_type::<Vec<i32>>();
+        bt_factory
+    }
+
+    fn compile_error(bt_factory: &mut BtFactory<(), (), ()>, structure: &str) -> BehaviorError {
+        let tree_json = format!(
+            r#"{{
+                "tree_blackboard": [
+                    {{ "bb_name": "hp", "bb_type": "i32", "bb_value": "30" }},
+                    {{ "bb_name": "rate", "bb_type": "f32", "bb_value": "0.5" }},
+                    {{ "bb_name": "path", "bb_type": "Vec<i32>", "bb_value": "1|2" }}
+                ],
+                "tree_structure": {}
+            }}"#,
+            structure
+        );
+        bt_factory
+            .compile_tree_template_from_json_str("error", &tree_json)
+            .unwrap();
+        match bt_factory.create_tree_instance("error") {
+            Ok(_) => panic!("the tree should not compile: {}", structure),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn compare_and_set_blackboard() {
+        let mut bt_factory = create_factory();
+        bt_factory
+            .compile_tree_template_from_json_str("test", TREE_JSON)
+            .unwrap();
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        // the target is cleared before it is checked the second time.
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+
+        let blackboard_map = instance.blackboard_map_ref();
+        let hp = blackboard_map["hp"].borrow();
+        assert_eq!(hp.downcast_ref::<i32>(), Some(&100));
+        let name = blackboard_map["name"].borrow();
+        assert_eq!(name.downcast_ref::<String>().unwrap(), "healed");
+        let target = blackboard_map["target"].borrow();
+        assert_eq!(target.downcast_ref::<Option<i64>>(), Some(&None));
+    }
+
+    #[test]
+    fn invalid_blackboard_operations() {
+        let mut bt_factory = create_factory();
+        assert!(matches!(
+            compile_error(
+                &mut bt_factory,
+                r#"{ "CompareBlackBoard": [1, "path", "Eq", "1|2"] }"#
+            ),
+            BehaviorError::BlackBoardOperationUnsupported { .. }
+        ));
+        assert!(matches!(
+            compile_error(
+                &mut bt_factory,
+                r#"{ "CompareBlackBoard": [1, "hp", "Gt", "<rate>"] }"#
+            ),
+            BehaviorError::BlackBoardDowncastFailed { .. }
+        ));
+        assert!(matches!(
+            compile_error(&mut bt_factory, r#"{ "SetBlackBoard": [1, "hp", "full"] }"#),
+            BehaviorError::BlackBoardLiteralParseFailed { .. }
+        ));
+        assert!(matches!(
+            compile_error(&mut bt_factory, r#"{ "ClearBlackBoard": [1, "mana"] }"#),
+            BehaviorError::BlackBoardNotFound { .. }
+        ));
+        assert!(matches!(
+            compile_error(&mut bt_factory, r#"{ "IsBlackBoardSet": [1, "hp"] }"#),
+            BehaviorError::BlackBoardNotOption { .. }
+        ));
+        // clearing needs a default value.
+        let mut plain_factory = BtFactory::<(), (), ()>::new();
+        plain_factory.register_blackboard_type::<Vec<i32>>();
+        assert!(matches!(
+            compile_error(&mut plain_factory, r#"{ "ClearBlackBoard": [1, "path"] }"#),
+            BehaviorError::BlackBoardOperationUnsupported { .. }
+        ));
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "clear",
+                r#"{
+                    "tree_blackboard": [
+                        { "bb_name": "hp", "bb_type": "i32", "bb_value": "30" },
+                        { "bb_name": "name", "bb_type": "String", "bb_value": "healer" },
+                        { "bb_name": "path", "bb_type": "Vec<i32>", "bb_value": "1|2" }
+                    ],
+                    "tree_structure": { "Sequence": [1, [
+                        { "ClearBlackBoard": [2, "hp"] },
+                        { "ClearBlackBoard": [3, "name"] },
+                        { "ClearBlackBoard": [4, "path"] }
+                    ]] }
+                }"#,
+            )
+            .unwrap();
+        let mut instance = bt_factory.create_tree_instance("clear").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        let blackboard_map = instance.blackboard_map_ref();
+        let hp = blackboard_map["hp"].borrow();
+        assert_eq!(hp.downcast_ref::<i32>(), Some(&0));
+        let name = blackboard_map["name"].borrow();
+        assert_eq!(name.downcast_ref::<String>().unwrap(), "");
+        let path = blackboard_map["path"].borrow();
+        assert_eq!(path.downcast_ref::<Vec<i32>>(), Some(&Vec::new()));
+    }
+
+    #[cfg(feature = "expression_node")]
+    #[test]
+    fn expression_host_functions() {
+        use crate::evalexpr::Value;
+        use crate::ExpressionArgType::{Any, Number};
+
+        // the world holds the position of each entity, the context counts the moves.
+        let mut bt_factory = BtFactory::<i32, Vec<f64>, usize>::new();
+        bt_factory.register_expression_function("position", &[Number], |_, world, _, args| {
+            Ok(Value::from_float(world[args[0].as_number()? as usize]))
+        });
+        bt_factory.register_expression_function("here", &[], |_, world, entity, _| {
+            Ok(Value::from_float(world[*entity]))
+        });
+        bt_factory.register_expression_function(
+            "move_to",
+            &[Any],
+            |blackboard, world, entity, args| {
+                world[*entity] = args[0].as_number()?;
+                *blackboard.context_mut() += 1;
+                Ok(Value::Empty)
+            },
+        );
+        let compile = |bt_factory: &mut BtFactory<i32, Vec<f64>, usize>, expression: &str| {
+            let tree_json = format!(
+                r#"{{
+                    "tree_blackboard": [
+                        {{ "bb_name": "target", "bb_type": "i32", "bb_value": "1" }},
+                        {{ "bb_name": "dist", "bb_type": "f64", "bb_value": "0.0" }}
+                    ],
+                    "tree_structure": {{ "Expression": [1, "{}"] }}
+                }}"#,
+                expression
+            );
+            bt_factory
+                .compile_tree_template_from_json_str("test", &tree_json)
+                .unwrap();
+            bt_factory.create_tree_instance("test")
+        };
+
+        let mut instance = compile(
+            &mut bt_factory,
+            "dist = position(target) - here(); move_to(position(target)); dist > 0",
+        )
+        .unwrap();
+        let mut world = vec![1.0, 4.0];
+        assert_eq!(instance.as_mut().tick(&mut world, &0), Status::Success);
+        assert_eq!(world, vec![4.0, 4.0]);
+        assert_eq!(*instance.context_ref(), 1);
+        let dist = *instance.blackboard_map_ref()["dist"]
+            .borrow()
+            .downcast_ref::<f64>()
+            .unwrap();
+        assert_eq!(dist, 3.0);
+        assert_eq!(instance.as_mut().tick(&mut world, &0), Status::Failure);
+        // builtin functions still work, arguments only known when evaluated are checked then.
+        let mut instance = compile(&mut bt_factory, "min(here(), 2) == 2").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut world, &0), Status::Success);
+        let mut instance = compile(&mut bt_factory, "position(here() > 0) > 0").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut world, &0), Status::Failure);
+
+        assert!(matches!(
+            compile(&mut bt_factory, "position(target, 1) > 0").err(),
+            Some(BehaviorError::ExpressionFunctionArityMismatch {
+                expected_arity: 1,
+                found_arity: 2,
+                ..
+            })
+        ));
+        assert!(matches!(
+            compile(&mut bt_factory, "here(1) > 0").err(),
+            Some(BehaviorError::ExpressionFunctionArityMismatch { .. })
+        ));
+        assert!(matches!(
+            compile(&mut bt_factory, "position(true) > 0").err(),
+            Some(BehaviorError::ExpressionFunctionArgumentMismatch {
+                argument_index: 0,
+                ..
+            })
+        ));
+
+        // scorers, weights and probabilities call them as well, here() is 4 and position(0) 1.
+        for tree_structure in [
+            r#"{ "UtilitySelect": [1, false, 0.0, [
+                [{ "Expression": "position(0)" }, { "AlwaysFailure": 2 }],
+                [{ "Expression": "here()" }, { "AlwaysSuccess": 3 }]
+            ]] }"#,
+            r#"{ "WeightSelect": [1, "here() - position(1)|here()", [
+                { "AlwaysFailure": 2 },
+                { "AlwaysSuccess": 3 }
+            ]] }"#,
+            r#"{ "RandomSequence": [1, "here()|position(0)", [
+                { "AlwaysSuccess": 2 },
+                { "AlwaysSuccess": 3 }
+            ]] }"#,
+            r#"{ "Chance": [1, "here() / position(1)", { "AlwaysSuccess": 2 }] }"#,
+        ] {
+            bt_factory
+                .compile_tree_template_from_json_str(
+                    "weights",
+                    &format!(r#"{{ "tree_structure": {} }}"#, tree_structure),
+                )
+                .unwrap();
+            let mut instance = bt_factory.create_tree_instance("weights").unwrap();
+            for _ in 0..8 {
+                assert_eq!(
+                    instance.as_mut().tick(&mut vec![1.0, 4.0], &1),
+                    Status::Success
+                );
+            }
+        }
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "weights",
+                r#"{ "tree_structure": { "Chance": [1, "here(1)", { "AlwaysSuccess": 2 }] } }"#,
+            )
+            .unwrap();
+        assert!(matches!(
+            bt_factory.create_tree_instance("weights").err(),
+            Some(BehaviorError::ExpressionFunctionArityMismatch { .. })
+        ));
+    }
+
+    #[cfg(feature = "expression_node")]
+    #[test]
+    fn expression_non_numeric_blackboard() {
+        let mut bt_factory = create_factory();
+        bt_factory.register_expression_blackboard_type::<Option<i64>>();
+        bt_factory.register_expression_blackboard_type::<Vec<i32>>();
+        bt_factory.register_blackboard_type::<Vec<f32>>();
+        let compile = |bt_factory: &mut BtFactory<(), (), ()>, expression: &str| {
+            let tree_json = format!(
+                r#"{{
+                    "tree_blackboard": [
+                        {{ "bb_name": "alive", "bb_type": "bool", "bb_value": "true" }},
+                        {{ "bb_name": "name", "bb_type": "String", "bb_value": "orc" }},
+                        {{ "bb_name": "target", "bb_type": "Option<i64>", "bb_value": "7" }},
+                        {{ "bb_name": "path", "bb_type": "Vec<i32>", "bb_value": "1|2|3" }},
+                        {{ "bb_name": "weights", "bb_type": "Vec<f32>", "bb_value": "1.0" }},
+                        {{ "bb_name": "count", "bb_type": "i32", "bb_value": "0" }}
+                    ],
+                    "tree_structure": {{ "Expression": [1, "{}"] }}
+                }}"#,
+                expression.replace('"', "\\\"")
+            );
+            bt_factory
+                .compile_tree_template_from_json_str("test", &tree_json)
+                .unwrap();
+            bt_factory.create_tree_instance("test")
+        };
+
+        let mut instance = compile(
+            &mut bt_factory,
+            r#"alive && is_some(target) && len(path) == 3 && get(path, 1) == 2.0 && name == "orc""#,
+        )
+        .unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+
+        let mut instance = compile(
+            &mut bt_factory,
+            r#"count = len(path) + target; target = (); alive = !alive; name = name + "!"; path = (4, 5)"#,
+        )
+        .unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Success);
+        let blackboard_map = instance.blackboard_map_ref();
+        let value = |name: &str| format!("{:?}", blackboard_map[name].borrow());
+        assert_eq!(value("count"), "10");
+        assert_eq!(value("target"), "None");
+        assert_eq!(value("alive"), "false");
+        assert_eq!(value("name"), "\"orc!\"");
+        assert_eq!(value("path"), "[4, 5]");
+
+        // values that don't convert back and indexes out of range fail the node.
+        let mut instance = compile(&mut bt_factory, "alive = 1").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+        let mut instance = compile(&mut bt_factory, "get(path, 3) == 0").unwrap();
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Failure);
+        assert!(matches!(
+            compile(&mut bt_factory, "len(weights) > 0").err(),
+            Some(BehaviorError::ExpressionInvalidVariable { .. })
+        ));
+
+        // host functions see the blackboard types when the tree is compiled.
+        bt_factory.register_expression_function(
+            "not",
+            &[crate::ExpressionArgType::Boolean],
+            |_, _, _, args| Ok(crate::evalexpr::Value::Boolean(!args[0].as_boolean()?)),
+        );
+        assert!(compile(&mut bt_factory, "not(alive)").is_ok());
+        assert!(matches!(
+            compile(&mut bt_factory, "not(name)").err(),
+            Some(BehaviorError::ExpressionFunctionArgumentMismatch { .. })
+        ));
     }
 }
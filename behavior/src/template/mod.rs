@@ -1,16 +1,37 @@
 mod status;
 pub use status::{BranchData, Status};
 mod behavior;
-pub use behavior::Behavior;
+pub use behavior::{Behavior, UtilityScorer};
 mod blackboard;
-pub use blackboard::{BlackBoard, BlackBoardMap, BlackBoardType, SharedBlackBoardValue};
+pub use blackboard::{
+    BlackBoard, BlackBoardClearFunc, BlackBoardCmpFunc, BlackBoardCodec, BlackBoardEqFunc,
+    BlackBoardIsSetFunc, BlackBoardMap, BlackBoardOps, BlackBoardParseFunc, BlackBoardType,
+    SharedBlackBoardValue,
+};
 cfg_tree_visualization!(
     pub use blackboard::{Visualization, FlattenedVisualization};
 );
+cfg_expression_node!(
+    pub use blackboard::ExpressionAdapter;
+);
+cfg_thread_safe!(
+    pub use blackboard::BlackBoardGuard;
+);
+mod clock;
+pub use clock::{BtClock, ManualClock, SharedClock, WallClock};
 mod control_node;
 pub use control_node::*;
+mod event;
+pub use event::{Event, EventQueue};
+mod observer;
+pub use observer::TreeObserver;
+mod semaphore;
+pub use semaphore::{Semaphore, SharedSemaphore};
+mod snapshot;
+pub(crate) use snapshot::{load_tree_state, restore_status, save_tree_state};
+pub use snapshot::{BlackBoardValueState, NodeState};
 mod tree_node;
-pub use tree_node::{NodeType, TreeNode, TreeNodeBase, TreeNodeStatus, TreeNodeType};
+pub use tree_node::{MaybeSend, NodeType, TreeNode, TreeNodeBase, TreeNodeStatus, TreeNodeType};
 mod decorator_node;
 pub use decorator_node::*;
 mod leaf_node;
@@ -0,0 +1,144 @@
+use super::Status;
+use downcast_rs::{impl_downcast, Downcast};
+use std::fmt::Debug;
+
+// Receives the node events of the instance carrying it, see `BtInstance::set_observer`.
+// Nodes of a subtree report the subtree's name as `tree_name`.
+pub trait TreeObserver: Downcast + Debug + Send {
+    // The node is ticked while not running, i.e. it starts over.
+    fn on_enter(&mut self, _tree_name: &str, _node_index: i32, _node_name: &str) {}
+
+    // After a tick that left the node with another status than before.
+    fn on_status_change(
+        &mut self,
+        _tree_name: &str,
+        _node_index: i32,
+        _node_name: &str,
+        _prev_status: Status,
+        _status: Status,
+    ) {
+    }
+
+    // A running node is reset by its parent or `BtInstance::halt`, the status is Idle afterwards.
+    fn on_reset(&mut self, _tree_name: &str, _node_index: i32, _node_name: &str) {}
+
+    fn on_subtree_enter(&mut self, _tree_name: &str, _node_index: i32, _subtree_name: &str) {}
+
+    fn on_subtree_exit(
+        &mut self,
+        _tree_name: &str,
+        _node_index: i32,
+        _subtree_name: &str,
+        _status: Status,
+    ) {
+    }
+}
+impl_downcast!(TreeObserver);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::factory::BtFactory;
+
+    #[derive(Debug, Default)]
+    struct RecordObserver {
+        events: Vec<String>,
+    }
+
+    impl TreeObserver for RecordObserver {
+        fn on_enter(&mut self, tree_name: &str, node_index: i32, node_name: &str) {
+            self.events
+                .push(format!("enter {}:{} {}", tree_name, node_index, node_name));
+        }
+
+        fn on_status_change(
+            &mut self,
+            tree_name: &str,
+            node_index: i32,
+            _node_name: &str,
+            prev_status: Status,
+            status: Status,
+        ) {
+            self.events.push(format!(
+                "status {}:{} {:?}->{:?}",
+                tree_name, node_index, prev_status, status
+            ));
+        }
+
+        fn on_reset(&mut self, tree_name: &str, node_index: i32, _node_name: &str) {
+            self.events
+                .push(format!("reset {}:{}", tree_name, node_index));
+        }
+
+        fn on_subtree_enter(&mut self, tree_name: &str, node_index: i32, subtree_name: &str) {
+            self.events.push(format!(
+                "subtree_enter {}:{} {}",
+                tree_name, node_index, subtree_name
+            ));
+        }
+
+        fn on_subtree_exit(
+            &mut self,
+            tree_name: &str,
+            node_index: i32,
+            subtree_name: &str,
+            status: Status,
+        ) {
+            self.events.push(format!(
+                "subtree_exit {}:{} {} {:?}",
+                tree_name, node_index, subtree_name, status
+            ));
+        }
+    }
+
+    #[test]
+    fn observe_node_events() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "test",
+                r#"{ "tree_structure": { "Sequence": [1, [
+                    { "AlwaysSuccess": 2 },
+                    { "SubTree": [3, "child", {}] }
+                ]] } }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "child",
+                r#"{ "tree_structure": { "AlwaysRunning": 1 } }"#,
+            )
+            .unwrap();
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        instance
+            .as_mut()
+            .set_observer(Box::new(RecordObserver::default()));
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        instance.as_mut().halt(&mut (), &());
+
+        let observer = instance.as_mut().take_observer().unwrap();
+        let observer = observer.downcast_ref::<RecordObserver>().unwrap();
+        assert_eq!(
+            observer.events,
+            vec![
+                "enter test:1 SequenceNode",
+                "enter test:2 AlwaysSuccessNode",
+                "status test:2 Idle->Success",
+                "enter test:3 SubTreeNode",
+                "subtree_enter test:3 child",
+                "enter child:1 AlwaysRunningNode",
+                "status child:1 Idle->Running",
+                "subtree_exit test:3 child Running",
+                "status test:3 Idle->Running",
+                "status test:1 Idle->Running",
+                "subtree_enter test:3 child",
+                "subtree_exit test:3 child Running",
+                "reset test:1",
+                "reset test:3",
+                "reset child:1",
+            ]
+        );
+        assert!(instance.as_mut().take_observer().is_none());
+    }
+}
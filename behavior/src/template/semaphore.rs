@@ -0,0 +1,119 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+// Counted slots shared by the `Semaphore` nodes naming it across all instances of a factory,
+// instances ticked in parallel included. See `BtFactory::register_semaphore`.
+#[derive(Debug)]
+pub struct Semaphore {
+    name: String,
+    capacity: AtomicI64,
+    acquired: AtomicI64,
+}
+
+pub type SharedSemaphore = Arc<Semaphore>;
+
+impl Semaphore {
+    pub fn new(name: &str, capacity: i64) -> Self {
+        Self {
+            name: name.to_string(),
+            capacity: AtomicI64::new(capacity),
+            acquired: AtomicI64::new(0),
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> i64 {
+        self.capacity.load(Ordering::Acquire)
+    }
+
+    // Slots acquired beyond a lowered capacity are kept until released.
+    #[inline]
+    pub fn set_capacity(&self, capacity: i64) {
+        self.capacity.store(capacity, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn acquired(&self) -> i64 {
+        self.acquired.load(Ordering::Acquire)
+    }
+
+    pub fn try_acquire(&self) -> bool {
+        self.acquired
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |acquired| {
+                (acquired < self.capacity()).then_some(acquired + 1)
+            })
+            .is_ok()
+    }
+
+    #[inline]
+    pub fn release(&self) {
+        self.acquired.fetch_sub(1, Ordering::AcqRel);
+    }
+
+    // Takes a slot even if none is free, e.g. for a node restored from a snapshot holding one.
+    #[inline]
+    pub(crate) fn force_acquire(&self) {
+        self.acquired.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::factory::BtFactory;
+    use crate::Status;
+
+    #[test]
+    fn semaphore_shared_across_instances() {
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        let door = bt_factory.register_semaphore("door", 1);
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "open",
+                r#"{ "tree_structure": { "Semaphore": [1, "door", false, { "WaitTicks": [2, 1] }] } }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "queue",
+                r#"{ "tree_structure": { "Semaphore": [1, "door", true, { "AlwaysSuccess": 2 }] } }"#,
+            )
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "missing",
+                r#"{ "tree_structure": { "Semaphore": [1, "gate", false, { "AlwaysSuccess": 2 }] } }"#,
+            )
+            .unwrap();
+        assert!(bt_factory.create_tree_instance("missing").is_err());
+
+        let mut first = bt_factory.create_tree_instance("open").unwrap();
+        let mut second = bt_factory.create_tree_instance("open").unwrap();
+        let mut queued = bt_factory.create_tree_instance("queue").unwrap();
+        assert_eq!(first.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(second.as_mut().tick(&mut (), &()), Status::Failure);
+        assert_eq!(queued.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(door.acquired(), 1);
+        assert_eq!(first.as_mut().tick(&mut (), &()), Status::Success);
+        assert_eq!(queued.as_mut().tick(&mut (), &()), Status::Success);
+        assert_eq!(door.acquired(), 0);
+
+        // halting or dropping the holder releases the slot.
+        assert_eq!(first.as_mut().tick(&mut (), &()), Status::Running);
+        first.as_mut().halt(&mut (), &());
+        assert_eq!(door.acquired(), 0);
+        assert_eq!(second.as_mut().tick(&mut (), &()), Status::Running);
+        drop(second);
+        assert_eq!(door.acquired(), 0);
+
+        bt_factory.register_semaphore("door", 2);
+        assert_eq!(first.as_mut().tick(&mut (), &()), Status::Running);
+        let mut third = bt_factory.create_tree_instance("open").unwrap();
+        assert_eq!(third.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(door.acquired(), 2);
+    }
+}
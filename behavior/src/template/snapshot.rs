@@ -0,0 +1,191 @@
+use super::status::Status::Idle;
+use super::{Status, TreeNode, TreeNodeStatus};
+use crate::BehaviorError;
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+// Runtime state of one node, nodes are stored in pre-order of the tree they belong to.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, PartialEq)]
+pub struct NodeState {
+    pub node_index: i32,
+    pub status: Status,
+    pub data: Vec<u8>,
+}
+
+impl NodeState {
+    pub fn new(node_index: i32, status: Status) -> Self {
+        Self {
+            node_index,
+            status,
+            data: Vec::new(),
+        }
+    }
+
+    pub fn encode<T: Encode>(&mut self, value: &T) -> Result<(), BehaviorError> {
+        self.data =
+            bincode::encode_to_vec(value, bincode::config::standard()).map_err(|error| {
+                BehaviorError::SnapshotEncodeFailed {
+                    node_index: self.node_index,
+                    error_info: error.to_string(),
+                }
+            })?;
+        Ok(())
+    }
+
+    pub fn decode<T: Decode>(&self) -> Result<T, BehaviorError> {
+        bincode::decode_from_slice(&self.data, bincode::config::standard())
+            .map(|(value, _)| value)
+            .map_err(|error| BehaviorError::RestoreSnapshotFailed {
+                node_index: self.node_index,
+                error_info: error.to_string(),
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, PartialEq)]
+pub struct BlackBoardValueState {
+    pub name: String,
+    pub value_type: String,
+    pub data: Vec<u8>,
+}
+
+// Idle can't be set through `set_status`.
+#[inline]
+pub(crate) fn restore_status<T: TreeNodeStatus + ?Sized>(node: &mut T, status: Status) {
+    match status {
+        Idle => node.reset_status(),
+        status => node.set_status(status),
+    }
+}
+
+pub(crate) fn save_tree_state<A: TreeNode, C, F: ?Sized, W, E>(
+    node: &dyn TreeNode<
+        Action = A,
+        BlackBoardContext = C,
+        ActionTickFunc = F,
+        World = W,
+        Entity = E,
+    >,
+    states: &mut Vec<NodeState>,
+) -> Result<(), BehaviorError> {
+    let mut state = NodeState::new(node.node_index(), node.get_status());
+    node.save_state(&mut state)?;
+    states.push(state);
+    for child in node.children() {
+        save_tree_state(child.as_ref(), states)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn load_tree_state<A: TreeNode, C, F: ?Sized, W, E>(
+    node: &mut dyn TreeNode<
+        Action = A,
+        BlackBoardContext = C,
+        ActionTickFunc = F,
+        World = W,
+        Entity = E,
+    >,
+    states: &mut std::slice::Iter<'_, NodeState>,
+) -> Result<(), BehaviorError> {
+    let node_index = node.node_index();
+    let state = states
+        .next()
+        .ok_or_else(|| BehaviorError::RestoreSnapshotFailed {
+            node_index,
+            error_info: "node state not found".to_string(),
+        })?;
+    if state.node_index != node_index {
+        return Err(BehaviorError::RestoreSnapshotFailed {
+            node_index,
+            error_info: format!("node index mismatch, state_node_index={}", state.node_index),
+        });
+    }
+    restore_status(node, state.status);
+    node.load_state(state)?;
+    for child in node.children_mut() {
+        load_tree_state(child.as_mut(), states)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::factory::{BtFactory, BtInstanceSnapshot};
+    use crate::{ManualClock, Status};
+    use std::sync::Arc;
+
+    const TREE_JSON: &str = r#"{
+        "tree_blackboard": [{ "bb_name": "count", "bb_type": "i32", "bb_value": "3" }],
+        "tree_structure": {
+            "Sequence": [1, [
+                { "Repeat": [2, 2, { "AlwaysSuccess": 3 }] },
+                { "Wait": [4, 100] },
+                { "SubTree": [5, "child", { "count": "count" }] }
+            ]]
+        }
+    }"#;
+    const CHILD_JSON: &str = r#"{
+        "tree_blackboard": [{ "bb_name": "count", "bb_type": "i32", "bb_value": "0" }],
+        "tree_structure": { "Wait": [1, 50] }
+    }"#;
+
+    fn create_factory() -> (BtFactory<(), (), ()>, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(0));
+        let mut bt_factory = BtFactory::<(), (), ()>::new();
+        bt_factory.set_clock(clock.clone());
+        bt_factory
+            .compile_tree_template_from_json_str("test", TREE_JSON)
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str("child", CHILD_JSON)
+            .unwrap();
+        (bt_factory, clock)
+    }
+
+    #[test]
+    fn snapshot_and_restore() {
+        let (bt_factory, clock) = create_factory();
+        let mut instance = bt_factory.create_tree_instance("test").unwrap();
+        // Repeat runs once, then Wait starts.
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        assert_eq!(instance.as_mut().tick(&mut (), &()), Status::Running);
+        clock.advance(60);
+
+        let snapshot = instance.snapshot().unwrap();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: BtInstanceSnapshot = serde_json::from_str(&json).unwrap();
+        let bytes = bincode::encode_to_vec(&snapshot, bincode::config::standard()).unwrap();
+        let (snapshot, _): (BtInstanceSnapshot, _) =
+            bincode::decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+
+        let mut restored = bt_factory.restore_tree_instance("test", &snapshot).unwrap();
+        assert_eq!(restored.snapshot().unwrap(), snapshot);
+        for _ in 0..3 {
+            assert_eq!(
+                instance.as_mut().tick(&mut (), &()),
+                restored.as_mut().tick(&mut (), &())
+            );
+            clock.advance(50);
+        }
+        assert_eq!(instance.snapshot().unwrap(), restored.snapshot().unwrap());
+    }
+
+    #[test]
+    fn restore_mismatched_snapshot() {
+        let (mut bt_factory, _) = create_factory();
+        let snapshot = bt_factory
+            .create_tree_instance("test")
+            .unwrap()
+            .snapshot()
+            .unwrap();
+        bt_factory
+            .compile_tree_template_from_json_str(
+                "child",
+                r#"{ "tree_structure": { "Wait": [1, 50] } }"#,
+            )
+            .unwrap();
+        assert!(bt_factory
+            .restore_tree_instance("child", &snapshot)
+            .is_err());
+    }
+}
@@ -1,7 +1,9 @@
 // Inner must be unsigned integer type
 type Inner = u32;
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(
+    serde::Serialize, serde::Deserialize, bincode::Encode, bincode::Decode, Copy, Clone, PartialEq,
+)]
 pub struct BranchData {
     inner: Inner,
 }
@@ -81,7 +83,16 @@ impl std::fmt::Debug for BranchData {
 }
 
 // IMPORTANT: Custom nodes should NEVER return Idle.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+)]
 pub enum Status {
     Idle,
     Success,
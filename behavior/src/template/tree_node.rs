@@ -1,6 +1,8 @@
 use super::blackboard::BlackBoard;
+use super::snapshot::NodeState;
 use super::status::Status::*;
 use super::Status;
+use crate::BehaviorError;
 use std::fmt::Debug;
 #[cfg(feature = "tree_visualization")]
 use std::fmt::Write;
@@ -56,7 +58,18 @@ impl TreeNodeStatus for TreeNodeBase {
     }
 }
 
-pub trait TreeNode: TreeNodeStatus {
+cfg_thread_safe! {
+    // Send with the `thread_safe` feature so that instances can move between threads, nodes are
+    // only ever used by the thread ticking their instance.
+    pub trait MaybeSend: Send {}
+    impl<T: ?Sized + Send> MaybeSend for T {}
+}
+cfg_not_thread_safe! {
+    pub trait MaybeSend {}
+    impl<T: ?Sized> MaybeSend for T {}
+}
+
+pub trait TreeNode: TreeNodeStatus + MaybeSend {
     type Action: TreeNode;
     type BlackBoardContext;
     type ActionTickFunc: ?Sized;
@@ -72,6 +85,21 @@ pub trait TreeNode: TreeNodeStatus {
         entity: &Self::Entity,
     ) -> Status;
 
+    // Entry point when a parent or the instance ticks the node.
+    #[inline]
+    fn execute_tick(
+        &mut self,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
+        func: &mut Self::ActionTickFunc,
+        world: &mut Self::World,
+        entity: &Self::Entity,
+    ) -> Status {
+        if blackboard.is_observed() && !self.is_running() {
+            blackboard.notify_enter(self.node_index(), self.node_name());
+        }
+        self.control_tick(blackboard, func, world, entity)
+    }
+
     // tick for action node
     fn action_tick(
         &mut self,
@@ -82,9 +110,20 @@ pub trait TreeNode: TreeNodeStatus {
         Success
     }
 
+    // score for action node used as a scorer
+    #[inline]
+    fn action_score(
+        &mut self,
+        _ctx: &mut Self::BlackBoardContext,
+        _world: &mut Self::World,
+        _entity: &Self::Entity,
+    ) -> f64 {
+        0.0
+    }
+
     fn reset(
         &mut self,
-        ctx: &mut Self::BlackBoardContext,
+        blackboard: &mut BlackBoard<Self::BlackBoardContext>,
         world: &mut Self::World,
         entity: &Self::Entity,
     );
@@ -111,6 +150,29 @@ pub trait TreeNode: TreeNodeStatus {
         >,
     >;
 
+    fn children_mut(
+        &mut self,
+    ) -> Vec<
+        &mut Box<
+            dyn TreeNode<
+                Action = Self::Action,
+                BlackBoardContext = Self::BlackBoardContext,
+                ActionTickFunc = Self::ActionTickFunc,
+                World = Self::World,
+                Entity = Self::Entity,
+            >,
+        >,
+    >;
+
+    // runtime state besides the status, children are saved by the caller.
+    fn save_state(&self, _state: &mut NodeState) -> Result<(), BehaviorError> {
+        Ok(())
+    }
+
+    fn load_state(&mut self, _state: &NodeState) -> Result<(), BehaviorError> {
+        Ok(())
+    }
+
     cfg_tree_visualization! {
         fn visualize(
             &self,
@@ -128,12 +190,16 @@ pub trait TreeNode: TreeNodeStatus {
 
             write!(
                 s,
-                "{}[{}]{}:{}\n",
+                "{}[{}]{}:{}",
                 prefix,
                 self.node_index(),
                 self.node_name(),
                 viz.get_node_status(self.node_index()).as_str()
             )?;
+            if let Some(scores) = viz.get_node_scores(self.node_index()) {
+                write!(s, " scores={:?}", scores)?;
+            }
+            s.push('\n');
             let children = self.children();
             let children_len = children.len();
             for (i, child) in children.into_iter().enumerate() {
@@ -0,0 +1,189 @@
+use behavior::{
+    batch::available_worker_count,
+    factory::BtFactory,
+    generate_node,
+    manager::BtManager,
+    node::{BlackBoardCell, BtNode, BtNodeGenerator, Executor, MetaDataCell},
+    BehaviorError, BlackBoardMap, Status, TreeNodeBase, TreeNodeStatus,
+};
+use behavior_macros::TreeNodeStatus;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const INSTANCE_COUNT: u64 = 20_000;
+
+#[derive(Debug, Default)]
+pub struct Context {}
+
+// Read-only view of the game world shared by all workers, mutations go to the command buffer
+// of the worker and are applied after the batch.
+#[derive(Debug, Default)]
+pub struct World {
+    positions: Arc<Vec<f32>>,
+    commands: Vec<(u64, f32)>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, Hash, PartialEq)]
+pub struct Entity(pub u64);
+
+#[derive(Debug, TreeNodeStatus)]
+pub struct MoveToTarget {
+    base: TreeNodeBase,
+    target: BlackBoardCell<f32>,
+    speed: MetaDataCell<f32>,
+}
+impl BtNodeGenerator for MoveToTarget {
+    type Context = Context;
+    type World = World;
+    type Entity = Entity;
+    fn generate_node(
+        tree_name: &str,
+        tree_index: i32,
+        tree_depth: i32,
+        node_name: &str,
+        node_index: i32,
+        bb_map: &BlackBoardMap,
+        meta_map: Option<&HashMap<String, String>>,
+        bb_ref_map: Option<&HashMap<String, String>>,
+        dyn_ref_map: Option<&HashMap<String, String>>,
+    ) -> Result<Executor<Self::Context, Self::World, Self::Entity>, BehaviorError> {
+        generate_node!(
+            tree_name, tree_index, tree_depth, node_name, node_index;
+            bb_map, meta_map, bb_ref_map, dyn_ref_map;
+            target;
+            speed;
+            ;
+        )
+    }
+}
+
+#[cfg(feature = "gen_editor_data")]
+impl behavior::EditorNodeDataGenerator for MoveToTarget {
+    fn generate_editor_node_data() -> serde_json::Value {
+        serde_json::Value::Null
+    }
+}
+
+impl BtNode for MoveToTarget {
+    type Context = Context;
+    type World = World;
+    type Entity = Entity;
+    fn tick(&mut self, _ctx: &mut Context, world: &mut World, entity: &Entity) -> Status {
+        let position = world.positions[entity.0 as usize];
+        let target = self.target.get();
+        let mut next = position;
+        for _ in 0..32 {
+            next += (target - next).clamp(-self.speed.get(), self.speed.get()) * 0.1;
+        }
+        world.commands.push((entity.0, next));
+        if (next - target).abs() < f32::EPSILON {
+            self.target.set(-target);
+            Status::Success
+        } else {
+            Status::Running
+        }
+    }
+}
+
+fn create_manager() -> BtManager<Context, World, Entity> {
+    let bench_tree_json_str = r#"
+    {
+        "tree_blackboard": [
+            {
+                "bb_name": "target",
+                "bb_type": "f32",
+                "bb_value": "100.0"
+            }
+        ],
+        "tree_structure": {
+            "Sequence": [
+                1,
+                [
+                    {
+                        "AlwaysSuccess": 2
+                    },
+                    {
+                        "Action": [
+                            3,
+                            {
+                                "name": "MoveToTarget",
+                                "meta_map": {
+                                    "speed": "2.0"
+                                },
+                                "bb_ref_map": {
+                                    "target": "target"
+                                }
+                            }
+                        ]
+                    }
+                ]
+            ]
+        }
+    }"#;
+    let mut bt_factory = BtFactory::<Context, World, Entity>::new();
+    bt_factory.register_tree_node::<MoveToTarget>();
+    bt_factory
+        .compile_tree_template_from_json_str("bench_tree", bench_tree_json_str)
+        .unwrap();
+    let mut manager = BtManager::new(bt_factory);
+    for entity in 0..INSTANCE_COUNT {
+        manager.spawn(Entity(entity), "bench_tree").unwrap();
+    }
+    manager
+}
+
+fn apply_commands(positions: &mut Arc<Vec<f32>>, commands: &[(u64, f32)]) {
+    let positions = Arc::make_mut(positions);
+    for (entity, position) in commands {
+        positions[*entity as usize] = *position;
+    }
+}
+
+fn batch_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_tick");
+    group.sample_size(20);
+
+    let mut manager = create_manager();
+    let mut positions = Arc::new(vec![0.0; INSTANCE_COUNT as usize]);
+    group.bench_function("serial", |b| {
+        b.iter(|| {
+            let mut world = World {
+                positions: positions.clone(),
+                commands: Vec::new(),
+            };
+            manager.tick_all(&mut world);
+            drop(world.positions);
+            apply_commands(&mut positions, &world.commands);
+        })
+    });
+
+    let mut worker_counts = vec![2, 4, available_worker_count()];
+    worker_counts.sort();
+    worker_counts.dedup();
+    for worker_count in worker_counts {
+        let mut manager = create_manager();
+        let mut positions = Arc::new(vec![0.0; INSTANCE_COUNT as usize]);
+        group.bench_with_input(
+            BenchmarkId::new("parallel", worker_count),
+            &worker_count,
+            |b, &worker_count| {
+                b.iter(|| {
+                    let worlds = manager.tick_all_parallel(worker_count, |_| World {
+                        positions: positions.clone(),
+                        commands: Vec::new(),
+                    });
+                    let commands: Vec<_> = worlds
+                        .into_iter()
+                        .flat_map(|world| world.commands)
+                        .collect();
+                    apply_commands(&mut positions, &commands);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, batch_tick);
+criterion_main!(benches);
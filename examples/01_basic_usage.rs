@@ -64,17 +64,17 @@ impl BtNode for BtActNodeExample {
             "-----------------------------(BtNodeExample::tick start)-----------------------------"
         );
         self.tick_count = self.tick_count.wrapping_add(1);
-        *self.bb_data1 += self.meta_data1.get();
-        *self.bb_data2 += self.meta_data2.get();
+        *self.bb_data1.as_mut() += self.meta_data1.get();
+        *self.bb_data2.as_mut() += self.meta_data2.get();
         if self.dyn_data1.is_mutable() {
-            *self.dyn_data1 = *self.bb_data1;
+            self.dyn_data1.set(self.bb_data1.get());
         } else {
-            *self.bb_data1 = self.dyn_data1.get();
+            self.bb_data1.set(self.dyn_data1.get());
         }
         if self.dyn_data2.is_mutable() {
-            *self.dyn_data2 = *self.bb_data2;
+            self.dyn_data2.set(self.bb_data2.get());
         } else {
-            *self.bb_data2 = self.dyn_data2.get();
+            self.bb_data2.set(self.dyn_data2.get());
         }
         ftlog::info!("bb_data1={}", self.bb_data1.get());
         ftlog::info!("bb_data2={}", self.bb_data2.get());
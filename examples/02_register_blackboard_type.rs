@@ -68,29 +68,29 @@ impl BtNode for BtActNodeExample {
         );
         self.tick_count += 1;
         if self.bb_data1.is_none() {
-            *self.bb_data1 = Some(Entity(100));
+            self.bb_data1.set(Some(Entity(100)));
         } else {
             self.bb_data1.unwrap_mut().0 += 100;
         }
         ftlog::info!("bb_data1={:?}", self.bb_data1.as_ref());
 
-        if self.bb_data2.len() < 10 {
-            self.bb_data2.push_back(self.tick_count);
+        if self.bb_data2.as_ref().len() < 10 {
+            self.bb_data2.as_mut().push_back(self.tick_count);
         }
         ftlog::info!("bb_data2={:?}", self.bb_data2.as_ref());
 
-        *self.bb_data3 += self.meta_data1.get();
-        *self.bb_data4 += self.meta_data2.get();
+        *self.bb_data3.as_mut() += self.meta_data1.get();
+        *self.bb_data4.as_mut() += self.meta_data2.get();
         ftlog::info!("meta_data1={:?}", self.meta_data1.get());
         ftlog::info!("meta_data2={:?}", self.meta_data2.as_ref());
         ftlog::info!("bb_data3={:?}", self.bb_data3.as_ref());
         ftlog::info!("bb_data4={:?}", self.bb_data4.as_mut());
 
         if self.dyn_data1.is_mutable() {
-            *self.dyn_data1 += 1111;
+            *self.dyn_data1.as_mut() += 1111;
         }
         if self.dyn_data2.is_mutable() {
-            *self.dyn_data2 += 1111;
+            *self.dyn_data2.as_mut() += 1111;
         }
         ftlog::info!("dyn_data1={:?}", self.dyn_data1.as_ref());
         ftlog::info!("dyn_data2={:?}", self.dyn_data2.as_mut());
@@ -78,18 +78,18 @@ impl BtNode for BtActNodeExample {
     type Entity = Entity;
     fn tick(&mut self, _ctx: &mut Context, _world: &mut World, _entity: &Entity) -> Status {
         if self.bb_data1.is_none() {
-            *self.bb_data1 = Some(Entity(self.meta_data2.get() as u64));
+            self.bb_data1.set(Some(Entity(self.meta_data2.get() as u64)));
             self.bb_data1
                 .set(Some(Entity(self.meta_data3.get() as u64)));
-            let target_entity = self.bb_data1.unwrap_mut();
+            let mut target_entity = self.bb_data1.unwrap_mut();
             *target_entity = Entity(300);
-            if self.bb_data2.is_empty() {
-                self.bb_data2.push_back(self.meta_data1.get() as i32);
+            if self.bb_data2.as_ref().is_empty() {
+                self.bb_data2.as_mut().push_back(self.meta_data1.get() as i32);
             }
         }
         // (*self.bb_data2).push_back(100);
-        *self.bb_data3 += 1;
-        *self.bb_data4 += 1.0;
+        *self.bb_data3.as_mut() += 1;
+        *self.bb_data4.as_mut() += 1.0;
         self.node_data1 += 10.0;
         self.node_data2 += 10;
         Status::Success
@@ -71,10 +71,10 @@ impl BtNode for BtActNodeExample {
         ftlog::info!(
             "-----------------------------(BtNodeExample::tick start)-----------------------------"
         );
-        *self.bb_data1 += 10;
-        ftlog::info!("bb_data1={}", *self.bb_data1);
-        *self.bb_data2 += 10.0;
-        ftlog::info!("bb_data2={}", *self.bb_data2);
+        *self.bb_data1.as_mut() += 10;
+        ftlog::info!("bb_data1={}", self.bb_data1.get());
+        *self.bb_data2.as_mut() += 10.0;
+        ftlog::info!("bb_data2={}", self.bb_data2.get());
         Status::Success
     }
 }